@@ -0,0 +1,73 @@
+// Board と User を複製して探索する場合と、CompactState の apply/undo で探索する場合の速さを比べる
+//
+//   cargo run --release --example state_benchmark [深さ]
+use splendor_clone::game::board::Board;
+use splendor_clone::game::compact::CompactState;
+use splendor_clone::game::game_command::{GameCommand, NUMBER_OF_COMMANDS};
+use splendor_clone::game::turn::Turn;
+use splendor_clone::game::user::User;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::env;
+use std::time::Instant;
+
+const DEFAULT_DEPTH: u32 = 3;
+
+// 深さ depth までの合法手を全て辿り、末端の局面数を数える
+fn perft_clone(users: &[User], board: &Board, seat: usize, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let mut nodes = 0;
+    for input in 0..NUMBER_OF_COMMANDS {
+        let mut users = users.to_vec();
+        let mut board = board.clone();
+        let command = GameCommand::to_command(input);
+        if Turn::play(command, &mut users[seat], &mut board).is_ok() {
+            nodes += perft_clone(&users, &board, (seat + 1) % users.len(), depth - 1);
+        }
+    }
+    nodes
+}
+
+fn perft_compact(state: &mut CompactState, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let mut nodes = 0;
+    for action in 0..NUMBER_OF_COMMANDS {
+        if let Some(undo) = state.apply(action) {
+            nodes += perft_compact(state, depth - 1);
+            state.undo(undo);
+        }
+    }
+    nodes
+}
+
+fn main() {
+    let depth = env::args()
+        .nth(1)
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(DEFAULT_DEPTH);
+    let board = Board::with_rng(&mut StdRng::seed_from_u64(0));
+    let users = vec![User::new(0), User::new(1)];
+
+    let start = Instant::now();
+    let clone_nodes = perft_clone(&users, &board, 0, depth);
+    let clone_time = start.elapsed();
+
+    let mut state = CompactState::from_game(&users, &board, 0);
+    let start = Instant::now();
+    let compact_nodes = perft_compact(&mut state, depth);
+    let compact_time = start.elapsed();
+
+    assert_eq!(clone_nodes, compact_nodes);
+    println!("深さ {}: {} 局面", depth, clone_nodes);
+    println!("Board/User の複製:      {:?}", clone_time);
+    println!("CompactState apply/undo: {:?}", compact_time);
+    println!(
+        "速度比: {:.1} 倍",
+        clone_time.as_secs_f64() / compact_time.as_secs_f64()
+    );
+}
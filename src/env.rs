@@ -0,0 +1,263 @@
+use crate::game::agent::Agent;
+use crate::game::board::Board;
+use crate::game::encoder::Encoder;
+use crate::game::game_command::{GameCommand, NUMBER_OF_COMMANDS};
+use crate::game::observation::Observation;
+use crate::game::turn::{Decision, Phase, Turn};
+use crate::game::user::User;
+use crate::game::{Game, GameResult, VP_TO_END};
+
+use ndarray::Array1;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+pub mod ffi;
+
+// 強化学習用の環境
+// 学習するエージェントは seat の席に座り、それ以外の席は opponents が順に埋める
+pub struct Env {
+    board: Board,
+    users: Vec<User>,
+    agents: Vec<Option<Box<dyn Agent>>>,
+    seat: usize,
+    turn: Turn,
+    result: Option<GameResult>,
+    reward_shaping: RewardShaping,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RewardShaping {
+    // 終局時に勝ち 1.0, 負け -1.0, 引き分け 0.0
+    WinLoss,
+    // WinLoss に加えて、毎手番に増えた勝利点 / VP_TO_END
+    VpDelta,
+}
+
+#[derive(Clone, Debug)]
+pub struct Info {
+    pub legal: bool,
+    pub turn: u32,
+    pub vps: Vec<u8>,
+    pub winner: Option<u8>,
+}
+
+impl Env {
+    pub fn new(opponents: Vec<Box<dyn Agent>>) -> Env {
+        let mut agents: Vec<Option<Box<dyn Agent>>> = vec![None];
+        agents.extend(opponents.into_iter().map(Some));
+        let number_of_users = agents.len();
+        let mut env = Env {
+            board: Board::new(),
+            users: (0..number_of_users as u8).map(User::new).collect(),
+            agents,
+            seat: 0,
+            turn: Turn::new(number_of_users),
+            result: None,
+            reward_shaping: RewardShaping::WinLoss,
+        };
+        env.reset(rand::random());
+        env
+    }
+
+    // 学習するエージェントの席を変える (席は 0 から)
+    // 前の席のまま進めた局面が残らないよう、新しいエピソードを始める
+    pub fn seat(mut self, seat: usize) -> Env {
+        let learner = self.agents.remove(self.seat);
+        self.seat = seat.min(self.agents.len());
+        self.agents.insert(self.seat, learner);
+        self.reset(rand::random());
+        self
+    }
+
+    // 学習するエージェントの席
+    pub fn get_seat(&self) -> usize {
+        self.seat
+    }
+
+    pub fn reward_shaping(mut self, reward_shaping: RewardShaping) -> Env {
+        self.reward_shaping = reward_shaping;
+        self
+    }
+
+    // 学習するエージェントの席はそのままで、最初の手番まで進めた局面を返す
+    pub fn reset(&mut self, seed: u64) -> Array1<f32> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        self.board = Board::with_rng(&mut rng);
+        self.users = (0..self.agents.len() as u8).map(User::new).collect();
+        for (index, agent) in self.agents.iter_mut().enumerate() {
+            if let Some(agent) = agent {
+                agent.seed(seed.wrapping_add(index as u64 + 1));
+            }
+        }
+        self.turn = Turn::new(self.agents.len());
+        self.result = None;
+
+        self.advance();
+        self.observation()
+    }
+
+    // 不正なコマンドは状態を変えずに legal = false を返す
+    // (Turn には渡さないので、失敗が続いても降参にはならない)
+    pub fn step(&mut self, action: u8) -> (Array1<f32>, f32, bool, Info) {
+        if self.result.is_some() {
+            return (self.observation(), 0.0, true, self.info(true));
+        }
+        if action >= NUMBER_OF_COMMANDS || self.legal_action_mask()[action as usize] == 0.0 {
+            return (self.observation(), 0.0, false, self.info(false));
+        }
+
+        let vp = self.users[self.seat].get_vp();
+        let command = GameCommand::to_command(action);
+        let _ = self
+            .turn
+            .decide(Decision::Command(command), &mut self.users, &mut self.board);
+        self.advance();
+        let gained = self.users[self.seat].get_vp() - vp;
+
+        let mut reward = match self.reward_shaping {
+            RewardShaping::WinLoss => 0.0,
+            RewardShaping::VpDelta => gained as f32 / VP_TO_END as f32,
+        };
+        if let Some(result) = &self.result {
+            reward += match result.winner {
+                Some(winner) if winner as usize == self.seat => 1.0,
+                Some(_) => -1.0,
+                None => 0.0,
+            };
+        }
+
+        (
+            self.observation(),
+            reward,
+            self.result.is_some(),
+            self.info(true),
+        )
+    }
+
+    pub fn observation(&self) -> Array1<f32> {
+        let observation =
+            Observation::new(&self.users, &self.board, self.seat).turn(self.turn.get_turn());
+        Encoder::encode_observation(&observation)
+    }
+
+    pub fn legal_action_mask(&self) -> Array1<f32> {
+        Encoder::legal_action_mask(&self.users[self.seat], &self.board)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.result.is_some()
+    }
+
+    fn info(&self, legal: bool) -> Info {
+        Info {
+            legal,
+            turn: self.turn.get_turn(),
+            vps: self.users.iter().map(|user| user.get_vp()).collect(),
+            winner: self.result.as_ref().and_then(|result| result.winner),
+        }
+    }
+
+    // 学習するエージェントがコマンドを選ぶ番が来るか、ゲームが終わるまで相手を進める
+    // 学習するエージェントのトークンを捨てる、貴族タイルを選ぶ段階は既定の選び方で決める
+    fn advance(&mut self) {
+        while let Some((seat, phase)) = self.turn.pending() {
+            let phase = phase.clone();
+            if seat == self.seat {
+                if phase != Phase::Main {
+                    let decision = self.turn.default_decision(&self.users).unwrap();
+                    let _ = self.turn.decide(decision, &mut self.users, &mut self.board);
+                    continue;
+                }
+                if self.legal_action_mask().iter().any(|v| *v > 0.0) {
+                    break;
+                }
+                // 実行できるコマンドがなければ降参とする
+                self.turn.surrender(&self.users);
+                continue;
+            }
+
+            let observation =
+                Observation::new(&self.users, &self.board, seat).turn(self.turn.get_turn());
+            let agent = self.agents[seat].as_mut().unwrap();
+            if let Some(decision) = agent.decide(&observation, &phase) {
+                let _ = self.turn.decide(decision, &mut self.users, &mut self.board);
+            }
+        }
+
+        // 起きたことは使わないので捨てる
+        self.turn.take_events();
+        if self.turn.is_over() {
+            self.result = Some(Game::result(
+                &self.users,
+                self.turn.get_surrendered(),
+                self.turn.get_turn(),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Env, RewardShaping};
+    use crate::game::agent::{Agent, HeuristicAgent, RandomAgent};
+    use crate::game::encoder::NUMBER_OF_FEATURES;
+    use crate::game::turn::Phase;
+
+    fn opponents() -> Vec<Box<dyn Agent>> {
+        vec![Box::new(RandomAgent::new())]
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut env = Env::new(opponents());
+        let first = env.reset(42);
+        let second = env.reset(42);
+        assert_eq!(first.len(), NUMBER_OF_FEATURES);
+        assert_eq!(first, second);
+        assert!(!env.is_done());
+
+        // 席を変えても、reset の後は学習するエージェントの手番から始まる
+        let mut env = Env::new(opponents()).seat(1);
+        env.reset(42);
+        assert_eq!(env.get_seat(), 1);
+        assert_eq!(env.turn.pending(), Some((1, &Phase::Main)));
+        assert_eq!(env.step(0).3.turn, 2);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut env = Env::new(opponents());
+        env.reset(1);
+
+        // 手札がないので手札のカードは購入できない
+        let (_, reward, done, info) = env.step(42);
+        assert!(!info.legal);
+        assert!(!done);
+        assert_eq!(reward, 0.0);
+
+        let (_, _, _, info) = env.step(0);
+        assert!(info.legal);
+        assert_eq!(info.turn, 2);
+    }
+
+    #[test]
+    fn test_episode() {
+        let opponents: Vec<Box<dyn Agent>> = vec![Box::new(HeuristicAgent::new())];
+        let mut env = Env::new(opponents)
+            .seat(1)
+            .reward_shaping(RewardShaping::VpDelta);
+        env.reset(7);
+
+        let mut done = false;
+        let mut reward = 0.0;
+        while !done {
+            let mask = env.legal_action_mask();
+            let action = mask.iter().position(|v| *v > 0.0).unwrap();
+            let (_, r, d, info) = env.step(action as u8);
+            assert!(info.legal);
+            reward = r;
+            done = d;
+        }
+        assert!((-1.0..=2.0).contains(&reward));
+    }
+}
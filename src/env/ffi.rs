@@ -0,0 +1,229 @@
+//! Python などから ctypes で Env を操作するための C ABI
+//!
+//! ```python
+//! import ctypes
+//! lib = ctypes.CDLL("target/release/libsplendor_clone.so")
+//! lib.splendor_env_new.restype = ctypes.c_void_p
+//! lib.splendor_env_new.argtypes = [ctypes.c_uint32, ctypes.c_uint32, ctypes.c_uint32]
+//! env = ctypes.c_void_p(lib.splendor_env_new(1, 1, 0))
+//! observation = (ctypes.c_float * lib.splendor_env_observation_size())()
+//! lib.splendor_env_reset(env, ctypes.c_uint64(0), observation)
+//! ```
+//!
+//! カードと貴族タイルは data/ から読み込むため、リポジトリの直下で実行すること
+//!
+//! Rust の panic は呼び出し元まで巻き戻さず、NULL か -2 を返す
+//! -2 を返した Env は splendor_env_free 以外に使わないこと
+
+use super::{Env, RewardShaping};
+use crate::game::agent::{Agent, HeuristicAgent, RandomAgent};
+use crate::game::encoder::NUMBER_OF_FEATURES;
+use crate::game::game_command::NUMBER_OF_COMMANDS;
+
+use ndarray::Array1;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+const OPPONENT_RANDOM: u32 = 0;
+const OPPONENT_HEURISTIC: u32 = 1;
+const REWARD_WIN_LOSS: u32 = 0;
+const REWARD_VP_DELTA: u32 = 1;
+
+const OK: i32 = 0;
+const ILLEGAL_ACTION: i32 = 1;
+const NULL_POINTER: i32 = -1;
+const PANICKED: i32 = -2;
+
+#[no_mangle]
+pub extern "C" fn splendor_env_observation_size() -> u32 {
+    NUMBER_OF_FEATURES as u32
+}
+
+#[no_mangle]
+pub extern "C" fn splendor_env_action_size() -> u32 {
+    u32::from(NUMBER_OF_COMMANDS)
+}
+
+/// opponent は 0 = random, 1 = heuristic、reward_shaping は 0 = 勝敗のみ, 1 = 勝利点の増分
+/// 引数が不正な場合や、data/ が読み込めない場合は NULL を返す
+#[no_mangle]
+pub extern "C" fn splendor_env_new(
+    number_of_opponents: u32,
+    opponent: u32,
+    reward_shaping: u32,
+) -> *mut Env {
+    panic::catch_unwind(|| new(number_of_opponents, opponent, reward_shaping))
+        .unwrap_or(ptr::null_mut())
+}
+
+fn new(number_of_opponents: u32, opponent: u32, reward_shaping: u32) -> *mut Env {
+    let reward_shaping = match reward_shaping {
+        REWARD_WIN_LOSS => RewardShaping::WinLoss,
+        REWARD_VP_DELTA => RewardShaping::VpDelta,
+        _ => return ptr::null_mut(),
+    };
+    if number_of_opponents == 0 || number_of_opponents > 3 {
+        return ptr::null_mut();
+    }
+
+    let mut opponents: Vec<Box<dyn Agent>> = vec![];
+    for _ in 0..number_of_opponents {
+        match opponent {
+            OPPONENT_RANDOM => opponents.push(Box::new(RandomAgent::new())),
+            OPPONENT_HEURISTIC => opponents.push(Box::new(HeuristicAgent::new())),
+            _ => return ptr::null_mut(),
+        }
+    }
+
+    Box::into_raw(Box::new(Env::new(opponents).reward_shaping(reward_shaping)))
+}
+
+/// # Safety
+/// env は splendor_env_new が返したポインタで、解放後は使わないこと
+#[no_mangle]
+pub unsafe extern "C" fn splendor_env_free(env: *mut Env) {
+    if !env.is_null() {
+        guard(|| {
+            drop(Box::from_raw(env));
+            OK
+        });
+    }
+}
+
+/// observation には splendor_env_observation_size 個の float を書き込む
+///
+/// # Safety
+/// env は有効な Env、observation は十分な長さのバッファを指すこと
+#[no_mangle]
+pub unsafe extern "C" fn splendor_env_reset(
+    env: *mut Env,
+    seed: u64,
+    observation: *mut f32,
+) -> i32 {
+    let env = match env.as_mut() {
+        Some(env) => env,
+        None => return NULL_POINTER,
+    };
+    guard(|| write(&env.reset(seed), observation))
+}
+
+/// 不正なコマンドなら状態を変えずに 1 を返す
+///
+/// # Safety
+/// env は有効な Env、observation は十分な長さのバッファ、reward と done は書き込み可能な領域を指すこと
+#[no_mangle]
+pub unsafe extern "C" fn splendor_env_step(
+    env: *mut Env,
+    action: u32,
+    observation: *mut f32,
+    reward: *mut f32,
+    done: *mut i32,
+) -> i32 {
+    let env = match env.as_mut() {
+        Some(env) => env,
+        None => return NULL_POINTER,
+    };
+    if reward.is_null() || done.is_null() {
+        return NULL_POINTER;
+    }
+
+    let action = if action < u32::from(NUMBER_OF_COMMANDS) {
+        action as u8
+    } else {
+        NUMBER_OF_COMMANDS
+    };
+    guard(|| {
+        let (next, r, d, info) = env.step(action);
+        *reward = r;
+        *done = d as i32;
+        match write(&next, observation) {
+            OK if !info.legal => ILLEGAL_ACTION,
+            code => code,
+        }
+    })
+}
+
+/// mask には splendor_env_action_size 個の float を書き込む
+///
+/// # Safety
+/// env は有効な Env、mask は十分な長さのバッファを指すこと
+#[no_mangle]
+pub unsafe extern "C" fn splendor_env_legal_action_mask(env: *const Env, mask: *mut f32) -> i32 {
+    let env = match env.as_ref() {
+        Some(env) => env,
+        None => return NULL_POINTER,
+    };
+    guard(|| write(&env.legal_action_mask(), mask))
+}
+
+/// 勝者の席を返す。ゲームが終わっていないか引き分けなら -1
+///
+/// # Safety
+/// env は有効な Env を指すこと
+#[no_mangle]
+pub unsafe extern "C" fn splendor_env_winner(env: *const Env) -> i32 {
+    match env.as_ref() {
+        Some(env) => guard(|| {
+            env.result
+                .as_ref()
+                .and_then(|result| result.winner)
+                .map_or(-1, i32::from)
+        }),
+        None => NULL_POINTER,
+    }
+}
+
+// code の中の panic を捕まえて PANICKED を返す
+fn guard<F: FnOnce() -> i32>(code: F) -> i32 {
+    panic::catch_unwind(AssertUnwindSafe(code)).unwrap_or(PANICKED)
+}
+
+unsafe fn write(values: &Array1<f32>, buffer: *mut f32) -> i32 {
+    if buffer.is_null() {
+        return NULL_POINTER;
+    }
+    let buffer = slice::from_raw_parts_mut(buffer, values.len());
+    for (b, v) in buffer.iter_mut().zip(values.iter()) {
+        *b = *v;
+    }
+    OK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env() {
+        unsafe {
+            assert!(splendor_env_new(1, 9, 0).is_null());
+
+            let env = splendor_env_new(1, OPPONENT_RANDOM, REWARD_WIN_LOSS);
+            let mut observation = vec![0.0; splendor_env_observation_size() as usize];
+            let mut mask = vec![0.0; splendor_env_action_size() as usize];
+            let mut reward = 0.0;
+            let mut done = 0;
+
+            assert_eq!(splendor_env_reset(env, 3, observation.as_mut_ptr()), OK);
+            assert_eq!(splendor_env_legal_action_mask(env, mask.as_mut_ptr()), OK);
+            assert_eq!(mask[0], 1.0);
+            assert_eq!(
+                splendor_env_step(env, 0, observation.as_mut_ptr(), &mut reward, &mut done),
+                OK
+            );
+            assert_eq!(
+                splendor_env_step(env, 99, observation.as_mut_ptr(), &mut reward, &mut done),
+                ILLEGAL_ACTION
+            );
+            assert_eq!(splendor_env_winner(env), -1);
+            splendor_env_free(env);
+        }
+    }
+
+    #[test]
+    fn test_guard() {
+        assert_eq!(guard(|| OK), OK);
+        assert_eq!(guard(|| panic!("guarded")), PANICKED);
+    }
+}
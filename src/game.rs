@@ -1,74 +1,200 @@
+use self::agent::{Agent, RandomAgent};
 use self::board::Board;
-use self::game_command::GameCommand;
-use self::game_command::GameCommand::*;
+use self::game_event::GameObserver;
+use self::locale::{message, text};
+use self::observation::Observation;
+use self::renderer::Renderer;
+use self::turn::{Decision, Phase, Turn};
 use self::user::User;
 
 use std::time::Instant;
 
 pub mod action_reward_table;
+pub mod agent;
 pub mod board;
+pub mod card_catalog;
 pub mod card_stack;
+pub mod card_tracker;
 pub mod color;
-mod game_command;
+pub mod compact;
+pub mod encoder;
+pub mod game_command;
+pub mod game_event;
 pub mod gem;
 pub mod level;
+pub mod locale;
 pub mod noble_tile;
+pub mod observation;
+pub mod renderer;
 pub mod token_stack;
+pub mod turn;
 pub mod user;
+pub mod zobrist;
 
-const VP_TO_END: u8 = 15;
-const MAX_NUMBER_OF_TRIALS: u8 = 100;
+pub const VP_TO_END: u8 = 15;
+pub(crate) const MAX_NUMBER_OF_TRIALS: u8 = 100;
+pub const MAX_NUMBER_OF_TURNS: u32 = 100;
 
 pub struct Game {
     board: Board,
     users: Vec<User>,
+    agents: Vec<Box<dyn Agent>>,
+    observers: Vec<Box<dyn GameObserver>>,
+    turn: Turn,
+    renderer: Renderer,
+    verbose: bool,
+}
+
+pub struct GameResult {
+    pub winner: Option<u8>,
+    pub vps: Vec<u8>,
+    pub turns: u32,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game {
     pub fn new() -> Game {
+        Game::with_agents(vec![
+            Box::new(RandomAgent::new()),
+            Box::new(RandomAgent::new()),
+        ])
+    }
+    pub fn with_agents(agents: Vec<Box<dyn Agent>>) -> Game {
+        let game = Game::with_users(agents.len());
+        Game { agents, ..game }
+    }
+    // エージェントを持たない Game
+    // play は Err を返すので、pending と decide で外から 1手ずつ進める
+    pub fn with_users(number_of_users: usize) -> Game {
         Game {
             board: Board::new(),
-            users: vec![User::new(0), User::new(1)],
+            users: (0..number_of_users as u8).map(User::new).collect(),
+            agents: vec![],
+            observers: vec![],
+            turn: Turn::new(number_of_users),
+            renderer: Renderer::new(),
+            verbose: true,
         }
     }
-    pub fn play(&mut self) {
-        let mut turn = 1;
+    pub fn quiet(mut self) -> Game {
+        self.verbose = false;
+        self
+    }
+    // verbose のときに局面を出す Renderer (色をつけるかなど)
+    pub fn renderer(mut self, renderer: Renderer) -> Game {
+        self.renderer = renderer;
+        self
+    }
+    pub fn observer(mut self, observer: Box<dyn GameObserver>) -> Game {
+        self.add_observer(observer);
+        self
+    }
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
+    }
+    // 席の数だけエージェントがいなければ、何もせずに Err を返す
+    pub fn play(&mut self) -> Result<GameResult, String> {
+        if self.agents.len() != self.users.len() {
+            return Err(message(
+                "game.missing_agents",
+                &[&self.agents.len(), &self.users.len()],
+            ));
+        }
+
         let mut max_duration = 0;
         let mut sum_duration = 0;
-        let mut is_over = false;
-
-        loop {
-            let start = Instant::now();
-            println!("{}手番目\n{}", turn, self.board);
+        let mut turn = self.turn.get_turn();
+        let mut start = Instant::now();
 
-            for user in self.users.iter_mut() {
-                let command = user.read();
-                match Game::eval(command, user, &mut self.board) {
-                    Ok(msg) => Game::print(&msg, &user),
-                    Err(error_msg) => is_over = true,
-                }
+        self.dispatch();
+        while let Some((seat, phase)) = self.pending() {
+            let phase = phase.clone();
+            if self.turn.get_turn() != turn {
+                let end = start.elapsed().subsec_nanos();
+                max_duration = max_duration.max(end);
+                sum_duration += end;
+                start = Instant::now();
+                turn = self.turn.get_turn();
             }
 
-            let end = start.elapsed().subsec_nanos();
-            if end > max_duration {
-                max_duration = end;
+            let observation = self.observation(seat);
+            if self.verbose && phase == Phase::Main {
+                println!(
+                    "{}{}",
+                    self.renderer.render(&observation),
+                    observation.card_tracker()
+                );
             }
-            sum_duration += end;
-
-            if Game::is_over(&self.users) || is_over {
-                break;
+            // 決められないエージェントは降参とみなし、残りのユーザーで続ける
+            let decision = match self.agents[seat].decide(&observation, &phase) {
+                Some(decision) => decision,
+                None => {
+                    self.surrender();
+                    continue;
+                }
+            };
+            if let Err(message) = self.decide(decision) {
+                if self.verbose {
+                    println!("{}", message);
+                }
             }
+        }
+        let end = start.elapsed().subsec_nanos();
+        max_duration = max_duration.max(end);
+        sum_duration += end;
 
-            turn = turn + 1;
+        let result = Game::result(&self.users, self.turn.get_surrendered(), turn);
+        if self.verbose {
+            println!("\n\n{}", text("game.over"));
+            println!("{}\n{}", message("game.turn", &[&turn]), self.board);
+            for user in self.users.iter() {
+                Game::print("", user);
+            }
+            println!("{}", message("game.max_duration", &[&max_duration]));
+            println!(
+                "{}",
+                message("game.average_duration", &[&(sum_duration / turn.max(1))])
+            );
         }
+        Ok(result)
+    }
 
-        println!("\n\nゲーム終了:");
-        println!("{}手番目\n{}", turn, self.board);
-        for user in self.users.iter() {
-            Game::print(&"", &user);
+    // 決める番のユーザーと決めること (終わっていれば None)
+    // UI やネットワーク対戦は pending を見て decide を呼び、Game を 1手ずつ進める
+    pub fn pending(&self) -> Option<(usize, &Phase)> {
+        self.turn.pending()
+    }
+    // 起きたことは登録した GameObserver に通知する
+    pub fn decide(&mut self, decision: Decision) -> Result<(), String> {
+        let result = self.turn.decide(decision, &mut self.users, &mut self.board);
+        self.dispatch();
+        result
+    }
+    pub fn surrender(&mut self) {
+        self.turn.surrender(&self.users);
+        self.dispatch();
+    }
+    pub fn get_turn(&self) -> u32 {
+        self.turn.get_turn()
+    }
+    pub fn observation(&self, seat: usize) -> Observation {
+        Observation::new(&self.users, &self.board, seat).turn(self.turn.get_turn())
+    }
+
+    fn dispatch(&mut self) {
+        for event in self.turn.take_events() {
+            if self.verbose {
+                println!("{}", event);
+            }
+            for observer in self.observers.iter_mut() {
+                observer.notify(&event);
+            }
         }
-        println!("ターン経過最大: {}ns", max_duration);
-        println!("ターン経過平均: {}ns", sum_duration / turn);
     }
 
     pub fn copy_board(&mut self) -> Board {
@@ -78,56 +204,163 @@ impl Game {
         self.users.clone()
     }
 
-    pub fn eval(input: GameCommand, user: &mut User, board: &mut Board) -> Result<String, String> {
-        let mut input = input;
-        for _ in 0..MAX_NUMBER_OF_TRIALS {
-            match Game::eval_by_selection(input, user, board) {
-                Ok(result) => {
-                    return Ok(result.to_string());
-                }
-                Err(error_msg) => {
-                    println!("{}", error_msg);
-                    input = user.read();
-                    continue;
+    pub fn print(result: &str, user: &User) {
+        println!("{}", result);
+        println!("{}", message("user.status", &[user]));
+    }
+
+    pub fn is_over(users: &[User]) -> bool {
+        users.iter().any(|user| user.get_vp() >= VP_TO_END)
+    }
+
+    // 降参していないユーザーのうち勝利点が最も高いユーザーが勝者
+    // 同点の場合は取得したカードが少ないほうが勝ち、それでも並べば引き分け
+    pub fn result(users: &[User], surrendered: &[usize], turns: u32) -> GameResult {
+        let mut ranking: Vec<&User> = users
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !surrendered.contains(index))
+            .map(|(_, user)| user)
+            .collect();
+        ranking.sort_by_key(|user| {
+            (
+                std::cmp::Reverse(user.get_vp()),
+                user.get_number_of_acquired_cards(),
+            )
+        });
+
+        let winner = match ranking.as_slice() {
+            [first] => Some(first.get_id()),
+            [first, second, ..] => {
+                if first.get_vp() == second.get_vp()
+                    && first.get_number_of_acquired_cards() == second.get_number_of_acquired_cards()
+                {
+                    None
+                } else {
+                    Some(first.get_id())
                 }
             }
+            [] => None,
+        };
+
+        GameResult {
+            winner,
+            vps: users.iter().map(|user| user.get_vp()).collect(),
+            turns,
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::agent::{Agent, HeuristicAgent, RandomAgent};
+    use super::game_command::GameCommand;
+    use super::game_event::{GameEvent, GameObserver};
+    use super::observation::Observation;
+    use super::turn::{Decision, Phase};
+    use super::Game;
 
-        Err("降参を選ばれました".to_string())
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct Recorder(Rc<RefCell<Vec<GameEvent>>>);
+
+    impl GameObserver for Recorder {
+        fn notify(&mut self, event: &GameEvent) {
+            self.0.borrow_mut().push(event.clone());
+        }
     }
 
-    fn eval_by_selection(
-        input: GameCommand,
-        user: &mut User,
-        board: &mut Board,
-    ) -> Result<&'static str, &'static str> {
-        match input {
-            ReserveDevelopmentCard { x, y } => {
-                GameCommand::reserve_development_card(x, y, user, board)
-            }
-            BuyDevelopmentCard { x, y } => GameCommand::buy_development_card(x, y, user, board),
-            SelectTwoSameTokens(color) => GameCommand::select_two_same_tokens(color, user, board),
-            SelectThreeTokens(color1, color2, color3) => {
-                GameCommand::select_three_tokens(color1, color2, color3, user, board)
-            }
-            ReserveStackCard(level) => GameCommand::reserve_stack_card(level, user, board),
-            BuyReservedCard(index) => GameCommand::buy_reserved_card(index, user, board),
+    #[test]
+    fn test_play() {
+        let agents: Vec<Box<dyn Agent>> = vec![
+            Box::new(HeuristicAgent::new()),
+            Box::new(RandomAgent::new()),
+        ];
+        let result = Game::with_agents(agents).quiet().play().unwrap();
+        assert_eq!(result.vps.len(), 2);
+        assert!(result.turns >= 1);
+        if let Some(winner) = result.winner {
+            assert!(winner < 2);
         }
     }
 
-    pub fn print(result: &str, user: &User) -> () {
-        println!("{}", result);
-        println!("ユーザーステータス: {}", user);
+    // エージェントを持たない Game は play できない
+    #[test]
+    fn test_play_without_agents() {
+        let mut game = Game::with_users(2).quiet();
+        assert!(game.play().is_err());
+        assert_eq!(game.pending().map(|(seat, _)| seat), Some(0));
     }
 
-    pub fn is_over(users: &Vec<User>) -> bool {
-        let mut result = false;
-        for user in users.iter() {
-            if user.get_vp() >= VP_TO_END {
-                result = true;
-                println!("プレイヤー{}が勝利しました", user.get_id());
-            }
+    #[test]
+    fn test_observer() {
+        let events = Rc::new(RefCell::new(vec![]));
+        let agents: Vec<Box<dyn Agent>> = vec![
+            Box::new(HeuristicAgent::new()),
+            Box::new(HeuristicAgent::new()),
+        ];
+        let result = Game::with_agents(agents)
+            .quiet()
+            .observer(Box::new(Recorder(events.clone())))
+            .play()
+            .unwrap();
+
+        let events = events.borrow();
+        assert_eq!(events[0], GameEvent::TurnStarted { turn: 1, seat: 0 });
+        assert_eq!(
+            events.last(),
+            Some(&GameEvent::GameEnded {
+                winner: result.winner,
+                vps: result.vps,
+                turns: result.turns,
+            })
+        );
+        let started = events
+            .iter()
+            .filter(|event| matches!(event, GameEvent::TurnStarted { .. }))
+            .count();
+        assert!(started as u32 >= result.turns);
+    }
+
+    // 終わった Game をもう一度 play しても、同じ結果を返す
+    #[test]
+    fn test_play_twice() {
+        let mut game = Game::new().quiet();
+        let first = game.play().unwrap();
+        let second = game.play().unwrap();
+        assert_eq!(second.turns, first.turns);
+        assert_eq!(second.vps, first.vps);
+    }
+
+    struct Silent;
+
+    impl Agent for Silent {
+        fn name(&self) -> &str {
+            "silent"
         }
-        result
+        fn read(&mut self, _observation: &Observation) -> GameCommand {
+            unreachable!()
+        }
+        fn decide(&mut self, _observation: &Observation, _phase: &Phase) -> Option<Decision> {
+            None
+        }
+    }
+
+    // 決められないエージェントは降参になり、GameEnded まで通知する
+    #[test]
+    fn test_silent_agent() {
+        let events = Rc::new(RefCell::new(vec![]));
+        let agents: Vec<Box<dyn Agent>> = vec![Box::new(Silent), Box::new(RandomAgent::new())];
+        let result = Game::with_agents(agents)
+            .quiet()
+            .observer(Box::new(Recorder(events.clone())))
+            .play()
+            .unwrap();
+
+        assert_eq!(result.winner, Some(1));
+        let events = events.borrow();
+        assert!(events.contains(&GameEvent::Surrendered { seat: 0 }));
+        assert!(matches!(events.last(), Some(GameEvent::GameEnded { .. })));
     }
 }
@@ -1,33 +1,80 @@
 use std::collections::HashMap;
 
 use crate::game::board::Board;
+use crate::game::card_stack::Card;
 use crate::game::color::Color;
 use crate::game::color::Color::*;
-use crate::game::game_command::GameCommand;
 use crate::game::game_command::GameCommand::*;
-use crate::game::gem::{Gem, GEMS};
+use crate::game::game_command::{GameCommand, NUMBER_OF_COMMANDS};
+use crate::game::gem::GEMS;
 use crate::game::user::User;
+use crate::game::VP_TO_END;
+
+use serde_derive::{Deserialize, Serialize};
 
 mod action_reward;
 mod color_value;
+mod weights;
 
 pub struct ActionReward {
     action: GameCommand,
     reward: f32,
 }
 pub struct ColorValue(HashMap<Color, f32>);
-pub struct ActionRewardTable(Vec<ActionReward>);
+pub struct ActionRewardTable {
+    rewards: Vec<ActionReward>,
+    weights: Weights,
+}
+
+// 色の価値と各コマンドの報酬を決める重み
+// 項目が欠けた重みファイルは既定値で補う
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Weights {
+    pub base_value: f32,
+    pub scarcity: f32,
+    pub reserve_card: f32,
+    pub reserve_stack_card: f32,
+    pub buy_point: f32,
+    pub buy_color: f32,
+    pub two_same_tokens: f32,
+    pub three_tokens: f32,
+    pub noble_progress: f32,
+    pub reserve_denial: f32,
+    pub endgame: f32,
+}
+
+impl Default for ActionRewardTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl ActionRewardTable {
     pub fn new() -> ActionRewardTable {
-        ActionRewardTable(vec![])
+        ActionRewardTable::with_weights(Weights::default())
     }
 
-    pub fn look(&mut self, step: u8, users: &mut Vec<User>, board: &mut Board) -> GameCommand {
-        let mut user = User::new(1);
-        let color_value = self.calc_color_value(&user, board);
+    pub fn with_weights(weights: Weights) -> ActionRewardTable {
+        ActionRewardTable {
+            rewards: vec![],
+            weights,
+        }
+    }
 
-        self.estimate(1, &mut user, board, &color_value);
+    pub fn get_weights(&self) -> &Weights {
+        &self.weights
+    }
+
+    pub fn look(&mut self, user: &User, users: &[User], board: &Board) -> GameCommand {
+        self.rewards.clear();
+        let color_value = self.calc_color_value(user, board);
+        let opponents: Vec<&User> = users
+            .iter()
+            .filter(|opponent| opponent.get_id() != user.get_id())
+            .collect();
+
+        self.estimate(user, &opponents, board, &color_value);
         self.choice()
     }
 
@@ -37,124 +84,143 @@ impl ActionRewardTable {
         let required_cost = board.get_required_cost();
         let owned = user.get_owned_gems();
 
-        // 基礎点 = 0.3
-        // α = 1 - 所持宝石数 / 盤面の必要な宝石数
+        // 基礎点 = base_value (既定値 0.3)
+        // α = 1 - scarcity * 所持宝石数 / 盤面の必要な宝石数
+        // 盤面でその色が必要とされていなければ、希少さは考えない (α = 1)
         for color in GEMS.iter() {
-            color_value.set(
-                *color,
-                0.3 * (1.0 - owned.get(*color) as f32 / required_cost.get(*color) as f32),
-            );
+            let required = required_cost.get(*color);
+            let scarcity = if required == 0 {
+                0.0
+            } else {
+                self.weights.scarcity * owned.get(*color) as f32 / required as f32
+            };
+            color_value.set(*color, self.weights.base_value * (1.0 - scarcity));
         }
         color_value.set_gold_value();
         color_value
     }
 
+    // 盤面とユーザーを複製して各コマンドを試行するため、元の状態は変更しない
     pub fn estimate(
         &mut self,
-        step: u8,
-        user: &mut User,
-        board: &mut Board,
+        user: &User,
+        opponents: &[&User],
+        board: &Board,
         color_value: &ColorValue,
     ) {
-        for input in 0..45 {
+        for input in 0..NUMBER_OF_COMMANDS {
             let command = GameCommand::to_command(input);
-            match command {
-                ReserveDevelopmentCard { x, y } => {
-                    let output = GameCommand::reserve_development_card(x, y, user, board);
-                    match output {
-                        Ok(_) => self
-                            .0
-                            .push(ActionReward::new(command, color_value.get(Gold))),
-                        Err(_) => (),
-                    };
-                }
-                BuyDevelopmentCard { x, y } => {
-                    let output = GameCommand::buy_development_card(x, y, user, board);
-                    match output {
-                        Ok(_) => match user.get_acquired_cards().as_slice().last() {
-                            Some(card) => self.0.push(ActionReward::new(
-                                command,
-                                card.get_point() as f32 + color_value.get(card.get_color()),
-                            )),
-                            None => (),
-                        },
-                        Err(_) => (),
-                    };
-                }
-                SelectTwoSameTokens(c) => {
-                    let result = GameCommand::select_two_same_tokens(c, user, board);
-                    match result {
-                        Ok(_) => self
-                            .0
-                            .push(ActionReward::new(command, 2.0 * color_value.get(c))),
-                        Err(_) => (),
-                    };
-                }
-                SelectThreeTokens(c1, c2, c3) => {
-                    let t1 = user.get_number_of_tokens(c1);
-                    let t2 = user.get_number_of_tokens(c2);
-                    let t3 = user.get_number_of_tokens(c3);
-
-                    let result = GameCommand::select_three_tokens(c1, c2, c3, user, board);
+            if let Some(reward) = self.reward(&command, user, opponents, board, color_value) {
+                self.rewards.push(ActionReward::new(command, reward));
+            }
+        }
+    }
 
-                    let t1 = user.get_number_of_tokens(c1) - t1;
-                    let t2 = user.get_number_of_tokens(c2) - t2;
-                    let t3 = user.get_number_of_tokens(c3) - t3;
+    fn reward(
+        &self,
+        command: &GameCommand,
+        user: &User,
+        opponents: &[&User],
+        board: &Board,
+        color_value: &ColorValue,
+    ) -> Option<f32> {
+        let mut next_user = user.clone();
+        let mut next_board = board.clone();
+        let (next_user, next_board) = (&mut next_user, &mut next_board);
 
-                    let mut total = 0.0;
+        match *command {
+            ReserveDevelopmentCard { x, y } => {
+                let card = board.peek_card(x, y)?;
+                GameCommand::reserve_development_card(x, y, next_user, next_board).ok()?;
+                Some(
+                    self.weights.reserve_card * color_value.get(Gold)
+                        + self.reserve_denial(card, opponents),
+                )
+            }
+            BuyDevelopmentCard { x, y } => {
+                GameCommand::buy_development_card(x, y, next_user, next_board).ok()?;
+                let card = next_user.get_acquired_cards().last()?;
+                Some(self.buy_reward(card, user, next_user, board, color_value))
+            }
+            SelectTwoSameTokens(c) => {
+                GameCommand::select_two_same_tokens(c, next_user, next_board).ok()?;
+                Some(self.weights.two_same_tokens * color_value.get(c))
+            }
+            SelectThreeTokens(c1, c2, c3) => {
+                GameCommand::select_three_tokens(c1, c2, c3, next_user, next_board).ok()?;
 
-                    if t1 > 0 {
-                        total += color_value.get(c1);
-                    }
-                    if t2 > 0 {
-                        total += color_value.get(c2);
-                    }
-                    if t3 > 0 {
-                        total += color_value.get(c3);
+                let mut total = 0.0;
+                for c in [c1, c2, c3].iter() {
+                    if next_user.get_number_of_tokens(*c) > user.get_number_of_tokens(*c) {
+                        total += color_value.get(*c);
                     }
-
-                    match result {
-                        Ok(_) => self.0.push(ActionReward::new(command, total)),
-                        Err(_) => (),
-                    };
-                }
-                ReserveStackCard(l) => {
-                    let result = GameCommand::reserve_stack_card(l, user, board);
-                    match result {
-                        Ok(_) => self.0.push(ActionReward::new(command, 0.0)),
-                        Err(_) => (),
-                    };
-                }
-                BuyReservedCard(index) => {
-                    let output = GameCommand::buy_reserved_card(index, user, board);
-                    match output {
-                        Ok(_) => match user.get_acquired_cards().as_slice().last() {
-                            Some(card) => self.0.push(ActionReward::new(
-                                command,
-                                card.get_point() as f32 + color_value.get(card.get_color()),
-                            )),
-                            None => (),
-                        },
-                        Err(_) => (),
-                    };
                 }
+                Some(self.weights.three_tokens * total)
+            }
+            ReserveStackCard(l) => {
+                GameCommand::reserve_stack_card(l, next_user, next_board).ok()?;
+                Some(self.weights.reserve_stack_card)
+            }
+            BuyReservedCard(index) => {
+                GameCommand::buy_reserved_card(index, next_user, next_board).ok()?;
+                let card = next_user.get_acquired_cards().last()?;
+                Some(self.buy_reward(card, user, next_user, board, color_value))
             }
         }
     }
 
+    fn buy_reward(
+        &self,
+        card: &Card,
+        user: &User,
+        next_user: &User,
+        board: &Board,
+        color_value: &ColorValue,
+    ) -> f32 {
+        self.weights.buy_point * card.get_point() as f32
+            + self.weights.buy_color * color_value.get(card.get_color())
+            + self.noble_progress(card.get_color(), user, board)
+            + self.endgame(user, next_user)
+    }
+
+    // 貴族タイルごとに、その色が足りていれば 勝利点 / 残り必要数 を加算する
+    fn noble_progress(&self, color: Color, user: &User, board: &Board) -> f32 {
+        let jewelries = user.get_jewelries();
+        let mut progress = 0.0;
+        for tile in board.peek_noble_tile().iter() {
+            if tile.get_bonus(color) <= jewelries.get(color) {
+                continue;
+            }
+            let remaining = jewelries.shortfall(tile.get_required());
+            progress += tile.get_point() as f32 / remaining as f32;
+        }
+        self.weights.noble_progress * progress
+    }
+
+    // 次の手番で相手が購入できるカードを確保すると、そのカードの価値だけ加算する
+    fn reserve_denial(&self, card: &Card, opponents: &[&User]) -> f32 {
+        if opponents.iter().any(|opponent| card.is_available(opponent)) {
+            self.weights.reserve_denial * (card.get_point() as f32 + 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    // 得た勝利点(貴族の訪問を含む)を、終了条件に近いほど重く評価する
+    fn endgame(&self, user: &User, next_user: &User) -> f32 {
+        let gained = next_user.get_vp().saturating_sub(user.get_vp()) as f32;
+        let progress = (next_user.get_vp() as f32 / VP_TO_END as f32).min(1.0);
+        self.weights.endgame * gained * progress
+    }
+
     fn choice(&self) -> GameCommand {
         let mut max_value = 0.0;
         let mut command = GameCommand::ReserveDevelopmentCard { x: 0, y: 0 };
 
-        for e in self.0.iter() {
-            // println!("{:?}", e);
-            match e {
-                ActionReward { action, reward } => {
-                    if *reward > max_value {
-                        command = action.clone();
-                        max_value = *reward;
-                    }
-                }
+        for ActionReward { action, reward } in self.rewards.iter() {
+            if *reward > max_value {
+                command = action.clone();
+                max_value = *reward;
             }
         }
 
@@ -166,44 +232,118 @@ impl ActionRewardTable {
 mod tests {
     use super::ActionRewardTable;
     use super::ColorValue;
-    use crate::game::board::Board;
-    use crate::game::user::User;
-    use crate::game::Game;
-
+    use crate::game::board::{Board, COORDINATE};
     use crate::game::card_stack::Card;
     use crate::game::color::Color::*;
-    use crate::game::token_stack::{Token, TokenStack};
+    use crate::game::gem::{Gem, GEMS};
+    use crate::game::level::Level;
+    use crate::game::token_stack::Token;
+    use crate::game::turn::Turn;
+    use crate::game::user::User;
 
     #[test]
     fn test_calc_color_value() {
-        let mut game = Game::new();
-        let mut board = game.copy_board();
-        // let mut users = game.copy_users();
+        let board = Board::new();
+        let user = User::new(1);
+        let mut table = ActionRewardTable::new();
+
+        table.calc_color_value(&user, &board);
+    }
+
+    // 場のカードがなく必要な宝石が 0 でも、色の価値は有限
+    #[test]
+    fn test_color_value_without_cards() {
+        let mut board = Board::new();
+        for level in [Level::One, Level::Two, Level::Three].iter() {
+            while board.get_stack_card(*level).is_some() {}
+        }
+        for (x, y) in COORDINATE.iter() {
+            board.get_card(*x, *y);
+        }
         let mut user = User::new(1);
+        user.add_token(Token::new(Red));
         let mut table = ActionRewardTable::new();
 
-        table.calc_color_value(&mut user, &mut board);
+        let color_value = table.calc_color_value(&user, &board);
+        for color in [Black, White, Red, Blue, Green, Gold].iter() {
+            assert!(color_value.get(*color).is_finite());
+        }
+        assert_eq!(color_value.get(Red), table.weights.base_value);
     }
 
     #[test]
     fn test_estimate() {
-        let mut game = Game::new();
-        let mut board = game.copy_board();
-        // let mut users = game.copy_users();
-        let mut user = User::new(1);
+        let board = Board::new();
+        let user = User::new(1);
         let mut table = ActionRewardTable::new();
-        let mut color_value = ColorValue::new();
+        let color_value = ColorValue::new();
 
-        table.estimate(1, &mut user, &mut board, &color_value);
+        table.estimate(&user, &[], &board, &color_value);
+        assert!(!table.rewards.is_empty());
+        assert_eq!(user.get_number_of_hands(), 0);
     }
 
     #[test]
     fn test_choise() {
-        let mut game = Game::new();
-        let mut board = game.copy_board();
-        let mut users = game.copy_users();
-        let mut table = ActionRewardTable::new();
+        let table = ActionRewardTable::new();
 
         table.choice();
     }
+
+    #[test]
+    fn test_look() {
+        let board = Board::new();
+        let users = vec![User::new(0), User::new(1)];
+        let mut table = ActionRewardTable::new();
+
+        let mut expected_user = users[0].clone();
+        let mut expected_board = board.clone();
+        let command = table.look(&users[0], &users, &board);
+        assert!(Turn::play(command, &mut expected_user, &mut expected_board).is_ok());
+    }
+
+    #[test]
+    fn test_noble_progress() {
+        let board = Board::new();
+        let user = User::new(1);
+        let table = ActionRewardTable::new();
+
+        let tile = &board.peek_noble_tile()[0];
+        for color in GEMS.iter() {
+            let progress = table.noble_progress(*color, &user, &board);
+            if tile.get_bonus(*color) > 0 {
+                assert!(progress > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reserve_denial() {
+        let table = ActionRewardTable::new();
+        let card = Card {
+            point: 2,
+            cost: Gem::from([0, 0, 3, 0, 0]),
+            ..Default::default()
+        };
+        let poor = User::new(1);
+        let mut rich = User::new(2);
+        for _ in 0..3 {
+            rich.add_token(Token::new(Red));
+        }
+
+        assert_eq!(table.reserve_denial(&card, &[&poor]), 0.0);
+        assert_eq!(table.reserve_denial(&card, &[&poor, &rich]), 1.5);
+    }
+
+    #[test]
+    fn test_endgame() {
+        let table = ActionRewardTable::new();
+        let mut user = User::new(1);
+        user.add_vp(12);
+        let mut next_user = user.clone();
+        next_user.add_vp(3);
+
+        assert_eq!(table.endgame(&user, &user), 0.0);
+        assert_eq!(table.endgame(&user, &next_user), 3.0);
+    }
 }
@@ -4,15 +4,12 @@ use std::fmt;
 
 impl ActionReward {
     pub fn new(action: GameCommand, reward: f32) -> ActionReward {
-        ActionReward {
-            action: action,
-            reward: reward,
-        }
+        ActionReward { action, reward }
     }
 }
 
 impl fmt::Debug for ActionReward {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Action: {} 報酬点: {}", self.action, self.reward)
+        write!(f, "Action: {:?} reward: {}", self.action, self.reward)
     }
 }
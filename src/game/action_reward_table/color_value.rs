@@ -4,12 +4,18 @@ use super::ColorValue;
 use crate::game::color::Color::{self, *};
 use crate::game::gem::GEMS;
 
+impl Default for ColorValue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ColorValue {
     pub fn new() -> ColorValue {
         let mut color_value = HashMap::new();
         let colors = [Black, White, Red, Blue, Green, Gold];
-        for color in colors.into_iter() {
-            color_value.insert(color.clone(), 0.0);
+        for color in colors.iter() {
+            color_value.insert(*color, 0.0);
         }
         ColorValue(color_value)
     }
@@ -32,6 +38,6 @@ impl ColorValue {
     }
 
     fn _get(&self, color: Color) -> f32 {
-        self.0.get(&color).unwrap().clone()
+        *self.0.get(&color).unwrap()
     }
 }
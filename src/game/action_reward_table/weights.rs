@@ -0,0 +1,83 @@
+use super::Weights;
+
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            base_value: 0.3,
+            scarcity: 1.0,
+            reserve_card: 1.0,
+            reserve_stack_card: 0.0,
+            buy_point: 1.0,
+            buy_color: 1.0,
+            two_same_tokens: 2.0,
+            three_tokens: 1.0,
+            noble_progress: 0.5,
+            reserve_denial: 0.5,
+            endgame: 1.0,
+        }
+    }
+}
+
+impl Weights {
+    pub fn load(file_name: &str) -> io::Result<Weights> {
+        let weights = serde_json::from_reader(BufReader::new(File::open(file_name)?))?;
+        Ok(weights)
+    }
+
+    pub fn save(&self, file_name: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        File::create(file_name)?.write_all(json.as_bytes())
+    }
+
+    // 調整の対象となる重みを順番に返す
+    pub fn values_mut(&mut self) -> [&mut f32; 11] {
+        [
+            &mut self.base_value,
+            &mut self.scarcity,
+            &mut self.reserve_card,
+            &mut self.reserve_stack_card,
+            &mut self.buy_point,
+            &mut self.buy_color,
+            &mut self.two_same_tokens,
+            &mut self.three_tokens,
+            &mut self.noble_progress,
+            &mut self.reserve_denial,
+            &mut self.endgame,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Weights;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn test_save_and_load() {
+        let weights = Weights {
+            scarcity: 0.5,
+            ..Default::default()
+        };
+        let file_name = env::temp_dir().join(format!(
+            "splendor_clone_{}_test_weights.json",
+            process::id()
+        ));
+        let file_name = file_name.to_str().unwrap();
+
+        weights.save(file_name).unwrap();
+        assert_eq!(Weights::load(file_name).unwrap(), weights);
+    }
+
+    #[test]
+    fn test_values_mut() {
+        let mut weights = Weights::default();
+        for value in weights.values_mut().iter_mut() {
+            **value = 0.0;
+        }
+        assert_eq!(weights.two_same_tokens, 0.0);
+    }
+}
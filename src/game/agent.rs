@@ -0,0 +1,45 @@
+use crate::game::action_reward_table::ActionRewardTable;
+use crate::game::color::Color;
+use crate::game::game_command::GameCommand;
+use crate::game::observation::Observation;
+use crate::game::turn::{Decision, Phase, Turn};
+
+use rand::rngs::StdRng;
+
+mod heuristic_agent;
+mod random_agent;
+
+// 手番のユーザーに代わってコマンドを選ぶ
+// エージェントには手番のユーザーから見える局面だけを渡す
+// 見ていないカードの枚数や次に引くカードの確率は Observation::card_tracker で求める
+pub trait Agent {
+    fn name(&self) -> &str;
+    fn read(&mut self, observation: &Observation) -> GameCommand;
+    // トークンが上限を超えたら 1個ずつ捨てる色を選ぶ (既定では一番多く持っている宝石トークン)
+    fn discard(&mut self, observation: &Observation) -> Color {
+        Turn::default_discard(&observation.me().token_stack)
+    }
+    // 複数の貴族タイルが訪問できるとき、訪問させるものを id で選ぶ
+    fn choose_noble(&mut self, _observation: &Observation, noble_tiles: &[u8]) -> u8 {
+        noble_tiles[0]
+    }
+    // phase で決めることを read, discard, choose_noble のどれかで選ぶ (Over では None)
+    fn decide(&mut self, observation: &Observation, phase: &Phase) -> Option<Decision> {
+        match phase {
+            Phase::Main => Some(Decision::Command(self.read(observation))),
+            Phase::Discard(_) => Some(Decision::Discard(self.discard(observation))),
+            Phase::Noble(ids) => Some(Decision::Noble(self.choose_noble(observation, ids))),
+            Phase::Over => None,
+        }
+    }
+    // 乱数を使うエージェントは対局を再現できるように種を受け取る
+    fn seed(&mut self, _seed: u64) {}
+}
+
+pub struct RandomAgent {
+    rng: StdRng,
+}
+
+pub struct HeuristicAgent {
+    table: ActionRewardTable,
+}
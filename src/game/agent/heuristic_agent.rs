@@ -0,0 +1,34 @@
+use super::{Agent, HeuristicAgent};
+use crate::game::action_reward_table::{ActionRewardTable, Weights};
+use crate::game::game_command::GameCommand;
+use crate::game::observation::Observation;
+
+impl Default for HeuristicAgent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeuristicAgent {
+    pub fn new() -> HeuristicAgent {
+        HeuristicAgent {
+            table: ActionRewardTable::new(),
+        }
+    }
+    pub fn with_weights(weights: Weights) -> HeuristicAgent {
+        HeuristicAgent {
+            table: ActionRewardTable::with_weights(weights),
+        }
+    }
+}
+
+impl Agent for HeuristicAgent {
+    fn name(&self) -> &str {
+        "heuristic"
+    }
+    fn read(&mut self, observation: &Observation) -> GameCommand {
+        let (users, board) = observation.to_game();
+        self.table
+            .look(&users[observation.get_seat()], &users, &board)
+    }
+}
@@ -0,0 +1,38 @@
+use super::{Agent, RandomAgent};
+use crate::game::game_command::{GameCommand, NUMBER_OF_COMMANDS};
+use crate::game::observation::Observation;
+
+use rand::rngs::StdRng;
+use rand::{FromEntropy, Rng, SeedableRng};
+
+impl Default for RandomAgent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RandomAgent {
+    pub fn new() -> RandomAgent {
+        RandomAgent {
+            rng: StdRng::from_entropy(),
+        }
+    }
+    pub fn with_seed(seed: u64) -> RandomAgent {
+        RandomAgent {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn name(&self) -> &str {
+        "random"
+    }
+    fn read(&mut self, _observation: &Observation) -> GameCommand {
+        let random_value = self.rng.gen::<u8>() % NUMBER_OF_COMMANDS;
+        GameCommand::to_command(random_value)
+    }
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+}
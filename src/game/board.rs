@@ -1,16 +1,19 @@
+use crate::game::card_catalog::CardCatalog;
 use crate::game::card_stack::{Card, CardStack};
 use crate::game::color::Color;
-use crate::game::gem::{Gem, GEMS};
+use crate::game::gem::Gem;
 use crate::game::level::Level;
+use crate::game::locale::{message, text};
 use crate::game::noble_tile::NobleTile;
 use crate::game::token_stack::{Token, TokenStack};
 
 use ndarray::Array2;
+use rand::Rng;
 use std::fmt;
 
 const LIMIT_OF_GETTING_SAME_TOKEN: u8 = 4;
 
-const COORDINATE: [(u8, u8); 12] = [
+pub const COORDINATE: [(u8, u8); 12] = [
     (0, 0),
     (0, 1),
     (0, 2),
@@ -25,59 +28,86 @@ const COORDINATE: [(u8, u8); 12] = [
     (2, 3),
 ];
 
+// 場のカードは id で持ち、山札が尽きて補充できなかった場所は None
 #[derive(Clone)]
 pub struct Board {
-    board: Array2<Card>,
+    board: Array2<Option<u8>>,
     card_stack: CardStack,
     token_stack: TokenStack,
     noble_tile: Vec<NobleTile>,
 }
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
+        let noble_tiles: Vec<String> = self.noble_tile.iter().map(NobleTile::to_string).collect();
+        writeln!(f)?;
+        writeln!(
             f,
-            "
-貴族タイル: {:?}
-----------------------------------------------------------------------------------------------
-{}| {}| {}| {}  残り枚数: {}
-{}| {}| {}| {}  残り枚数: {}
-{}| {}| {}| {}  残り枚数: {}
-----------------------------------------------------------------------------------------------
-残りのトークン (黒, 白, 赤, 青, 緑, 金) = ({}, {}, {}, {}, {}, {})
-        ",
-            self.noble_tile,
-            self.board[(0, 0)],
-            self.board[(0, 1)],
-            self.board[(0, 2)],
-            self.board[(0, 3)],
-            self.card_stack.len(Level::Three),
-            self.board[(1, 0)],
-            self.board[(1, 1)],
-            self.board[(1, 2)],
-            self.board[(1, 3)],
-            self.card_stack.len(Level::Two),
-            self.board[(2, 0)],
-            self.board[(2, 1)],
-            self.board[(2, 2)],
-            self.board[(2, 3)],
-            self.card_stack.len(Level::One),
+            "{}",
+            message(
+                "board.noble_tiles",
+                &[&format!("[{}]", noble_tiles.join(", "))]
+            )
+        )?;
+        writeln!(f, "{}", "-".repeat(94))?;
+        for (x, level) in [(0, Level::Three), (1, Level::Two), (2, Level::One)].iter() {
+            writeln!(
+                f,
+                "{}| {}| {}| {}  {}",
+                self.slot(*x, 0),
+                self.slot(*x, 1),
+                self.slot(*x, 2),
+                self.slot(*x, 3),
+                message("board.remaining", &[&self.card_stack.len(*level)])
+            )?;
+        }
+        writeln!(f, "{}", "-".repeat(94))?;
+        let tokens = format!(
+            "{}, {}, {}, {}, {}, {}",
             self.token_stack.len(Color::Black),
             self.token_stack.len(Color::White),
             self.token_stack.len(Color::Red),
             self.token_stack.len(Color::Blue),
             self.token_stack.len(Color::Green),
             self.token_stack.len(Color::Gold)
-        )
+        );
+        writeln!(f, "{}", message("board.tokens", &[&tokens]))
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Board {
+    // 観測から局面を組み立てるときに使う
+    pub(crate) fn from_parts(
+        grid: [[Option<u8>; 4]; 3],
+        card_stack: CardStack,
+        token_stack: TokenStack,
+        noble_tile: Vec<NobleTile>,
+    ) -> Board {
+        let mut board = Array2::from_elem((3, 4), None);
+        for (x, y) in COORDINATE.iter() {
+            board[[*x as usize, *y as usize]] = grid[*x as usize][*y as usize];
+        }
+        Board {
+            board,
+            card_stack,
+            token_stack,
+            noble_tile,
+        }
+    }
     pub fn new() -> Board {
+        Board::with_rng(&mut rand::thread_rng())
+    }
+    pub fn with_rng<R: Rng + ?Sized>(rng: &mut R) -> Board {
         let mut board = Board {
-            board: Array2::<Card>::default((3, 4)),
-            card_stack: CardStack::new(Some("data/card.json")),
+            board: Array2::from_elem((3, 4), None),
+            card_stack: CardStack::with_rng(rng),
             token_stack: TokenStack::new().fill(),
-            noble_tile: NobleTile::create_stack(),
+            noble_tile: NobleTile::create_stack_with_rng(rng),
         };
 
         for (x, y) in COORDINATE.iter() {
@@ -87,26 +117,24 @@ impl Board {
         board
     }
     pub fn peek_card(&self, x: u8, y: u8) -> Option<&Card> {
-        self.board.get((x as usize, y as usize))
+        self.peek_card_id(x, y)
+            .map(|id| CardCatalog::global().card(id))
+    }
+    pub fn peek_card_id(&self, x: u8, y: u8) -> Option<u8> {
+        self.board.get((x as usize, y as usize)).cloned().flatten()
     }
     pub fn get_card(&mut self, x: u8, y: u8) -> Option<Card> {
-        match self.board.get_mut((x as usize, y as usize)) {
-            Some(card) => {
-                let card2 = card.clone();
-                self.refill(x, y);
-                Some(card2)
-            }
-            None => None,
-        }
+        let card = *self.peek_card(x, y)?;
+        self.refill(x, y);
+        Some(card)
     }
     pub fn get_stack_card(&mut self, level: Level) -> Option<Card> {
-        self.card_stack.get(level)
+        self.card_stack
+            .get(level)
+            .map(|id| *CardCatalog::global().card(id))
     }
     pub fn uget_card(&mut self, x: u8, y: u8) -> Card {
-        let card = self.board.get_mut((x as usize, y as usize)).unwrap();
-        let card2 = card.clone();
-        self.refill(x, y);
-        card2
+        self.get_card(x, y).unwrap()
     }
     pub fn get_token(&mut self, color: Color) -> Option<Token> {
         self.token_stack.remove(color)
@@ -117,26 +145,37 @@ impl Board {
     pub fn get_token_stack(&mut self) -> &mut TokenStack {
         &mut self.token_stack
     }
+    pub fn peek_token_stack(&self) -> &TokenStack {
+        &self.token_stack
+    }
     pub fn can_get_token(&self, color: Color) -> bool {
         self.get_number_of_tokens(color) >= LIMIT_OF_GETTING_SAME_TOKEN
     }
     pub fn get_noble_tile(&mut self) -> &mut Vec<NobleTile> {
         &mut self.noble_tile
     }
+    pub fn peek_noble_tile(&self) -> &[NobleTile] {
+        &self.noble_tile
+    }
     pub fn get_required_cost(&self) -> Gem {
         let mut required_cost = Gem::new();
         for (x, y) in COORDINATE.iter() {
             if let Some(card) = self.peek_card(*x, *y) {
-                for color in GEMS.iter() {
-                    required_cost.add(*color, card.get_cost(*color));
-                }
+                required_cost += card.cost;
             }
         }
         required_cost
     }
-    fn get_number_of_tokens(&self, color: Color) -> u8 {
+    pub fn get_number_of_tokens(&self, color: Color) -> u8 {
         self.token_stack.len(color)
     }
+    pub fn get_number_of_stack_cards(&self, level: Level) -> u8 {
+        self.card_stack.len(level)
+    }
+    pub fn peek_stack(&self, level: Level) -> &[u8] {
+        self.card_stack.peek(level)
+    }
+    // 山札が尽きていれば空きになる
     fn refill(&mut self, x: u8, y: u8) {
         let card = match x {
             0 => self.card_stack.get(Level::Three),
@@ -145,63 +184,61 @@ impl Board {
             _ => unreachable!(),
         };
 
-        match card {
-            Some(card) => self.board[[x as usize, y as usize]] = card,
-            None => (),
+        self.board[[x as usize, y as usize]] = card;
+    }
+    fn slot(&self, x: u8, y: u8) -> String {
+        match self.peek_card(x, y) {
+            Some(card) => card.to_string(),
+            None => text("board.empty").to_string(),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Board;
-    use super::COORDINATE;
-    use crate::game::card_stack::{Card, CardStack};
-    use crate::game::color::Color::{self, *};
-    use crate::game::gem::{Gem, GEMS};
+    use super::{Board, COORDINATE};
+    use crate::game::card_stack::Card;
+    use crate::game::color::Color;
+    use crate::game::game_command::GameCommand;
+    use crate::game::gem::Gem;
     use crate::game::level::Level;
-    use crate::game::token_stack::{Token, TokenStack};
+    use crate::game::locale::text;
+    use crate::game::user::User;
     use ndarray::Array2;
+    use std::collections::HashSet;
 
     // TOOD mock
     struct TestBoard {
-        board: Array2<Card>,
-        card_stack: CardStack,
+        board: Array2<Option<Card>>,
+        card_stack: Vec<Card>,
     }
     impl TestBoard {
         fn get_required_cost(&self) -> Gem {
             let mut required_cost = Gem::new();
             for (x, y) in COORDINATE.iter() {
                 if let Some(card) = self.peek_card(*x, *y) {
-                    for color in GEMS.iter() {
-                        required_cost.add(*color, card.get_cost(*color));
-                    }
+                    required_cost += card.cost;
                 }
             }
             required_cost
         }
         fn peek_card(&self, x: u8, y: u8) -> Option<&Card> {
-            self.board.get((x as usize, y as usize))
+            self.board.get((x as usize, y as usize))?.as_ref()
         }
         fn refill(&mut self, x: u8, y: u8) {
-            let card = match x {
-                0 => self.card_stack.get(Level::Three),
-                1 => self.card_stack.get(Level::Two),
-                2 => self.card_stack.get(Level::One),
-                _ => unreachable!(),
-            };
-
-            match card {
-                Some(card) => self.board[[x as usize, y as usize]] = card,
-                None => (),
-            }
+            let level = 3 - x;
+            self.board[[x as usize, y as usize]] = self
+                .card_stack
+                .iter()
+                .rposition(|c| c.level == level)
+                .map(|order| self.card_stack.remove(order));
         }
     }
 
     fn setup_board() -> TestBoard {
         let mut board = TestBoard {
-            board: Array2::<Card>::default((3, 4)),
-            card_stack: CardStack::new(Some("data/test_card.json")),
+            board: Array2::from_elem((3, 4), None),
+            card_stack: Card::load("data/test_card.json").unwrap(),
         };
         for (x, y) in COORDINATE.iter() {
             board.refill(*x, *y);
@@ -216,4 +253,38 @@ mod tests {
         assert_eq!(1, board.get_required_cost().get(Color::Red));
         assert_eq!(1, board.get_required_cost().get(Color::Green));
     }
+
+    #[test]
+    fn test_card_ids() {
+        let board = Board::new();
+        let mut ids: HashSet<u8> = COORDINATE
+            .iter()
+            .filter_map(|(x, y)| board.peek_card_id(*x, *y))
+            .collect();
+        for level in [Level::One, Level::Two, Level::Three].iter() {
+            ids.extend(board.peek_stack(*level));
+        }
+        // 場と山札で全てのカードが 1枚ずつ
+        assert_eq!(ids.len(), 90);
+        assert_eq!(board.peek_card(2, 0).unwrap().level, 1);
+        assert_eq!(board.peek_card(0, 0).unwrap().level, 3);
+    }
+
+    #[test]
+    fn test_exhausted_stack() {
+        let mut board = Board::new();
+        while board.get_stack_card(Level::Three).is_some() {}
+
+        let card = board.get_card(0, 0).unwrap();
+        assert_eq!(card.level, 3);
+        assert!(board.peek_card(0, 0).is_none());
+        assert!(board.get_card(0, 0).is_none());
+        assert!(board.to_string().contains(text("board.empty")));
+
+        // 空きのカードは確保も購入もできない
+        let mut user = User::new(0);
+        assert!(GameCommand::reserve_development_card(0, 0, &mut user, &mut board).is_err());
+        assert!(GameCommand::buy_development_card(0, 0, &mut user, &mut board).is_err());
+        assert_eq!(user.get_number_of_hands(), 0);
+    }
 }
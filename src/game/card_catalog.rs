@@ -0,0 +1,209 @@
+use crate::game::card_stack::Card;
+use crate::game::color::Color;
+use crate::game::gem::Gem;
+use crate::game::level::Level;
+use crate::game::noble_tile::NobleTile;
+
+use std::sync::OnceLock;
+
+mod data_error;
+mod data_format;
+mod validator;
+
+// カードや貴族タイルがない場所
+pub const EMPTY: u8 = u8::MAX;
+
+pub const CARD_FILE: &str = "data/card.json";
+pub const NOBLE_TILE_FILE: &str = "data/noble_tile.json";
+
+static CATALOG: OnceLock<CardCatalog> = OnceLock::new();
+
+// 全てのカードと貴族タイルを data/*.json の id 順に並べたもの
+// Board、CardStack、User はカードをこの id で持つ
+pub struct CardCatalog {
+    cards: Vec<Card>,
+    noble_tiles: Vec<NobleTile>,
+}
+
+// カードと貴族タイルのファイルの形式
+// JSON Lines は 1行に 1つの JSON オブジェクト、CSV は 1行目が列名(id, level, color, point, cost_black, ...)
+// TOML は [[card]] または [[noble_tile]] の表の配列
+// どの形式でも id は必須で、0 から重複のない連番にする (CardCatalog の添字になるため)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DataFormat {
+    JsonLines,
+    JsonArray,
+    Toml,
+    Csv,
+}
+
+// カードと貴族タイルのデータの誤り
+// line は 1 から数えた行番号 (JSON 配列と TOML では何番目の要素か)
+#[derive(Clone, Debug, PartialEq)]
+pub enum DataError {
+    // ファイルを開けない、読めない
+    Io {
+        file: String,
+        message: String,
+    },
+    // 拡張子から形式がわからない
+    UnknownFormat {
+        file: String,
+    },
+    // 書き出せない
+    Write {
+        file: String,
+        message: String,
+    },
+    // 形式に合わない行や要素
+    Parse {
+        file: String,
+        line: usize,
+        message: String,
+    },
+    // レベルが 1〜3 以外
+    Level {
+        file: String,
+        line: usize,
+        id: u8,
+        level: u8,
+    },
+    // ボーナスが金のカード
+    Color {
+        file: String,
+        line: usize,
+        id: u8,
+        color: Color,
+    },
+    // 勝利点が上限を超えている
+    Point {
+        file: String,
+        line: usize,
+        id: u8,
+        point: u8,
+    },
+    // コスト(貴族タイルは必要なボーナス)が上限を超えている、または全て 0
+    Cost {
+        file: String,
+        line: usize,
+        id: u8,
+        cost: Gem,
+    },
+    // 同じ id が first_line でも使われている
+    DuplicateId {
+        file: String,
+        line: usize,
+        id: u8,
+        first_line: usize,
+    },
+    // id は 0 から連番でなければならない
+    MissingId {
+        file: String,
+        id: u8,
+    },
+    // レベルごと、レベルと色ごとの枚数が違う (color が None ならレベルの合計)
+    CardCount {
+        file: String,
+        level: u8,
+        color: Option<Color>,
+        expected: usize,
+        actual: usize,
+    },
+    NobleTileCount {
+        file: String,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl CardCatalog {
+    // data/*.json を最初に呼ばれたときに 1度だけ読み込む
+    // データに誤りがあればゲームを始められないので、全ての誤りを表示して終了する
+    pub fn global() -> &'static CardCatalog {
+        CATALOG.get_or_init(|| match CardCatalog::load(CARD_FILE, NOBLE_TILE_FILE) {
+            Ok(catalog) => catalog,
+            Err(errors) => {
+                let messages: Vec<String> = errors.iter().map(DataError::to_string).collect();
+                panic!("\n{}", messages.join("\n"))
+            }
+        })
+    }
+
+    // 検証して誤りがなければ id の順に並べたカタログを返す
+    pub fn load(card_file: &str, noble_tile_file: &str) -> Result<CardCatalog, Vec<DataError>> {
+        let (mut cards, mut errors) = CardCatalog::validate_cards(card_file);
+        let (mut noble_tiles, noble_tile_errors) =
+            CardCatalog::validate_noble_tiles(noble_tile_file);
+        errors.extend(noble_tile_errors);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        cards.sort_by_key(|card| card.id);
+        noble_tiles.sort_by_key(|tile| tile.get_id());
+        Ok(CardCatalog { cards, noble_tiles })
+    }
+
+    // カードと貴族タイルのファイルの全ての誤りを返す
+    pub fn validate(card_file: &str, noble_tile_file: &str) -> Vec<DataError> {
+        let mut errors = CardCatalog::validate_cards(card_file).1;
+        errors.extend(CardCatalog::validate_noble_tiles(noble_tile_file).1);
+        errors
+    }
+
+    pub fn card(&self, id: u8) -> &Card {
+        &self.cards[id as usize]
+    }
+
+    pub fn noble_tile(&self, id: u8) -> &NobleTile {
+        &self.noble_tiles[id as usize]
+    }
+
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    pub fn noble_tiles(&self) -> &[NobleTile] {
+        &self.noble_tiles
+    }
+
+    // level のカードの id を id の順に返す
+    pub fn card_ids(&self, level: Level) -> Vec<u8> {
+        let level = level as u8 + 1;
+        self.cards
+            .iter()
+            .filter(|card| card.level == level)
+            .map(|card| card.id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CardCatalog;
+    use crate::game::level::Level;
+
+    #[test]
+    fn test_global() {
+        let catalog = CardCatalog::global();
+        assert_eq!(catalog.cards().len(), 90);
+        assert_eq!(catalog.noble_tiles().len(), 10);
+        for id in 0..10 {
+            assert_eq!(catalog.card(id).id, id);
+            assert_eq!(catalog.noble_tile(id).get_id(), id);
+        }
+        assert!(std::ptr::eq(catalog, CardCatalog::global()));
+    }
+
+    #[test]
+    fn test_card_ids() {
+        let catalog = CardCatalog::global();
+        assert_eq!(catalog.card_ids(Level::One).len(), 40);
+        assert_eq!(catalog.card_ids(Level::Two).len(), 30);
+        assert_eq!(catalog.card_ids(Level::Three).len(), 20);
+        assert!(catalog
+            .card_ids(Level::Three)
+            .iter()
+            .all(|id| catalog.card(*id).level == 3));
+    }
+}
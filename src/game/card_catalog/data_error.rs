@@ -0,0 +1,74 @@
+use super::DataError;
+use crate::game::locale;
+
+use std::error;
+use std::fmt;
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::DataError::*;
+        let line = match self {
+            Io { file, message } => locale::message("data.io", &[file, message]),
+            UnknownFormat { file } => locale::message("data.unknown_format", &[file]),
+            Write { file, message } => locale::message("data.write", &[file, message]),
+            Parse {
+                file,
+                line,
+                message,
+            } => locale::message("data.parse", &[file, line, message]),
+            Level {
+                file,
+                line,
+                id,
+                level,
+            } => locale::message("data.level", &[file, line, id, level]),
+            Color {
+                file,
+                line,
+                id,
+                color,
+            } => locale::message("data.color", &[file, line, id, color]),
+            Point {
+                file,
+                line,
+                id,
+                point,
+            } => locale::message("data.point", &[file, line, id, point]),
+            Cost {
+                file,
+                line,
+                id,
+                cost,
+            } => locale::message("data.cost", &[file, line, id, &format!("{:?}", cost)]),
+            DuplicateId {
+                file,
+                line,
+                id,
+                first_line,
+            } => locale::message("data.duplicate_id", &[file, line, id, first_line]),
+            MissingId { file, id } => locale::message("data.missing_id", &[file, id]),
+            CardCount {
+                file,
+                level,
+                color: Some(color),
+                expected,
+                actual,
+            } => locale::message("data.color_count", &[file, level, color, actual, expected]),
+            CardCount {
+                file,
+                level,
+                color: None,
+                expected,
+                actual,
+            } => locale::message("data.card_count", &[file, level, actual, expected]),
+            NobleTileCount {
+                file,
+                expected,
+                actual,
+            } => locale::message("data.noble_tile_count", &[file, actual, expected]),
+        };
+        f.write_str(&line)
+    }
+}
+
+impl error::Error for DataError {}
@@ -0,0 +1,286 @@
+use super::{CardCatalog, DataError, DataFormat};
+use crate::game::card_stack::Card;
+use crate::game::locale::{self, message};
+use crate::game::noble_tile::NobleTile;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+impl FromStr for DataFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<DataFormat, String> {
+        match s {
+            "jsonl" => Ok(DataFormat::JsonLines),
+            "json" => Ok(DataFormat::JsonArray),
+            "toml" => Ok(DataFormat::Toml),
+            "csv" => Ok(DataFormat::Csv),
+            _ => Err(message("data.unknown_data_format", &[&s])),
+        }
+    }
+}
+
+impl DataFormat {
+    // 拡張子から形式を決める (.json は JSON 配列)
+    pub fn from_extension(file: &str) -> Result<DataFormat, DataError> {
+        match Path::new(file).extension().and_then(|e| e.to_str()) {
+            Some("jsonl") => Ok(DataFormat::JsonLines),
+            Some("json") => Ok(DataFormat::JsonArray),
+            Some("toml") => Ok(DataFormat::Toml),
+            Some("csv") => Ok(DataFormat::Csv),
+            _ => Err(DataError::UnknownFormat {
+                file: file.to_string(),
+            }),
+        }
+    }
+
+    // 読み込むときは .json でも [ で始まらなければ JSON Lines とみなす
+    pub fn detect(file: &str, text: &str) -> Result<DataFormat, DataError> {
+        match DataFormat::from_extension(file)? {
+            DataFormat::JsonArray if !text.trim_start().starts_with('[') => {
+                Ok(DataFormat::JsonLines)
+            }
+            format => Ok(format),
+        }
+    }
+
+    // 読めたレコードを位置つきで返し、読めなかったレコードは誤りにして続ける
+    pub fn parse<T: DeserializeOwned>(
+        &self,
+        file: &str,
+        text: &str,
+    ) -> (Vec<(usize, T)>, Vec<DataError>) {
+        let mut records = vec![];
+        let mut errors = vec![];
+        let mut push = |line: usize, result: Result<T, String>| match result {
+            Ok(record) => records.push((line, record)),
+            Err(message) => errors.push(DataError::Parse {
+                file: file.to_string(),
+                line,
+                message,
+            }),
+        };
+
+        match self {
+            DataFormat::JsonLines => {
+                for (index, l) in text.lines().enumerate() {
+                    if !l.trim().is_empty() {
+                        push(
+                            index + 1,
+                            serde_json::from_str(l).map_err(|e| e.to_string()),
+                        );
+                    }
+                }
+            }
+            DataFormat::JsonArray => match serde_json::from_str::<Vec<serde_json::Value>>(text) {
+                Ok(values) => {
+                    for (index, value) in values.into_iter().enumerate() {
+                        push(
+                            index + 1,
+                            serde_json::from_value(value).map_err(|e| e.to_string()),
+                        );
+                    }
+                }
+                Err(e) => push(e.line(), Err(e.to_string())),
+            },
+            // 最初の表の配列を読む
+            DataFormat::Toml => match text.parse::<toml::Value>() {
+                Ok(toml::Value::Table(table)) => {
+                    let values = table
+                        .into_iter()
+                        .find_map(|(_, value)| match value {
+                            toml::Value::Array(values) => Some(values),
+                            _ => None,
+                        })
+                        .unwrap_or_default();
+                    for (index, value) in values.into_iter().enumerate() {
+                        push(index + 1, value.try_into().map_err(|e| e.to_string()));
+                    }
+                }
+                Ok(_) => push(1, Err(locale::text("data.not_table").to_string())),
+                Err(e) => {
+                    let line = e.line_col().map_or(1, |(line, _)| line + 1);
+                    push(line, Err(e.to_string()));
+                }
+            },
+            DataFormat::Csv => {
+                let mut reader = csv::Reader::from_reader(text.as_bytes());
+                let headers = match reader.headers() {
+                    Ok(headers) => headers.clone(),
+                    Err(e) => {
+                        push(1, Err(e.to_string()));
+                        return (records, errors);
+                    }
+                };
+                for result in reader.records() {
+                    match result {
+                        Ok(record) => {
+                            let line = record.position().map_or(0, |p| p.line() as usize);
+                            push(
+                                line,
+                                record
+                                    .deserialize(Some(&headers))
+                                    .map_err(|e| e.to_string()),
+                            );
+                        }
+                        Err(e) => {
+                            let line = e.position().map_or(0, |p| p.line() as usize);
+                            push(line, Err(e.to_string()));
+                        }
+                    }
+                }
+            }
+        }
+
+        (records, errors)
+    }
+
+    // key は TOML の表の配列の名前
+    pub fn write<T: Serialize>(&self, records: &[T], key: &str) -> Result<String, String> {
+        match self {
+            DataFormat::JsonLines => {
+                let mut text = String::new();
+                for record in records.iter() {
+                    text += &serde_json::to_string(record).map_err(|e| e.to_string())?;
+                    text += "\n";
+                }
+                Ok(text)
+            }
+            // 差分が見やすいように 1行に 1要素
+            DataFormat::JsonArray => {
+                let lines = records
+                    .iter()
+                    .map(|record| serde_json::to_string(record).map(|l| format!("  {}", l)))
+                    .collect::<Result<Vec<String>, _>>()
+                    .map_err(|e| e.to_string())?;
+                Ok(format!("[\n{}\n]\n", lines.join(",\n")))
+            }
+            DataFormat::Toml => {
+                let mut document = BTreeMap::new();
+                document.insert(key, records);
+                toml::to_string(&document).map_err(|e| e.to_string())
+            }
+            DataFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(vec![]);
+                for record in records.iter() {
+                    writer.serialize(record).map_err(|e| e.to_string())?;
+                }
+                let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+                String::from_utf8(bytes).map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+impl CardCatalog {
+    // input のカードを output に書き出し、書き出した枚数を返す
+    // format が None なら output の拡張子で決める
+    pub fn export_cards(
+        input: &str,
+        output: &str,
+        format: Option<DataFormat>,
+    ) -> Result<usize, DataError> {
+        CardCatalog::export::<Card>(input, output, format, "card")
+    }
+
+    pub fn export_noble_tiles(
+        input: &str,
+        output: &str,
+        format: Option<DataFormat>,
+    ) -> Result<usize, DataError> {
+        CardCatalog::export::<NobleTile>(input, output, format, "noble_tile")
+    }
+
+    fn export<T: DeserializeOwned + Serialize>(
+        input: &str,
+        output: &str,
+        format: Option<DataFormat>,
+        key: &str,
+    ) -> Result<usize, DataError> {
+        let format = match format {
+            Some(format) => format,
+            None => DataFormat::from_extension(output)?,
+        };
+        let records: Vec<T> = CardCatalog::read_records(input)?;
+        let write_error = |message: String| DataError::Write {
+            file: output.to_string(),
+            message,
+        };
+        let text = format.write(&records, key).map_err(write_error)?;
+        fs::write(output, text).map_err(|e| write_error(e.to_string()))?;
+        Ok(records.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::card_catalog::{CardCatalog, DataFormat, CARD_FILE, NOBLE_TILE_FILE};
+    use crate::game::card_stack::Card;
+    use crate::game::noble_tile::NobleTile;
+
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn test_round_trip() {
+        let catalog = CardCatalog::global();
+        let formats = [
+            DataFormat::JsonLines,
+            DataFormat::JsonArray,
+            DataFormat::Toml,
+            DataFormat::Csv,
+        ];
+        for format in formats.iter() {
+            let text = format.write(catalog.cards(), "card").unwrap();
+            let (cards, errors) = format.parse::<Card>("cards", &text);
+            assert!(errors.is_empty(), "{:?}: {:?}", format, errors);
+            assert_eq!(cards.len(), catalog.cards().len());
+            for ((_, card), expected) in cards.iter().zip(catalog.cards().iter()) {
+                assert_eq!(card.id, expected.id);
+                assert_eq!(card.color, expected.color);
+                assert_eq!(card.cost, expected.cost);
+            }
+
+            let text = format.write(catalog.noble_tiles(), "noble_tile").unwrap();
+            let (noble_tiles, errors) = format.parse::<NobleTile>("noble_tiles", &text);
+            assert!(errors.is_empty(), "{:?}: {:?}", format, errors);
+            assert_eq!(
+                noble_tiles[3].1.get_required(),
+                catalog.noble_tile(3).get_required()
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let text = "id,level,color,point,cost_black,cost_white,cost_red,cost_blue,cost_green
+0,1,Black,0,0,1,1,1,1
+1,1,Purple,0,0,1,1,1,1
+";
+        let (cards, errors) = DataFormat::Csv.parse::<Card>("cards.csv", text);
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].0, 2);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().starts_with("cards.csv:3:"));
+    }
+
+    #[test]
+    fn test_export() {
+        let output =
+            env::temp_dir().join(format!("splendor_clone_{}_test_export.toml", process::id()));
+        let output = output.to_str().unwrap();
+        assert_eq!(
+            CardCatalog::export_noble_tiles(NOBLE_TILE_FILE, output, None),
+            Ok(10)
+        );
+        assert_eq!(NobleTile::load(output).unwrap().len(), 10);
+        assert_eq!(
+            DataFormat::detect(CARD_FILE, "{"),
+            Ok(DataFormat::JsonLines)
+        );
+        assert!(CardCatalog::export_cards(CARD_FILE, "cards.txt", None).is_err());
+    }
+}
@@ -0,0 +1,316 @@
+use super::{CardCatalog, DataError, DataFormat};
+use crate::game::card_stack::Card;
+use crate::game::color::Color::Gold;
+use crate::game::gem::{Gem, GEMS};
+use crate::game::noble_tile::NobleTile;
+
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::fs;
+
+// レベル 1, 2, 3 の順
+const CARDS_PER_LEVEL: [usize; 3] = [40, 30, 20];
+const CARDS_PER_COLOR: [usize; 3] = [8, 6, 4];
+const NUMBER_OF_NOBLE_TILES: usize = 10;
+const MAX_CARD_POINT: u8 = 5;
+const MAX_CARD_COST: u8 = 7;
+const MAX_NOBLE_TILE_POINT: u8 = 3;
+const MAX_NOBLE_TILE_BONUS: u8 = 4;
+
+impl CardCatalog {
+    // 形式を拡張子と中身から判断して読み、最初の誤りで止める
+    pub(crate) fn read_records<T: DeserializeOwned>(file: &str) -> Result<Vec<T>, DataError> {
+        let (format, text) = CardCatalog::read(file)?;
+        let (records, mut errors) = format.parse(file, &text);
+        if errors.is_empty() {
+            Ok(records.into_iter().map(|(_, record)| record).collect())
+        } else {
+            Err(errors.remove(0))
+        }
+    }
+
+    pub(super) fn validate_cards(file: &str) -> (Vec<Card>, Vec<DataError>) {
+        match CardCatalog::read(file) {
+            Ok((format, text)) => CardCatalog::check_cards(file, format.parse(file, &text)),
+            Err(error) => (vec![], vec![error]),
+        }
+    }
+
+    pub(super) fn validate_noble_tiles(file: &str) -> (Vec<NobleTile>, Vec<DataError>) {
+        match CardCatalog::read(file) {
+            Ok((format, text)) => CardCatalog::check_noble_tiles(file, format.parse(file, &text)),
+            Err(error) => (vec![], vec![error]),
+        }
+    }
+
+    // records は読めたレコードと位置、errors は読めなかったレコードの誤り
+    fn check_cards(
+        file: &str,
+        (records, mut errors): (Vec<(usize, Card)>, Vec<DataError>),
+    ) -> (Vec<Card>, Vec<DataError>) {
+        let ids: Vec<(usize, u8)> = records
+            .iter()
+            .map(|(line, card)| (*line, card.id))
+            .collect();
+        errors.extend(CardCatalog::check_ids(file, &ids));
+
+        let mut cards = vec![];
+        for (line, card) in records.into_iter() {
+            let (file, id) = (file.to_string(), card.id);
+            if card.level < 1 || card.level > 3 {
+                errors.push(DataError::Level {
+                    file,
+                    line,
+                    id,
+                    level: card.level,
+                });
+            } else if card.color == Gold {
+                errors.push(DataError::Color {
+                    file,
+                    line,
+                    id,
+                    color: card.color,
+                });
+            } else if card.point > MAX_CARD_POINT {
+                errors.push(DataError::Point {
+                    file,
+                    line,
+                    id,
+                    point: card.point,
+                });
+            } else if !CardCatalog::is_valid_cost(&card.cost, MAX_CARD_COST) {
+                errors.push(DataError::Cost {
+                    file,
+                    line,
+                    id,
+                    cost: card.cost,
+                });
+            } else {
+                cards.push(card);
+            }
+        }
+
+        for level in 1..=3 {
+            let expected = CARDS_PER_LEVEL[level as usize - 1];
+            let actual = cards.iter().filter(|card| card.level == level).count();
+            if actual != expected {
+                errors.push(DataError::CardCount {
+                    file: file.to_string(),
+                    level,
+                    color: None,
+                    expected,
+                    actual,
+                });
+                continue;
+            }
+            for color in GEMS.iter() {
+                let expected = CARDS_PER_COLOR[level as usize - 1];
+                let actual = cards
+                    .iter()
+                    .filter(|card| card.level == level && card.color == *color)
+                    .count();
+                if actual != expected {
+                    errors.push(DataError::CardCount {
+                        file: file.to_string(),
+                        level,
+                        color: Some(*color),
+                        expected,
+                        actual,
+                    });
+                }
+            }
+        }
+
+        (cards, errors)
+    }
+
+    fn check_noble_tiles(
+        file: &str,
+        (records, mut errors): (Vec<(usize, NobleTile)>, Vec<DataError>),
+    ) -> (Vec<NobleTile>, Vec<DataError>) {
+        let ids: Vec<(usize, u8)> = records
+            .iter()
+            .map(|(line, tile)| (*line, tile.get_id()))
+            .collect();
+        errors.extend(CardCatalog::check_ids(file, &ids));
+
+        let mut noble_tiles = vec![];
+        for (line, tile) in records.into_iter() {
+            let (file, id) = (file.to_string(), tile.get_id());
+            if tile.get_point() > MAX_NOBLE_TILE_POINT {
+                errors.push(DataError::Point {
+                    file,
+                    line,
+                    id,
+                    point: tile.get_point(),
+                });
+            } else if !CardCatalog::is_valid_cost(tile.get_required(), MAX_NOBLE_TILE_BONUS) {
+                errors.push(DataError::Cost {
+                    file,
+                    line,
+                    id,
+                    cost: *tile.get_required(),
+                });
+            } else {
+                noble_tiles.push(tile);
+            }
+        }
+
+        if noble_tiles.len() != NUMBER_OF_NOBLE_TILES {
+            errors.push(DataError::NobleTileCount {
+                file: file.to_string(),
+                expected: NUMBER_OF_NOBLE_TILES,
+                actual: noble_tiles.len(),
+            });
+        }
+
+        (noble_tiles, errors)
+    }
+
+    // ファイルを読み、拡張子と中身から形式を決める
+    fn read(file: &str) -> Result<(DataFormat, String), DataError> {
+        let text = fs::read_to_string(file).map_err(|e| DataError::Io {
+            file: file.to_string(),
+            message: e.to_string(),
+        })?;
+        Ok((DataFormat::detect(file, &text)?, text))
+    }
+
+    // id は重複なく 0 から連番にする (CardCatalog の添字に使うため)
+    fn check_ids(file: &str, ids: &[(usize, u8)]) -> Vec<DataError> {
+        let mut errors = vec![];
+        let mut lines: HashMap<u8, usize> = HashMap::new();
+        for (line, id) in ids.iter() {
+            match lines.get(id) {
+                Some(first_line) => errors.push(DataError::DuplicateId {
+                    file: file.to_string(),
+                    line: *line,
+                    id: *id,
+                    first_line: *first_line,
+                }),
+                None => {
+                    lines.insert(*id, *line);
+                }
+            }
+        }
+        if let Some(max) = lines.keys().max() {
+            for id in 0..*max {
+                if !lines.contains_key(&id) {
+                    errors.push(DataError::MissingId {
+                        file: file.to_string(),
+                        id,
+                    });
+                }
+            }
+        }
+        errors
+    }
+
+    fn is_valid_cost(cost: &Gem, max: u8) -> bool {
+        cost.total() > 0 && cost.iter().all(|(_, n)| n <= max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::card_catalog::{
+        CardCatalog, DataError, DataFormat, CARD_FILE, NOBLE_TILE_FILE,
+    };
+    use crate::game::color::Color::*;
+    use crate::game::gem::Gem;
+    use crate::game::locale::message;
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(CardCatalog::validate(CARD_FILE, NOBLE_TILE_FILE), vec![]);
+
+        let errors = CardCatalog::validate("data/not_found.json", NOBLE_TILE_FILE);
+        assert_eq!(errors.len(), 1);
+        match &errors[0] {
+            DataError::Io { file, .. } => assert_eq!(file, "data/not_found.json"),
+            error => panic!("{}", error),
+        }
+    }
+
+    #[test]
+    fn test_check_cards() {
+        let text = r#"{"id":0,"level":1,"color":"Black","point":0,"cost_black":0,"cost_white":1,"cost_red":1,"cost_blue":1,"cost_green":1}
+{"id":1,"level":4,"color":"Black","point":0,"cost_black":0,"cost_white":1,"cost_red":1,"cost_blue":1,"cost_green":1}
+{"id":1,"level":1,"color":"Gold","point":0,"cost_black":0,"cost_white":1,"cost_red":1,"cost_blue":1,"cost_green":1}
+
+{"id":3,"level":1,"color":"Red","point":0,"cost_black":8,"cost_white":0,"cost_red":0,"cost_blue":0,"cost_green":0}
+{"id":4,"level":1,"color":"Red"#;
+        let (cards, errors) =
+            CardCatalog::check_cards("cards", DataFormat::JsonLines.parse("cards", text));
+
+        assert_eq!(cards.len(), 1);
+        assert!(matches!(errors[0], DataError::Parse { line: 6, .. }));
+        assert_eq!(
+            &errors[1..5],
+            &[
+                DataError::DuplicateId {
+                    file: "cards".to_string(),
+                    line: 3,
+                    id: 1,
+                    first_line: 2,
+                },
+                DataError::MissingId {
+                    file: "cards".to_string(),
+                    id: 2,
+                },
+                DataError::Level {
+                    file: "cards".to_string(),
+                    line: 2,
+                    id: 1,
+                    level: 4,
+                },
+                DataError::Color {
+                    file: "cards".to_string(),
+                    line: 3,
+                    id: 1,
+                    color: Gold,
+                },
+            ]
+        );
+        assert_eq!(
+            errors[5],
+            DataError::Cost {
+                file: "cards".to_string(),
+                line: 5,
+                id: 3,
+                cost: Gem::from([8, 0, 0, 0, 0]),
+            }
+        );
+        assert_eq!(
+            errors[6].to_string(),
+            message("data.card_count", &[&"cards", &1, &1, &40])
+        );
+        assert_eq!(errors.len(), 9);
+    }
+
+    #[test]
+    fn test_check_noble_tiles() {
+        let text = r#"{"id":0,"point":3,"black_bonus":4,"white_bonus":4,"red_bonus":0,"blue_bonus":0,"green_bonus":0}
+{"id":1,"point":9,"black_bonus":4,"white_bonus":4,"red_bonus":0,"blue_bonus":0,"green_bonus":0}"#;
+        let (noble_tiles, errors) =
+            CardCatalog::check_noble_tiles("nobles", DataFormat::JsonLines.parse("nobles", text));
+
+        assert_eq!(noble_tiles.len(), 1);
+        assert_eq!(
+            errors,
+            vec![
+                DataError::Point {
+                    file: "nobles".to_string(),
+                    line: 2,
+                    id: 1,
+                    point: 9,
+                },
+                DataError::NobleTileCount {
+                    file: "nobles".to_string(),
+                    expected: 10,
+                    actual: 1,
+                },
+            ]
+        );
+    }
+}
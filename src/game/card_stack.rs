@@ -1,55 +1,83 @@
+use crate::game::card_catalog::CardCatalog;
 use crate::game::color::Color;
+use crate::game::gem::Gem;
 use crate::game::level::Level;
 
+use rand::seq::SliceRandom;
+use rand::Rng;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+mod affordability;
 mod card;
 
-#[derive(Serialize, Deserialize, Clone)]
+// id は data/card.json で割り振った CardCatalog の添字
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(from = "CardRecord", into = "CardRecord")]
 pub struct Card {
+    pub id: u8,
     pub level: u8,
     pub color: Color,
     pub point: u8,
-    pub cost_black: u8,
-    pub cost_white: u8,
-    pub cost_red: u8,
-    pub cost_blue: u8,
-    pub cost_green: u8,
+    pub cost: Gem,
 }
 
+// data/card.json の 1行分
+#[derive(Serialize, Deserialize, Clone)]
+struct CardRecord {
+    id: u8,
+    level: u8,
+    color: Color,
+    point: u8,
+    cost_black: u8,
+    cost_white: u8,
+    cost_red: u8,
+    cost_blue: u8,
+    cost_green: u8,
+}
+
+// ユーザーがカードを購入するのに足りないもの
+// shortfall はボーナスと宝石トークンを使った後の色ごとの不足で、金トークンで補える
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Affordability {
+    pub shortfall: Gem,
+    pub gold: u8,
+}
+
+// 山札はカードの id で持つ
 #[derive(Clone)]
-pub struct CardStack(HashMap<Level, Vec<Card>>);
+pub struct CardStack(HashMap<Level, Vec<u8>>);
 
-impl CardStack {
-    pub fn new(file_path: Option<&'static str>) -> CardStack {
-        let mut level1_stack = vec![];
-        let mut level2_stack = vec![];
-        let mut level3_stack = vec![];
+impl Default for CardStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        if let Some(file_path) = file_path {
-            let cards = Card::load(file_path);
-            for card in cards.into_iter() {
-                match card {
-                    Card { level: 1, .. } => level1_stack.push(card),
-                    Card { level: 2, .. } => level2_stack.push(card),
-                    Card { level: 3, .. } => level3_stack.push(card),
-                    Card { level: _, .. } => unreachable!(),
-                }
-            }
+impl CardStack {
+    pub fn new() -> CardStack {
+        CardStack::with_rng(&mut rand::thread_rng())
+    }
 
-            // シャッフルする
-            use rand::seq::SliceRandom;
-            let mut rng = rand::thread_rng();
-            level1_stack.shuffle(&mut rng);
-            level2_stack.shuffle(&mut rng);
-            level3_stack.shuffle(&mut rng);
+    // CardCatalog の全てのカードをレベルごとにシャッフルして積む
+    pub fn with_rng<R: Rng + ?Sized>(rng: &mut R) -> CardStack {
+        let catalog = CardCatalog::global();
+        let mut stack = HashMap::new();
+        for level in [Level::One, Level::Two, Level::Three].iter() {
+            let mut ids = catalog.card_ids(*level);
+            ids.shuffle(rng);
+            stack.insert(*level, ids);
         }
 
+        CardStack(stack)
+    }
+
+    // 山札の中身を id で指定する (最後の要素が次に引かれるカード)
+    pub(crate) fn from_ids(level1: Vec<u8>, level2: Vec<u8>, level3: Vec<u8>) -> CardStack {
         let mut stack = HashMap::new();
-        stack.insert(Level::One, level1_stack);
-        stack.insert(Level::Two, level2_stack);
-        stack.insert(Level::Three, level3_stack);
+        stack.insert(Level::One, level1);
+        stack.insert(Level::Two, level2);
+        stack.insert(Level::Three, level3);
 
         CardStack(stack)
     }
@@ -58,7 +86,13 @@ impl CardStack {
         self.0.get(&level).unwrap().len() as u8
     }
 
-    pub fn get(&mut self, level: Level) -> Option<Card> {
+    // 山札のカードの id を下から順に返す (最後の要素が次に引かれるカード)
+    pub fn peek(&self, level: Level) -> &[u8] {
+        self.0.get(&level).unwrap()
+    }
+
+    // 引いたカードの id を返す
+    pub fn get(&mut self, level: Level) -> Option<u8> {
         self.0.get_mut(&level).unwrap().pop()
     }
 }
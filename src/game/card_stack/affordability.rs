@@ -0,0 +1,161 @@
+use super::Affordability;
+use crate::game::gem::Gem;
+use crate::game::locale::{message, text};
+
+use std::collections::HashMap;
+use std::fmt;
+
+impl fmt::Display for Affordability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_affordable() {
+            return f.write_str(text("affordability.affordable"));
+        }
+        let shortfall: Vec<String> = self
+            .shortfall
+            .iter()
+            .filter(|(_, n)| *n > 0)
+            .map(|(color, n)| message("affordability.missing", &[&color, &n]))
+            .collect();
+        let shortfall = shortfall.join(text("affordability.separator"));
+        f.write_str(&message("affordability.need", &[&shortfall]))?;
+        if self.gold > 0 {
+            let gold = self.gold.min(self.gold_needed());
+            f.write_str(&message("affordability.gold", &[&gold]))?;
+        }
+        f.write_str(&message("affordability.turns", &[&self.turns()]))
+    }
+}
+
+impl Affordability {
+    // 不足を全て金トークンで払う場合に必要な数
+    pub fn gold_needed(&self) -> u8 {
+        self.shortfall.total()
+    }
+
+    // 持っている金トークンでも足りない数
+    pub fn gold_missing(&self) -> u8 {
+        self.gold_needed().saturating_sub(self.gold)
+    }
+
+    pub fn is_affordable(&self) -> bool {
+        self.gold_missing() == 0
+    }
+
+    // トークンを取る手番だけで購入できるようになるまでの最短の手番数
+    // 場のトークンの残りや所持数の上限は考えない
+    // (確保して金トークンを得る 1個より、同じ色を 2個取るほうが常に多く減らせる)
+    pub fn turns(&self) -> u8 {
+        let mut memo = HashMap::new();
+        Affordability::search(self.shortfall, self.gold, &mut memo)
+    }
+
+    fn search(shortfall: Gem, gold: u8, memo: &mut HashMap<Gem, u8>) -> u8 {
+        if shortfall.total() <= gold {
+            return 0;
+        }
+        if let Some(turns) = memo.get(&shortfall) {
+            return *turns;
+        }
+
+        let mut best = u8::MAX;
+        let colors: Vec<_> = shortfall.iter().filter(|(_, n)| *n > 0).collect();
+
+        // 同じ色を 2個
+        for (color, n) in colors.iter() {
+            let mut next = shortfall;
+            next.set(*color, n.saturating_sub(2));
+            best = best.min(Affordability::search(next, gold, memo));
+        }
+        // 違う色を 3個 (足りない色が 3色未満なら足りない色だけ)
+        let size = colors.len().min(3);
+        for combination in Affordability::combinations(colors.len(), size) {
+            let mut next = shortfall;
+            for index in combination {
+                let (color, n) = colors[index];
+                next.set(color, n - 1);
+            }
+            best = best.min(Affordability::search(next, gold, memo));
+        }
+
+        let turns = best.saturating_add(1);
+        memo.insert(shortfall, turns);
+        turns
+    }
+
+    // 0..n から size 個を選ぶ組み合わせ
+    fn combinations(n: usize, size: usize) -> Vec<Vec<usize>> {
+        if size == 0 {
+            return vec![vec![]];
+        }
+        let mut result = vec![];
+        for first in 0..n {
+            for mut rest in Affordability::combinations(n - first - 1, size - 1) {
+                for index in rest.iter_mut() {
+                    *index += first + 1;
+                }
+                rest.insert(0, first);
+                result.push(rest);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::card_stack::Card;
+    use crate::game::color::Color::*;
+    use crate::game::gem::Gem;
+    use crate::game::locale::{message, text};
+    use crate::game::token_stack::Token;
+    use crate::game::user::User;
+
+    #[test]
+    fn test_affordability() {
+        let card = Card {
+            cost: Gem::from([0, 0, 3, 2, 0]),
+            ..Default::default()
+        };
+        let mut user = User::new(1);
+        user.add_token(Token::new(Red));
+        user.add_token(Token::new(Gold));
+
+        let affordability = card.affordability(&user);
+        assert_eq!(affordability.shortfall, Gem::from([0, 0, 2, 2, 0]));
+        assert_eq!(affordability.gold_needed(), 4);
+        assert_eq!(affordability.gold_missing(), 3);
+        assert!(!affordability.is_affordable());
+        // 赤と青を 1個ずつ取る手番を 1回、青を 2個取る手番を 1回 (残りの赤 1個は金で払う)
+        assert_eq!(affordability.turns(), 2);
+        let shortfall = [
+            message("affordability.missing", &[&Red, &2]),
+            message("affordability.missing", &[&Blue, &2]),
+        ]
+        .join(text("affordability.separator"));
+        let expected = [
+            message("affordability.need", &[&shortfall]),
+            message("affordability.gold", &[&1]),
+            message("affordability.turns", &[&2]),
+        ]
+        .concat();
+        assert_eq!(affordability.to_string(), expected);
+    }
+
+    #[test]
+    fn test_turns() {
+        let card = Card {
+            cost: Gem::from([1, 1, 1, 0, 4]),
+            ..Default::default()
+        };
+        let user = User::new(1);
+        let affordability = card.affordability(&user);
+
+        // 3色を 1個ずつ、緑を 2個ずつ 2回
+        assert_eq!(affordability.turns(), 3);
+        assert_eq!(Card::default().affordability(&user).turns(), 0);
+        assert_eq!(
+            Card::default().affordability(&user).to_string(),
+            text("affordability.affordable")
+        );
+    }
+}
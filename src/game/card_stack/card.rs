@@ -1,88 +1,97 @@
-use super::Card;
+use super::{Affordability, Card, CardRecord};
+use crate::game::card_catalog::{CardCatalog, DataError};
 use crate::game::color::Color;
 use crate::game::color::Color::*;
-use crate::game::gem::{Gem, GEMS};
+use crate::game::gem::Gem;
+use crate::game::locale::message;
 use crate::game::user::User;
 
 use std::fmt;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
 
 impl fmt::Debug for Card {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Lv: {} {} {}点 {} {} {} {} {}",
-            self.level,
-            self.color,
-            self.point,
-            self.cost_black,
-            self.cost_white,
-            self.cost_red,
-            self.cost_blue,
-            self.cost_green,
+            "#{} L{} {:?} {}vp ({:?})",
+            self.id, self.level, self.color, self.point, self.cost
         )
     }
 }
 
 impl fmt::Display for Card {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "Lv: {} {} {}点 {} {} {} {} {}",
-            self.level,
-            self.color,
-            self.point,
-            self.cost_black,
-            self.cost_white,
-            self.cost_red,
-            self.cost_blue,
-            self.cost_green,
-        )
+        let cost = format!("{:?}", self.cost);
+        f.write_str(&message(
+            "card.display",
+            &[&self.id, &self.level, &self.color, &self.point, &cost],
+        ))
     }
 }
 
 impl Default for Card {
     fn default() -> Self {
         Card {
+            id: 0,
             level: 0,
             color: Color::Black,
             point: 0,
-            cost_black: 0,
-            cost_white: 0,
-            cost_red: 0,
-            cost_blue: 0,
-            cost_green: 0,
+            cost: Gem::new(),
         }
     }
 }
 
-impl Card {
-    pub fn load(file_name: &str) -> Vec<Card> {
-        let mut cards: Vec<Card> = vec![];
-        for result in BufReader::new(File::open(file_name).unwrap()).lines() {
-            let l = result.unwrap();
-            cards.push(serde_json::from_str(&l).unwrap());
+impl From<CardRecord> for Card {
+    fn from(record: CardRecord) -> Card {
+        Card {
+            id: record.id,
+            level: record.level,
+            color: record.color,
+            point: record.point,
+            cost: Gem::from([
+                record.cost_black,
+                record.cost_white,
+                record.cost_red,
+                record.cost_blue,
+                record.cost_green,
+            ]),
+        }
+    }
+}
+
+impl From<Card> for CardRecord {
+    fn from(card: Card) -> CardRecord {
+        CardRecord {
+            id: card.id,
+            level: card.level,
+            color: card.color,
+            point: card.point,
+            cost_black: card.cost.get(Black),
+            cost_white: card.cost.get(White),
+            cost_red: card.cost.get(Red),
+            cost_blue: card.cost.get(Blue),
+            cost_green: card.cost.get(Green),
         }
+    }
+}
 
-        cards
+impl Card {
+    // 値の範囲は検証しない (CardCatalog::validate を使う)
+    pub fn load(file_name: &str) -> Result<Vec<Card>, DataError> {
+        CardCatalog::read_records(file_name)
     }
     pub fn get_point(&self) -> u8 {
         self.point
     }
+    // ボーナスとトークンで足りない分を金トークンで払えるか
     pub fn is_available(&self, user: &User) -> bool {
-        let gems = user.get_jewelries();
-
-        let mut gold_token = user.get_number_of_tokens(Color::Gold) as i8;
+        self.affordability(user).is_affordable()
+    }
 
-        for color in GEMS.iter() {
-            let token = user.get_number_of_tokens(*color);
-            let gems = gems.get(*color);
-            let cost = self.get_cost(*color);
-            self.estimate_gold_token(token + gems, cost, &mut gold_token);
+    pub fn affordability(&self, user: &User) -> Affordability {
+        Affordability {
+            shortfall: self.cost.saturating_sub(&user.get_owned_gems()),
+            gold: user.get_number_of_tokens(Gold),
         }
-
-        gold_token >= 0
     }
 
     pub fn get_color(&self) -> Color {
@@ -90,42 +99,41 @@ impl Card {
     }
 
     pub fn get_cost(&self, color: Color) -> u8 {
-        match color {
-            Black => self.cost_black,
-            White => self.cost_white,
-            Red => self.cost_red,
-            Blue => self.cost_blue,
-            Green => self.cost_green,
-            Gold => unreachable!(),
-        }
-    }
-
-    fn estimate_gold_token(&self, user_token: u8, card_cost: u8, gold_token: &mut i8) {
-        if user_token < card_cost {
-            let diff = card_cost - user_token;
-            *gold_token -= diff as i8;
-        }
+        self.cost.get(color)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Card;
+    use crate::game::color::Color::*;
+    use crate::game::gem::Gem;
+    use crate::game::token_stack::Token;
+    use crate::game::user::User;
 
     #[test]
-    fn test_estimate_gold_token() {
-        let card: Card = Default::default();
-        let mut gold = 0;
-        card.estimate_gold_token(0, 0, &mut gold);
-        assert_eq!(gold, 0);
-
-        let mut gold = 0;
-        card.estimate_gold_token(0, 2, &mut gold);
-        assert_eq!(gold, -2);
-
-        let mut gold = 2;
-        card.estimate_gold_token(1, 2, &mut gold);
-        assert_eq!(gold, 1);
+    fn test_is_available() {
+        let card = Card {
+            cost: Gem::from([2, 1, 0, 0, 0]),
+            ..Default::default()
+        };
+        let mut user = User::new(1);
+        assert!(!card.is_available(&user));
+
+        user.add_token(Token::new(Black));
+        user.add_token(Token::new(White));
+        assert!(!card.is_available(&user));
+
+        user.add_token(Token::new(Gold));
+        assert!(card.is_available(&user));
     }
 
+    #[test]
+    fn test_serde() {
+        let json = r#"{"id":3,"level":1,"color":"Red","point":1,"cost_black":0,"cost_white":2,"cost_red":0,"cost_blue":3,"cost_green":0}"#;
+        let card: Card = serde_json::from_str(json).unwrap();
+        assert_eq!(card.id, 3);
+        assert_eq!(card.cost, Gem::from([0, 2, 0, 3, 0]));
+        assert_eq!(serde_json::to_string(&card).unwrap(), json);
+    }
 }
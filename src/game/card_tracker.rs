@@ -0,0 +1,182 @@
+use crate::game::card_catalog::CardCatalog;
+use crate::game::card_stack::Card;
+use crate::game::color::Color;
+use crate::game::gem::GEMS;
+use crate::game::level::{Level, LEVELS};
+use crate::game::locale::message;
+use crate::game::observation::Observation;
+
+use std::fmt;
+
+// seat のユーザーから見て、まだ見ていないカードをレベルごとに数える
+// 一度見たカードは見えなくなっても見たものとして扱う
+// 見ていないカードは山札か、他のユーザーが伏せたまま確保したカードのどちらか
+pub struct CardTracker {
+    seat: usize,
+    unseen: [Vec<u8>; 3],
+    hidden: [u8; 3],
+}
+
+impl fmt::Display for CardTracker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for level in LEVELS.iter().rev() {
+            let colors: Vec<String> = GEMS
+                .iter()
+                .map(|color| {
+                    format!(
+                        "{} {:.0}%",
+                        color,
+                        self.color_probability(*level, *color) * 100.0
+                    )
+                })
+                .collect();
+            writeln!(
+                f,
+                "{}",
+                message(
+                    "card_tracker.next",
+                    &[
+                        &(*level as u8 + 1),
+                        &self.deck_size(*level),
+                        &self.hidden(*level),
+                        &colors.join(", ")
+                    ]
+                )
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl CardTracker {
+    pub fn new(seat: usize) -> CardTracker {
+        let catalog = CardCatalog::global();
+        CardTracker {
+            seat,
+            unseen: [
+                catalog.card_ids(Level::One),
+                catalog.card_ids(Level::Two),
+                catalog.card_ids(Level::Three),
+            ],
+            hidden: [0; 3],
+        }
+    }
+
+    pub fn get_seat(&self) -> usize {
+        self.seat
+    }
+
+    // 手番ごとに seat のユーザーの観測を渡す
+    // 見ていないカードは Observation::unseen と同じ数え方で、一度見たカードは戻さない
+    pub fn observe(&mut self, observation: &Observation) {
+        debug_assert_eq!(observation.get_seat(), self.seat);
+
+        for level in LEVELS.iter() {
+            let unseen = observation.unseen(*level);
+            self.unseen[*level as usize].retain(|id| unseen.contains(id));
+            self.hidden[*level as usize] = observation.hidden(*level);
+        }
+    }
+
+    // まだ見ていないカードの id (id の順)
+    pub fn unseen(&self, level: Level) -> &[u8] {
+        &self.unseen[level as usize]
+    }
+
+    // 他のユーザーが伏せたまま確保している level のカードの枚数
+    pub fn hidden(&self, level: Level) -> u8 {
+        self.hidden[level as usize]
+    }
+
+    // 見ていないカードのうち伏せたカードを除いた枚数
+    pub fn deck_size(&self, level: Level) -> u8 {
+        self.unseen(level).len() as u8 - self.hidden(level)
+    }
+
+    // level の山札から次に引くカードが predicate を満たす確率
+    // 伏せたカードがどれかはわからないので、見ていないカードはどれも同じ確率で山札の一番上にある
+    pub fn probability<F: Fn(&Card) -> bool>(&self, level: Level, predicate: F) -> f32 {
+        let unseen = self.unseen(level);
+        if self.deck_size(level) == 0 {
+            return 0.0;
+        }
+        let catalog = CardCatalog::global();
+        let matched = unseen
+            .iter()
+            .filter(|id| predicate(catalog.card(**id)))
+            .count();
+        matched as f32 / unseen.len() as f32
+    }
+
+    pub fn color_probability(&self, level: Level, color: Color) -> f32 {
+        self.probability(level, |card| card.color == color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CardTracker;
+    use crate::game::board::Board;
+    use crate::game::card_catalog::CardCatalog;
+    use crate::game::color::Color;
+    use crate::game::game_command::GameCommand;
+    use crate::game::gem::GEMS;
+    use crate::game::level::Level;
+    use crate::game::locale::message;
+    use crate::game::observation::Observation;
+    use crate::game::user::User;
+
+    #[test]
+    fn test_observe() {
+        let mut users = vec![User::new(0), User::new(1)];
+        let mut board = Board::new();
+        let mut tracker = CardTracker::new(0);
+        tracker.observe(&Observation::new(&users, &board, 0));
+        assert_eq!(tracker.unseen(Level::Two).len(), 26);
+        assert_eq!(tracker.deck_size(Level::Three), 16);
+
+        let hidden = *board.peek_stack(Level::Two).last().unwrap();
+        GameCommand::reserve_stack_card(Level::Two, &mut users[1], &mut board).unwrap();
+        tracker.observe(&Observation::new(&users, &board, 0));
+        assert!(tracker.unseen(Level::Two).contains(&hidden));
+        assert_eq!(tracker.hidden(Level::Two), 1);
+        assert_eq!(tracker.deck_size(Level::Two), 25);
+
+        // 場のカードを確保すると、補充されたカードも見たものになる
+        let id = board.peek_card_id(2, 0).unwrap();
+        GameCommand::reserve_development_card(2, 0, &mut users[0], &mut board).unwrap();
+        tracker.observe(&Observation::new(&users, &board, 0));
+        assert!(!tracker.unseen(Level::One).contains(&id));
+        assert_eq!(tracker.unseen(Level::One).len(), 35);
+    }
+
+    #[test]
+    fn test_probability() {
+        let users = vec![User::new(0), User::new(1)];
+        let board = Board::new();
+        let mut tracker = CardTracker::new(1);
+        tracker.observe(&Observation::new(&users, &board, 1));
+
+        for level in [Level::One, Level::Two, Level::Three].iter() {
+            let sum: f32 = GEMS
+                .iter()
+                .map(|color| tracker.color_probability(*level, *color))
+                .sum();
+            assert!((sum - 1.0).abs() < 1e-5);
+        }
+
+        let catalog = CardCatalog::global();
+        let green = tracker
+            .unseen(Level::Two)
+            .iter()
+            .filter(|id| catalog.card(**id).color == Color::Green)
+            .count();
+        assert_eq!(
+            tracker.color_probability(Level::Two, Color::Green),
+            green as f32 / 26.0
+        );
+        // 色ごとの確率の前までが一致する
+        let header = message("card_tracker.next", &[&2, &26, &0, &""]);
+        assert!(tracker.to_string().contains(&header));
+    }
+}
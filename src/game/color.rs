@@ -1,7 +1,9 @@
+use crate::game::locale::text;
+
 use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Hash, Copy)]
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Hash, Copy, Debug)]
 pub enum Color {
     Black,
     White,
@@ -11,28 +13,16 @@ pub enum Color {
     Gold,
 }
 
-impl fmt::Debug for Color {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Color::Black => write!(f, "黒"),
-            Color::White => write!(f, "白"),
-            Color::Red => write!(f, "赤"),
-            Color::Blue => write!(f, "青"),
-            Color::Green => write!(f, "緑"),
-            Color::Gold => write!(f, "金"),
-        }
-    }
-}
-
 impl fmt::Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Color::Black => write!(f, "黒"),
-            Color::White => write!(f, "白"),
-            Color::Red => write!(f, "赤"),
-            Color::Blue => write!(f, "青"),
-            Color::Green => write!(f, "緑"),
-            Color::Gold => write!(f, "金"),
-        }
+        let key = match self {
+            Color::Black => "color.black",
+            Color::White => "color.white",
+            Color::Red => "color.red",
+            Color::Blue => "color.blue",
+            Color::Green => "color.green",
+            Color::Gold => "color.gold",
+        };
+        f.write_str(text(key))
     }
 }
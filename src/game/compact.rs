@@ -0,0 +1,50 @@
+use crate::game::board::COORDINATE;
+use crate::game::encoder::MAX_NUMBER_OF_USERS;
+use crate::game::user::MAX_NUMBER_OF_HANDS;
+
+pub use crate::game::card_catalog::EMPTY;
+
+mod compact_state;
+
+const MAX_DECK_SIZE: usize = 40;
+const MAX_NUMBER_OF_NOBLE_TILES: usize = 5;
+const NUMBER_OF_TOKEN_COLORS: usize = 6;
+
+// ヒープを使わない固定長のユーザー
+// 取得したカードは色ごとの枚数(ボーナス)と合計枚数だけを持つ
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CompactUser {
+    pub tokens: [u8; NUMBER_OF_TOKEN_COLORS],
+    pub bonuses: [u8; 5],
+    pub vp: u8,
+    pub cards: u8,
+    pub hand: [u8; MAX_NUMBER_OF_HANDS],
+}
+
+// 探索用の Copy できる局面
+// カードと貴族タイルは CardCatalog の id で持つ
+// 山札は下から順に並べ、deck_lens の位置より下が残りのカード
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CompactState {
+    grid: [u8; COORDINATE.len()],
+    decks: [[u8; MAX_DECK_SIZE]; 3],
+    deck_lens: [u8; 3],
+    tokens: [u8; NUMBER_OF_TOKEN_COLORS],
+    noble_tiles: [u8; MAX_NUMBER_OF_NOBLE_TILES],
+    number_of_noble_tiles: u8,
+    users: [CompactUser; MAX_NUMBER_OF_USERS],
+    number_of_users: u8,
+    seat: u8,
+}
+
+// apply で変わった部分を元に戻すための記録
+#[derive(Clone, Copy, Debug)]
+pub struct Undo {
+    user: CompactUser,
+    tokens: [u8; NUMBER_OF_TOKEN_COLORS],
+    slot: Option<(u8, u8)>,
+    deck: Option<(u8, u8)>,
+    noble_tiles: [u8; MAX_NUMBER_OF_NOBLE_TILES],
+    number_of_noble_tiles: u8,
+    seat: u8,
+}
@@ -0,0 +1,447 @@
+use super::{CompactState, CompactUser, Undo, EMPTY, MAX_DECK_SIZE, MAX_NUMBER_OF_NOBLE_TILES};
+use crate::game::board::{Board, COORDINATE};
+use crate::game::card_catalog::CardCatalog;
+use crate::game::color::Color::*;
+use crate::game::encoder::MAX_NUMBER_OF_USERS;
+use crate::game::game_command::NUMBER_OF_COMMANDS;
+use crate::game::level::LEVELS;
+use crate::game::user::{User, MAX_NUMBER_OF_HANDS};
+use crate::game::VP_TO_END;
+
+const GOLD: usize = 5;
+const MAX_NUMBER_OF_TOKENS: u8 = 10;
+const LIMIT_OF_GETTING_SAME_TOKEN: u8 = 4;
+// GameCommand::to_command の SelectThreeTokens と同じ並び
+const COLOR_SETS: [[usize; 3]; 10] = [
+    [0, 1, 2],
+    [0, 1, 3],
+    [0, 1, 4],
+    [0, 2, 3],
+    [0, 2, 4],
+    [0, 3, 4],
+    [1, 2, 3],
+    [1, 2, 4],
+    [1, 3, 4],
+    [2, 3, 4],
+];
+
+impl CompactUser {
+    fn new() -> CompactUser {
+        CompactUser {
+            tokens: [0; 6],
+            bonuses: [0; 5],
+            vp: 0,
+            cards: 0,
+            hand: [EMPTY; MAX_NUMBER_OF_HANDS],
+        }
+    }
+
+    fn number_of_tokens(&self) -> u8 {
+        self.tokens.iter().sum()
+    }
+
+    fn number_of_hands(&self) -> usize {
+        self.hand.iter().take_while(|id| **id != EMPTY).count()
+    }
+
+    // ボーナスと宝石トークンで足りない分を金トークンで払えるか
+    fn can_buy(&self, cost: &[u8; 5]) -> bool {
+        let shortfall: u8 = (0..5)
+            .map(|i| {
+                cost[i]
+                    .saturating_sub(self.bonuses[i])
+                    .saturating_sub(self.tokens[i])
+            })
+            .sum();
+        shortfall <= self.tokens[GOLD]
+    }
+
+    // 支払ったトークンは tokens(場のトークン)に戻す
+    fn pay(&mut self, cost: &[u8; 5], tokens: &mut [u8; 6]) {
+        for i in 0..5 {
+            let need = cost[i].saturating_sub(self.bonuses[i]);
+            let paid = need.min(self.tokens[i]);
+            self.tokens[i] -= paid;
+            tokens[i] += paid;
+            self.tokens[GOLD] -= need - paid;
+            tokens[GOLD] += need - paid;
+        }
+    }
+}
+
+impl CompactState {
+    // users[seat] が手番の局面に変換する
+    pub fn from_game(users: &[User], board: &Board, seat: usize) -> CompactState {
+        let mut state = CompactState {
+            grid: [EMPTY; COORDINATE.len()],
+            decks: [[EMPTY; MAX_DECK_SIZE]; 3],
+            deck_lens: [0; 3],
+            tokens: [0; 6],
+            noble_tiles: [EMPTY; MAX_NUMBER_OF_NOBLE_TILES],
+            number_of_noble_tiles: 0,
+            users: [CompactUser::new(); MAX_NUMBER_OF_USERS],
+            number_of_users: users.len().min(MAX_NUMBER_OF_USERS) as u8,
+            seat: seat as u8,
+        };
+
+        for (slot, (x, y)) in COORDINATE.iter().enumerate() {
+            state.grid[slot] = board.peek_card_id(*x, *y).unwrap_or(EMPTY);
+        }
+        for (level, deck) in LEVELS.iter().enumerate() {
+            let ids = board.peek_stack(*deck);
+            let len = ids.len().min(MAX_DECK_SIZE);
+            state.decks[level][..len].copy_from_slice(&ids[..len]);
+            state.deck_lens[level] = len as u8;
+        }
+        for (i, color) in [Black, White, Red, Blue, Green, Gold].iter().enumerate() {
+            state.tokens[i] = board.get_number_of_tokens(*color);
+        }
+        for tile in board
+            .peek_noble_tile()
+            .iter()
+            .take(MAX_NUMBER_OF_NOBLE_TILES)
+        {
+            state.noble_tiles[state.number_of_noble_tiles as usize] = tile.get_id();
+            state.number_of_noble_tiles += 1;
+        }
+
+        for (compact, user) in state.users.iter_mut().zip(users.iter()) {
+            for (i, color) in [Black, White, Red, Blue, Green, Gold].iter().enumerate() {
+                compact.tokens[i] = user.get_number_of_tokens(*color);
+            }
+            for card in user.get_acquired_cards() {
+                compact.bonuses[card.get_color() as usize] += 1;
+            }
+            compact.vp = user.get_vp();
+            compact.cards = user.get_number_of_acquired_cards() as u8;
+            for (order, id) in user.get_hand_ids().iter().enumerate() {
+                compact.hand[order] = *id;
+            }
+        }
+
+        state
+    }
+
+    pub fn get_seat(&self) -> usize {
+        self.seat as usize
+    }
+
+    pub fn get_user(&self, seat: usize) -> &CompactUser {
+        &self.users[seat]
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.users[..self.number_of_users as usize]
+            .iter()
+            .any(|user| user.vp >= VP_TO_END)
+    }
+
+    // 実行できるコマンド番号(GameCommand::to_command)の一覧
+    pub fn legal_actions(&self) -> Vec<u8> {
+        (0..NUMBER_OF_COMMANDS)
+            .filter(|action| {
+                let mut state = *self;
+                state.apply(*action).is_some()
+            })
+            .collect()
+    }
+
+    // 手番のユーザーがコマンド番号 action を実行し、手番を次のユーザーに移す
+    // Turn::play と同じ規則で、上限を超えたトークンを捨て、条件を満たした貴族タイルを 1つ訪問させる
+    // 実行できなければ何も変えずに None を返す
+    pub fn apply(&mut self, action: u8) -> Option<Undo> {
+        let catalog = CardCatalog::global();
+        let seat = self.seat as usize;
+        let mut undo = Undo {
+            user: self.users[seat],
+            tokens: self.tokens,
+            slot: None,
+            deck: None,
+            noble_tiles: self.noble_tiles,
+            number_of_noble_tiles: self.number_of_noble_tiles,
+            seat: self.seat,
+        };
+
+        match action {
+            0..=11 => {
+                let slot = action as usize;
+                let id = self.grid[slot];
+                if self.users[seat].number_of_hands() >= MAX_NUMBER_OF_HANDS || id == EMPTY {
+                    return None;
+                }
+                let hands = self.users[seat].number_of_hands();
+                self.users[seat].hand[hands] = id;
+                self.refill(slot, &mut undo);
+                self.take_gold(seat);
+            }
+            12..=23 => {
+                let slot = action as usize - 12;
+                let id = self.grid[slot];
+                if id == EMPTY || !self.users[seat].can_buy(&catalog.card(id).cost.to_array()) {
+                    return None;
+                }
+                self.buy(seat, id);
+                self.refill(slot, &mut undo);
+            }
+            24..=28 => {
+                let color = action as usize - 24;
+                if self.tokens[color] < LIMIT_OF_GETTING_SAME_TOKEN {
+                    return None;
+                }
+                self.take_token(seat, color);
+                self.take_token(seat, color);
+            }
+            29..=38 => {
+                let mut count = 0;
+                for color in COLOR_SETS[action as usize - 29].iter() {
+                    if self.tokens[*color] > 0 {
+                        self.take_token(seat, *color);
+                        count += 1;
+                    }
+                }
+                if count == 0 {
+                    return None;
+                }
+            }
+            39..=41 => {
+                let level = action as usize - 39;
+                let hands = self.users[seat].number_of_hands();
+                if hands >= MAX_NUMBER_OF_HANDS || self.deck_lens[level] == 0 {
+                    return None;
+                }
+                self.users[seat].hand[hands] = self.draw(level, &mut undo);
+                self.take_gold(seat);
+            }
+            42..=44 => {
+                let order = action as usize - 42;
+                let id = self.users[seat].hand[order];
+                if id == EMPTY || !self.users[seat].can_buy(&catalog.card(id).cost.to_array()) {
+                    return None;
+                }
+                self.buy(seat, id);
+                let hand = &mut self.users[seat].hand;
+                for i in order..MAX_NUMBER_OF_HANDS - 1 {
+                    hand[i] = hand[i + 1];
+                }
+                hand[MAX_NUMBER_OF_HANDS - 1] = EMPTY;
+            }
+            _ => return None,
+        }
+
+        self.discard(seat);
+        self.visit(seat);
+        self.seat = ((seat + 1) % self.number_of_users as usize) as u8;
+        Some(undo)
+    }
+
+    // apply の直前の局面に戻す
+    pub fn undo(&mut self, undo: Undo) {
+        let seat = undo.seat as usize;
+        self.users[seat] = undo.user;
+        self.tokens = undo.tokens;
+        if let Some((slot, id)) = undo.slot {
+            self.grid[slot as usize] = id;
+        }
+        if let Some((level, id)) = undo.deck {
+            self.decks[level as usize][self.deck_lens[level as usize] as usize] = id;
+            self.deck_lens[level as usize] += 1;
+        }
+        self.noble_tiles = undo.noble_tiles;
+        self.number_of_noble_tiles = undo.number_of_noble_tiles;
+        self.seat = undo.seat;
+    }
+
+    // 場のカードを同じ段の山札から補充する
+    // 山札が空のときは Board と同じく空きになる
+    fn refill(&mut self, slot: usize, undo: &mut Undo) {
+        // 段は上からレベル 3, 2, 1
+        let level = 2 - slot / 4;
+        undo.slot = Some((slot as u8, self.grid[slot]));
+        self.grid[slot] = if self.deck_lens[level] == 0 {
+            EMPTY
+        } else {
+            self.draw(level, undo)
+        };
+    }
+
+    // 山札の一番上を引く
+    // 同じ局面が同じ値になるように、引いた場所は EMPTY にする
+    fn draw(&mut self, level: usize, undo: &mut Undo) -> u8 {
+        self.deck_lens[level] -= 1;
+        let top = self.deck_lens[level] as usize;
+        let id = self.decks[level][top];
+        self.decks[level][top] = EMPTY;
+        undo.deck = Some((level as u8, id));
+        id
+    }
+
+    fn buy(&mut self, seat: usize, id: u8) {
+        let card = CardCatalog::global().card(id);
+        let user = &mut self.users[seat];
+        user.pay(&card.cost.to_array(), &mut self.tokens);
+        user.bonuses[card.color as usize] += 1;
+        user.vp += card.point;
+        user.cards += 1;
+    }
+
+    // 上限を超えたトークンを Turn::default_discard と同じ選び方で 1個ずつ場に戻す
+    fn discard(&mut self, seat: usize) {
+        while self.users[seat].number_of_tokens() > MAX_NUMBER_OF_TOKENS {
+            let tokens = &self.users[seat].tokens;
+            let color = (0..GOLD)
+                .max_by_key(|color| tokens[*color])
+                .filter(|color| tokens[*color] > 0)
+                .unwrap_or(GOLD);
+            self.users[seat].tokens[color] -= 1;
+            self.tokens[color] += 1;
+        }
+    }
+
+    // 条件を満たした貴族タイルのうち、場の並びで最初の 1つだけが訪問する
+    fn visit(&mut self, seat: usize) {
+        let catalog = CardCatalog::global();
+        let len = self.number_of_noble_tiles as usize;
+        let user = &mut self.users[seat];
+        let order = self.noble_tiles[..len].iter().position(|id| {
+            let bonus = catalog.noble_tile(*id).get_required().to_array();
+            (0..5).all(|i| user.bonuses[i] >= bonus[i])
+        });
+        if let Some(order) = order {
+            user.vp += catalog.noble_tile(self.noble_tiles[order]).get_point();
+            self.noble_tiles.copy_within(order + 1..len, order);
+            self.noble_tiles[len - 1] = EMPTY;
+            self.number_of_noble_tiles -= 1;
+        }
+    }
+
+    fn take_token(&mut self, seat: usize, color: usize) {
+        self.tokens[color] -= 1;
+        self.users[seat].tokens[color] += 1;
+    }
+
+    fn take_gold(&mut self, seat: usize) {
+        if self.tokens[GOLD] > 0 {
+            self.take_token(seat, GOLD);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::board::Board;
+    use crate::game::card_catalog::CardCatalog;
+    use crate::game::color::Color::*;
+    use crate::game::compact::CompactState;
+    use crate::game::game_command::GameCommand;
+    use crate::game::level::Level;
+    use crate::game::turn::Turn;
+    use crate::game::user::User;
+
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    // ランダムな対局で、Board と User に対する実行結果と一致することを確かめる
+    #[test]
+    fn test_apply() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..5 {
+            let mut board = Board::with_rng(&mut rng);
+            let mut users = vec![User::new(0), User::new(1)];
+            let mut state = CompactState::from_game(&users, &board, 0);
+
+            for turn in 0..200 {
+                let seat = turn % users.len();
+                let actions = state.legal_actions();
+                let action = match actions.choose(&mut rng) {
+                    Some(action) => *action,
+                    None => break,
+                };
+                let command = GameCommand::to_command(action);
+                assert!(Turn::play(command, &mut users[seat], &mut board).is_ok());
+                state.apply(action).unwrap();
+
+                let expected = CompactState::from_game(&users, &board, state.get_seat());
+                assert_eq!(state, expected);
+                if state.is_over() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_undo() {
+        // 先頭のコマンドばかり選ぶと合法手がなくなる局面もあるので、盤面は固定する
+        let users = vec![User::new(0), User::new(1)];
+        let board = Board::with_rng(&mut StdRng::seed_from_u64(7));
+        let mut state = CompactState::from_game(&users, &board, 0);
+
+        let mut undos = vec![];
+        let mut history = vec![state];
+        for _ in 0..20 {
+            let action = state.legal_actions()[0];
+            undos.push(state.apply(action).unwrap());
+            history.push(state);
+        }
+        history.pop();
+        while let Some(undo) = undos.pop() {
+            state.undo(undo);
+            assert_eq!(Some(state), history.pop());
+        }
+    }
+
+    // 山札が尽きた段の場のカードを確保すると、Board と同じく空きになる
+    #[test]
+    fn test_exhausted_deck() {
+        let mut board = Board::new();
+        while board.get_stack_card(Level::Three).is_some() {}
+        let mut users = vec![User::new(0), User::new(1)];
+        let mut state = CompactState::from_game(&users, &board, 0);
+
+        let undo = state.apply(0).unwrap();
+        let command = GameCommand::to_command(0);
+        assert!(Turn::play(command, &mut users[0], &mut board).is_ok());
+        assert_eq!(state, CompactState::from_game(&users, &board, 1));
+        assert!(!state.legal_actions().contains(&0));
+
+        let before = state;
+        state.undo(undo);
+        assert_ne!(state, before);
+        assert!(state.legal_actions().contains(&0));
+    }
+
+    // トークンを捨てる規則と、貴族タイルが 1つだけ訪問する規則も Turn と同じ
+    #[test]
+    fn test_turn_rules() {
+        let catalog = CardCatalog::global();
+        let mut board = Board::new();
+        *board.get_noble_tile() =
+            vec![catalog.noble_tile(8).clone(), catalog.noble_tile(1).clone()];
+        let mut users = vec![User::new(0), User::new(1)];
+        for color in [Black, Red, Blue, Green].iter() {
+            for card in catalog
+                .cards()
+                .iter()
+                .filter(|card| card.color == *color)
+                .take(3)
+            {
+                users[0].obtain(*card);
+            }
+        }
+        for color in [Black, White, Red].iter() {
+            for _ in 0..3 {
+                users[0].add_token(board.uget_token(*color));
+            }
+        }
+        let mut state = CompactState::from_game(&users, &board, 0);
+
+        let command = GameCommand::SelectThreeTokens(Red, Blue, Green);
+        let action = command.to_index().unwrap();
+        state.apply(action).unwrap();
+        assert!(Turn::play(command, &mut users[0], &mut board).is_ok());
+        assert_eq!(users[0].peek_token_stack().len_all(), 10);
+        assert_eq!(board.peek_noble_tile().len(), 1);
+        assert_eq!(state, CompactState::from_game(&users, &board, 1));
+    }
+}
@@ -0,0 +1,254 @@
+use crate::game::board::{Board, COORDINATE};
+use crate::game::card_stack::Card;
+use crate::game::color::Color::*;
+use crate::game::game_command::{GameCommand, NUMBER_OF_COMMANDS};
+use crate::game::gem::GEMS;
+use crate::game::level::LEVELS;
+use crate::game::noble_tile::NobleTile;
+use crate::game::observation::Observation;
+use crate::game::token_stack::MAX_NUMBER_OF_TOKEN;
+use crate::game::turn::MAX_NUMBER_OF_TOKENS;
+use crate::game::user::{User, MAX_NUMBER_OF_HANDS};
+use crate::game::VP_TO_END;
+
+use ndarray::{s, Array1, ArrayViewMut1};
+
+pub const MAX_NUMBER_OF_USERS: usize = 4;
+pub const MAX_NUMBER_OF_NOBLE_TILES: usize = 4;
+
+// 有無, レベル(3), 勝利点, 色(5), コスト(5)
+const CARD_FEATURES: usize = 1 + 3 + 1 + 5 + 5;
+// 有無, 勝利点, 必要なボーナス(5)
+const NOBLE_TILE_FEATURES: usize = 1 + 1 + 5;
+// 有無, 勝利点, ボーナス(5), トークン(6), 手札
+const USER_FEATURES: usize = 1 + 1 + 5 + 6 + MAX_NUMBER_OF_HANDS * CARD_FEATURES;
+const NUMBER_OF_TOKEN_COLORS: usize = 6;
+
+pub const NUMBER_OF_FEATURES: usize = COORDINATE.len() * CARD_FEATURES
+    + 3
+    + NUMBER_OF_TOKEN_COLORS
+    + MAX_NUMBER_OF_NOBLE_TILES * NOBLE_TILE_FEATURES
+    + MAX_NUMBER_OF_USERS * USER_FEATURES;
+
+const STACK_SIZES: [f32; 3] = [40.0, 30.0, 20.0];
+const MAX_COST: f32 = 7.0;
+const MAX_BONUS: f32 = 7.0;
+const MAX_POINT: f32 = 5.0;
+
+// 手番のユーザーから見た盤面を固定長のベクトルに変換する
+// 値はおおむね 0.0 から 1.0 に正規化する
+//
+// 並び順:
+//   場のカード(12枚), 山札の残り枚数(レベル1から3), 残りのトークン(黒, 白, 赤, 青, 緑, 金),
+//   貴族タイル(4枚), ユーザー(手番のユーザーから席順に4人分)
+// 他のユーザーが伏せて確保したカードは、有無とレベルだけにする
+pub struct Encoder;
+
+impl Encoder {
+    // observation の席のユーザーから見た特徴量
+    // 学習でも対局でもこれを使い、見えていない情報を特徴量に入れない
+    pub fn encode_observation(observation: &Observation) -> Array1<f32> {
+        let (users, board) = observation.to_game();
+        Encoder::encode(&users[observation.get_seat()], &users, &board)
+    }
+
+    pub fn encode(user: &User, users: &[User], board: &Board) -> Array1<f32> {
+        let mut features = Array1::<f32>::zeros(NUMBER_OF_FEATURES);
+        let mut offset = 0;
+
+        for (x, y) in COORDINATE.iter() {
+            let view = features.slice_mut(s![offset..offset + CARD_FEATURES]);
+            Encoder::encode_card(board.peek_card(*x, *y), view);
+            offset += CARD_FEATURES;
+        }
+
+        for (level, size) in LEVELS.iter().zip(STACK_SIZES.iter()) {
+            features[offset] = board.get_number_of_stack_cards(*level) as f32 / size;
+            offset += 1;
+        }
+
+        for color in [Black, White, Red, Blue, Green, Gold].iter() {
+            features[offset] =
+                board.get_number_of_tokens(*color) as f32 / MAX_NUMBER_OF_TOKEN as f32;
+            offset += 1;
+        }
+
+        for order in 0..MAX_NUMBER_OF_NOBLE_TILES {
+            let view = features.slice_mut(s![offset..offset + NOBLE_TILE_FEATURES]);
+            Encoder::encode_noble_tile(board.peek_noble_tile().get(order), view);
+            offset += NOBLE_TILE_FEATURES;
+        }
+
+        let seat = users
+            .iter()
+            .position(|u| u.get_id() == user.get_id())
+            .unwrap_or(0);
+        for order in 0..MAX_NUMBER_OF_USERS {
+            let view = features.slice_mut(s![offset..offset + USER_FEATURES]);
+            let relative = if order < users.len() {
+                users.get((seat + order) % users.len())
+            } else {
+                None
+            };
+            Encoder::encode_user(relative, order == 0, view);
+            offset += USER_FEATURES;
+        }
+
+        features
+    }
+
+    // 各コマンド番号(GameCommand::to_command)が実行可能なら 1.0、そうでなければ 0.0
+    pub fn legal_action_mask(user: &User, board: &Board) -> Array1<f32> {
+        let mut mask = Array1::<f32>::zeros(NUMBER_OF_COMMANDS as usize);
+        for input in 0..NUMBER_OF_COMMANDS {
+            let mut user = user.clone();
+            let mut board = board.clone();
+            let command = GameCommand::to_command(input);
+            if command.execute(&mut user, &mut board).is_ok() {
+                mask[input as usize] = 1.0;
+            }
+        }
+        mask
+    }
+
+    fn encode_card(card: Option<&Card>, mut view: ArrayViewMut1<f32>) {
+        let card = match card {
+            Some(card) => card,
+            None => return,
+        };
+        view[0] = 1.0;
+        view[card.level as usize] = 1.0;
+        view[4] = card.get_point() as f32 / MAX_POINT;
+        for (i, color) in GEMS.iter().enumerate() {
+            if card.get_color() == *color {
+                view[5 + i] = 1.0;
+            }
+            view[10 + i] = card.get_cost(*color) as f32 / MAX_COST;
+        }
+    }
+
+    fn encode_noble_tile(tile: Option<&NobleTile>, mut view: ArrayViewMut1<f32>) {
+        let tile = match tile {
+            Some(tile) => tile,
+            None => return,
+        };
+        view[0] = 1.0;
+        view[1] = tile.get_point() as f32 / MAX_POINT;
+        for (i, color) in GEMS.iter().enumerate() {
+            view[2 + i] = tile.get_bonus(*color) as f32 / MAX_BONUS;
+        }
+    }
+
+    // me でなければ伏せたカードの中身は入れない
+    fn encode_user(user: Option<&User>, me: bool, mut view: ArrayViewMut1<f32>) {
+        let user = match user {
+            Some(user) => user,
+            None => return,
+        };
+        view[0] = 1.0;
+        view[1] = user.get_vp() as f32 / VP_TO_END as f32;
+        let jewelries = user.get_jewelries();
+        for (i, color) in GEMS.iter().enumerate() {
+            view[2 + i] = jewelries.get(*color) as f32 / MAX_BONUS;
+        }
+        for (i, color) in [Black, White, Red, Blue, Green, Gold].iter().enumerate() {
+            view[7 + i] = user.get_number_of_tokens(*color) as f32 / MAX_NUMBER_OF_TOKENS as f32;
+        }
+        let offset = 13;
+        for order in 0..MAX_NUMBER_OF_HANDS {
+            let start = offset + order * CARD_FEATURES;
+            let mut card_view = view.slice_mut(s![start..start + CARD_FEATURES]);
+            match user.peek_card_in_hands(order as u8) {
+                Some(card) if !me && user.is_face_down(order as u8) => {
+                    card_view[0] = 1.0;
+                    card_view[card.level as usize] = 1.0;
+                }
+                card => Encoder::encode_card(card, card_view),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Encoder, CARD_FEATURES, NUMBER_OF_FEATURES, USER_FEATURES};
+    use crate::game::board::Board;
+    use crate::game::color::Color::*;
+    use crate::game::game_command::{GameCommand, NUMBER_OF_COMMANDS};
+    use crate::game::level::Level;
+    use crate::game::observation::Observation;
+    use crate::game::user::User;
+
+    use ndarray::s;
+
+    #[test]
+    fn test_encode() {
+        let board = Board::new();
+        let users = vec![User::new(0), User::new(1)];
+        let features = Encoder::encode(&users[0], &users, &board);
+
+        assert_eq!(features.len(), NUMBER_OF_FEATURES);
+        assert!(features.iter().all(|v| (0.0..=1.0).contains(v)));
+        // 場のカードは全て埋まっている
+        assert_eq!(features[0], 1.0);
+
+        let mut users = users;
+        users[1].add_vp(3);
+        let first = Encoder::encode(&users[0], &users, &board);
+        let second = Encoder::encode(&users[1], &users, &board);
+        assert_ne!(first, second);
+    }
+
+    // 上限の 10個のトークンを持っていても、特徴量は 1.0 を超えない
+    #[test]
+    fn test_token_range() {
+        let mut board = Board::new();
+        let mut users = vec![User::new(0), User::new(1)];
+        for color in [Red, Blue].iter() {
+            for _ in 0..5 {
+                users[0].add_token(board.uget_token(*color));
+            }
+        }
+
+        let features = Encoder::encode(&users[0], &users, &board);
+        assert!(features.iter().all(|v| (0.0..=1.0).contains(v)));
+        assert!(features.iter().any(|v| *v == 0.5));
+    }
+
+    #[test]
+    fn test_hidden_reserve() {
+        let mut board = Board::new();
+        let mut users = vec![User::new(0), User::new(1)];
+        GameCommand::reserve_stack_card(Level::Two, &mut users[1], &mut board).unwrap();
+
+        // 席 0 から見た席 1 の手札の 1枚目 (有無とレベルだけ)
+        let start = NUMBER_OF_FEATURES - 4 * USER_FEATURES + USER_FEATURES + 13;
+        let features = Encoder::encode(&users[0], &users, &board);
+        let card = features.slice(s![start..start + CARD_FEATURES]);
+        assert_eq!(card[0], 1.0);
+        assert_eq!(card[2], 1.0);
+        assert_eq!(card.sum(), 2.0);
+        assert_eq!(
+            Encoder::encode_observation(&Observation::new(&users, &board, 0)),
+            features
+        );
+
+        // 確保したユーザーには中身が見える
+        let mine = Encoder::encode(&users[1], &users, &board);
+        let start = NUMBER_OF_FEATURES - 4 * USER_FEATURES + 13;
+        assert!(mine.slice(s![start..start + CARD_FEATURES]).sum() > 2.0);
+    }
+
+    #[test]
+    fn test_legal_action_mask() {
+        let board = Board::new();
+        let user = User::new(0);
+        let mask = Encoder::legal_action_mask(&user, &board);
+
+        assert_eq!(mask.len(), NUMBER_OF_COMMANDS as usize);
+        // 場のカードはどれでも確保できる
+        assert!(mask.iter().take(12).all(|v| *v == 1.0));
+        // 手札がないので手札のカードは購入できない
+        assert!(mask.iter().skip(42).all(|v| *v == 0.0));
+    }
+}
@@ -1,14 +1,17 @@
 use crate::game::board::Board;
 use crate::game::color::Color;
 use crate::game::color::Color::*;
-use crate::game::gem::{Gem, GEMS};
+use crate::game::gem::GEMS;
 use crate::game::level::Level;
 use crate::game::level::Level::*;
+use crate::game::locale::{message, text};
 use crate::game::user::User;
 
 use std::fmt;
 
-#[derive(Clone)]
+pub const NUMBER_OF_COMMANDS: u8 = 45;
+
+#[derive(Clone, PartialEq, Debug)]
 pub enum GameCommand {
     ReserveDevelopmentCard { x: u8, y: u8 },
     BuyDevelopmentCard { x: u8, y: u8 },
@@ -21,18 +24,15 @@ pub enum GameCommand {
 impl fmt::Display for GameCommand {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use self::GameCommand::*;
-        match self {
-            ReserveDevelopmentCard { x, y } => write!(f, "カードを確保する({}, {})", x, y),
-            BuyDevelopmentCard { x, y } => write!(f, "カードを購入する({}, {})", x, y),
-            SelectTwoSameTokens(c) => write!(f, "同じ色のトークンを取得: {}", c),
-            SelectThreeTokens(c1, c2, c3) => {
-                write!(f, "違う色のトークンを取得: {} {} {}", c1, c2, c3)
-            }
-            ReserveStackCard(l) => write!(f, "スタックからカードを確保 {:?} ", l),
-            BuyReservedCard(index) => {
-                write!(f, "手札のカードを購入する: {}枚目", index)
-            }
-        }
+        let line = match self {
+            ReserveDevelopmentCard { x, y } => message("command.reserve", &[x, y]),
+            BuyDevelopmentCard { x, y } => message("command.buy", &[x, y]),
+            SelectTwoSameTokens(c) => message("command.take_two", &[c]),
+            SelectThreeTokens(c1, c2, c3) => message("command.take_three", &[c1, c2, c3]),
+            ReserveStackCard(l) => message("command.reserve_stack", &[&(*l as u8 + 1)]),
+            BuyReservedCard(index) => message("command.buy_reserved", &[index]),
+        };
+        f.write_str(&line)
     }
 }
 
@@ -43,7 +43,7 @@ impl GameCommand {
         struct Point {
             x: u8,
             y: u8,
-        };
+        }
         let coordinate: [Point; 12] = [
             Point { x: 0, y: 0 },
             Point { x: 0, y: 1 },
@@ -75,26 +75,66 @@ impl GameCommand {
         let level: [Level; 3] = [One, Two, Three];
 
         match input as usize {
-            p @ 0...11 => ReserveDevelopmentCard {
+            p @ 0..=11 => ReserveDevelopmentCard {
                 x: coordinate[p].x,
                 y: coordinate[p].y,
             },
-            p @ 12...23 => BuyDevelopmentCard {
+            p @ 12..=23 => BuyDevelopmentCard {
                 x: coordinate[p - 12].x,
                 y: coordinate[p - 12].y,
             },
-            c @ 24...28 => SelectTwoSameTokens(GEMS[c - 24]),
-            c @ 29...38 => SelectThreeTokens(
+            c @ 24..=28 => SelectTwoSameTokens(GEMS[c - 24]),
+            c @ 29..=38 => SelectThreeTokens(
                 color_set[c - 29].0,
                 color_set[c - 29].1,
                 color_set[c - 29].2,
             ),
-            l @ 39...41 => ReserveStackCard(level[l - 39]),
-            i @ 42...44 => BuyReservedCard((i - 42) as u8),
+            l @ 39..=41 => ReserveStackCard(level[l - 39]),
+            i @ 42..=44 => BuyReservedCard((i - 42) as u8),
             _ => unreachable!(),
         }
     }
 
+    // to_command の逆変換
+    // SelectThreeTokens は色の順によらず同じ番号で、to_command が返さないコマンドは None
+    pub fn to_index(&self) -> Option<u8> {
+        let command = self.canonical();
+        (0..NUMBER_OF_COMMANDS).find(|input| GameCommand::to_command(*input) == command)
+    }
+
+    // SelectThreeTokens の色を to_command と同じ順 (黒, 白, 赤, 青, 緑) に並べる
+    pub fn canonical(&self) -> GameCommand {
+        match *self {
+            GameCommand::SelectThreeTokens(color1, color2, color3) => {
+                let mut colors = [color1, color2, color3];
+                colors.sort_by_key(|color| *color as u8);
+                GameCommand::SelectThreeTokens(colors[0], colors[1], colors[2])
+            }
+            _ => self.clone(),
+        }
+    }
+
+    // 手番の主な行動だけを実行する (トークンを捨てることと貴族の訪問は含まない)
+    pub fn execute(
+        &self,
+        user: &mut User,
+        board: &mut Board,
+    ) -> Result<&'static str, &'static str> {
+        use self::GameCommand::*;
+        match *self {
+            ReserveDevelopmentCard { x, y } => {
+                GameCommand::reserve_development_card(x, y, user, board)
+            }
+            BuyDevelopmentCard { x, y } => GameCommand::buy_development_card(x, y, user, board),
+            SelectTwoSameTokens(color) => GameCommand::select_two_same_tokens(color, user, board),
+            SelectThreeTokens(color1, color2, color3) => {
+                GameCommand::select_three_tokens(color1, color2, color3, user, board)
+            }
+            ReserveStackCard(level) => GameCommand::reserve_stack_card(level, user, board),
+            BuyReservedCard(index) => GameCommand::buy_reserved_card(index, user, board),
+        }
+    }
+
     pub fn reserve_development_card(
         x: u8,
         y: u8,
@@ -102,21 +142,21 @@ impl GameCommand {
         board: &mut Board,
     ) -> Result<&'static str, &'static str> {
         if user.is_over_capacity_of_hand() {
-            Err("試行: カードの確保, 結果: 手札がいっぱいです")
+            Err(text("result.reserve_hand_full"))
         } else {
             match board.get_card(x, y) {
                 Some(card) => {
                     user.add_to_hands(card);
                     // 金トークンの取得
                     match board.get_token(Color::Gold) {
-                    Some(token) => {
-                        user.add_token(token);
-                        Ok("試行: カードの確保, 結果: カードを確保しました")
+                        Some(token) => {
+                            user.add_token(token);
+                            Ok(text("result.reserved"))
+                        }
+                        None => Ok(text("result.reserved_without_gold")),
                     }
-                    None => Ok("試行: カードの確保, 結果: カードを確保しましたが、金トークンは取得できませんでした"),
-                }
                 }
-                None => Err("試行: カードの確保, 結果: その場所にはもうカードがありません"),
+                None => Err(text("result.reserve_no_card")),
             }
         }
     }
@@ -127,54 +167,35 @@ impl GameCommand {
         user: &mut User,
         board: &mut Board,
     ) -> Result<&'static str, &'static str> {
-        let is_available;
-        match board.peek_card(x, y) {
-            Some(card) => {
-                is_available = card.is_available(&user);
-            }
-            None => {
-                return Err(
-                    "試行: カードの購入, 結果: そこにはカードがありません",
-                )
-            }
-        }
+        let is_available = match board.peek_card(x, y) {
+            Some(card) => card.is_available(user),
+            None => return Err(text("result.buy_no_card")),
+        };
 
         if is_available {
             let card = board.uget_card(x, y);
             user.pay(&card, board.get_token_stack());
             user.obtain(card);
-            let is_visited = GameCommand::visit(user, board);
-            if is_visited {
-                Ok("試行: 確保したカードの購入, 結果: カードを購入しました また、貴族の訪問がありました。")
-            } else {
-                Ok("試行: 確保したカードの購入, 結果: カードを購入しました")
-            }
+            Ok(text("result.bought"))
         } else {
-            Err("試行: カードの購入, 結果: 必要な宝石数が足りません")
+            Err(text("result.buy_short"))
         }
     }
 
+    // 持っているトークンの数によらず取れる (上限を超えた分は Turn が捨てさせる)
     pub fn select_two_same_tokens(
         color: Color,
         user: &mut User,
         board: &mut Board,
     ) -> Result<&'static str, &'static str> {
-        if !user.can_get_token() {
-            return Err("試行: トークンを取得, 結果: トークンの所持数が10を超えるため取得できませんでした");
-        }
         if board.can_get_token(color) {
-            let token = board.uget_token(color);
-            user.add_token(token);
-            if !user.can_get_token() {
-                return Ok(
-                    "試行: トークンを取得, 結果: トークンを取得しました",
-                );
+            for _ in 0..2 {
+                let token = board.uget_token(color);
+                user.add_token(token);
             }
-            let token = board.uget_token(color);
-            user.add_token(token);
-            Ok("試行: トークンを取得, 結果: トークンを取得しました")
+            Ok(text("result.tokens_taken"))
         } else {
-            Err("試行: トークンを取得, 結果: 残りのトークン数が4より少ないです")
+            Err(text("result.tokens_two_short"))
         }
     }
 
@@ -185,37 +206,18 @@ impl GameCommand {
         user: &mut User,
         board: &mut Board,
     ) -> Result<&'static str, &'static str> {
-        // TODO !!
-        if !user.can_get_token() {
-            return Err("試行: トークンを取得, 結果: トークンの所持数が10を超えるため取得できませんでした");
-        }
-
         let mut count = 0;
-        if let Some(token) = board.get_token(color1) {
-            user.add_token(token);
-            count = count + 1;
-        }
-
-        if !user.can_get_token() {
-            return Ok("試行: トークンを取得, 結果: トークンを取得しました");
-        }
-        if let Some(token) = board.get_token(color2) {
-            user.add_token(token);
-            count = count + 1;
-        }
-
-        if !user.can_get_token() {
-            return Ok("試行: トークンを取得, 結果: トークンを取得しました");
-        }
-        if let Some(token) = board.get_token(color3) {
-            user.add_token(token);
-            count = count + 1;
+        for color in [color1, color2, color3].iter() {
+            if let Some(token) = board.get_token(*color) {
+                user.add_token(token);
+                count += 1;
+            }
         }
 
         if count == 0 {
-            Err("試行: トークンを取得, 結果: 取得できるトークンがありません")
+            Err(text("result.tokens_none"))
         } else {
-            Ok("試行: トークンを取得, 結果: トークンを取得しました")
+            Ok(text("result.tokens_taken"))
         }
     }
 
@@ -225,20 +227,20 @@ impl GameCommand {
         board: &mut Board,
     ) -> Result<&'static str, &'static str> {
         if user.is_over_capacity_of_hand() {
-            Err("試行: スタックされたカード取得, 結果: 手札がいっぱいです")
+            Err(text("result.stack_hand_full"))
         } else {
             match board.get_stack_card(level) {
                 Some(card) => {
-                    user.add_to_hands(card);
+                    user.add_face_down_to_hands(card);
                     match board.get_token(Color::Gold) {
-                    Some(token) => {
-                        user.add_token(token);
-                        Ok("試行: スタックされたカード取得, 結果: カードを確保しました")
+                        Some(token) => {
+                            user.add_token(token);
+                            Ok(text("result.stack_reserved"))
+                        }
+                        None => Ok(text("result.stack_reserved_without_gold")),
                     }
-                    None => Ok("試行: スタックされたカード取得, 結果: カードを確保しましたが、金トークンは取得できませんでした"),
-                }
                 }
-                None => Err("試行: スタックされたカード取得, 結果: 指定のスタックにカードはありませんでした"),
+                None => Err(text("result.stack_empty")),
             }
         }
     }
@@ -248,45 +250,42 @@ impl GameCommand {
         user: &mut User,
         board: &mut Board,
     ) -> Result<&'static str, &'static str> {
-        let is_available;
-        match user.peek_card_in_hands(order) {
-            Some(card) => is_available = card.is_available(&user),
-            None => return Err("試行: 確保したカードの購入, 結果: そこにはカードがありません"),
-        }
+        let is_available = match user.peek_card_in_hands(order) {
+            Some(card) => card.is_available(user),
+            None => return Err(text("result.reserved_no_card")),
+        };
         if is_available {
             let card = user.uget_card_in_hands(order);
             user.pay(&card, board.get_token_stack());
             user.obtain(card);
             user.remove_card_in_hands(order);
-            let is_visited = GameCommand::visit(user, board);
-            if is_visited {
-                Ok("試行: 確保したカードの購入, 結果: カードを購入しました また、貴族の訪問がありました。")
-            } else {
-                Ok("試行: 確保したカードの購入, 結果: カードを購入しました")
-            }
+            Ok(text("result.reserved_bought"))
         } else {
-            Err("試行: 確保したカードの購入, 結果: 必要な宝石数が足りません")
+            Err(text("result.reserved_short"))
         }
     }
+}
 
-    fn visit(user: &mut User, board: &mut Board) -> bool {
-        let mut remove_tile_order = vec![];
-        let mut order = 0;
-        let jewelies = user.get_jewelries();
+#[cfg(test)]
+mod tests {
+    use super::{GameCommand, NUMBER_OF_COMMANDS};
+    use crate::game::color::Color::*;
 
-        for tile in board.get_noble_tile().iter_mut() {
-            if tile.can_visit(&jewelies) {
-                user.add_vp(tile.get_point());
-                remove_tile_order.push(order);
-            }
-            order += 1;
+    #[test]
+    fn test_to_index() {
+        for input in 0..NUMBER_OF_COMMANDS {
+            assert_eq!(GameCommand::to_command(input).to_index(), Some(input));
         }
 
-        let mut result = false;
-        for order in remove_tile_order.into_iter().rev() {
-            board.get_noble_tile().remove(order as usize);
-            result = true;
-        }
-        result
+        let permuted = GameCommand::SelectThreeTokens(Green, Black, Red);
+        assert_eq!(
+            permuted.to_index(),
+            GameCommand::SelectThreeTokens(Black, Red, Green).to_index()
+        );
+        assert_eq!(
+            GameCommand::SelectThreeTokens(Red, Red, Blue).to_index(),
+            None
+        );
+        assert_eq!(GameCommand::BuyReservedCard(3).to_index(), None);
     }
 }
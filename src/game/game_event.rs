@@ -0,0 +1,161 @@
+use crate::game::card_catalog::CardCatalog;
+use crate::game::color::Color;
+use crate::game::game_command::GameCommand;
+use crate::game::gem::Gem;
+use crate::game::locale::{message, text};
+
+use std::fmt;
+use std::io::Write;
+use std::time::Instant;
+
+mod json_lines;
+
+// ゲームで起きたこと
+// カードと貴族タイルは CardCatalog の id、seat は Game のユーザーの添字
+// 山札から確保したカード (slot が None の CardReserved) は確保したユーザーにしか見えないので、
+// 他のユーザーに送るときは card を隠すこと
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    TurnStarted {
+        turn: u32,
+        seat: usize,
+    },
+    TokensTaken {
+        seat: usize,
+        tokens: Gem,
+    },
+    // slot は場のカードの位置 (山札から確保したときは None)、gold は金トークンを取得したか
+    CardReserved {
+        seat: usize,
+        card: u8,
+        slot: Option<(u8, u8)>,
+        gold: bool,
+    },
+    // payment は支払った宝石トークン、gold は支払った金トークンの個数
+    CardBought {
+        seat: usize,
+        card: u8,
+        payment: Gem,
+        gold: u8,
+    },
+    // 山札が尽きていれば card は None
+    SlotRefilled {
+        x: u8,
+        y: u8,
+        card: Option<u8>,
+    },
+    NobleVisited {
+        seat: usize,
+        noble_tile: u8,
+    },
+    TokensDiscarded {
+        seat: usize,
+        color: Color,
+    },
+    // 手番の終わりの勝利点とトークン (command は降参したときは None)
+    TurnEnded {
+        turn: u32,
+        seat: usize,
+        command: Option<GameCommand>,
+        vp: u8,
+        tokens: Gem,
+        gold: u8,
+    },
+    Surrendered {
+        seat: usize,
+    },
+    GameEnded {
+        winner: Option<u8>,
+        vps: Vec<u8>,
+        turns: u32,
+    },
+}
+
+// Game に登録すると全ての GameEvent を起きた順に受け取る
+pub trait GameObserver {
+    fn notify(&mut self, event: &GameEvent);
+}
+
+// GameEvent を 1行に 1つの JSON オブジェクトとしてファイルなどに書き出す GameObserver
+// 複数の対局を 1つのファイルに書くときは game に対局の番号を入れる
+pub struct JsonLinesLogger<W: Write> {
+    writer: W,
+    game: Option<u32>,
+    players: Vec<String>,
+    start: Instant,
+    turn_start: Instant,
+}
+
+impl fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::GameEvent::*;
+        let catalog = CardCatalog::global();
+        let line = match self {
+            TurnStarted { turn, seat } => message("event.turn_started", &[turn, seat]),
+            TokensTaken { seat, tokens } => {
+                message("event.tokens_taken", &[seat, &format!("{:?}", tokens)])
+            }
+            CardReserved {
+                seat,
+                card,
+                slot,
+                gold,
+            } => {
+                let reserved = match slot {
+                    Some((x, y)) => {
+                        message("event.card_reserved", &[seat, x, y, catalog.card(*card)])
+                    }
+                    None => message("event.stack_reserved", &[seat, &catalog.card(*card).level]),
+                };
+                if *gold {
+                    reserved + text("event.gold_taken")
+                } else {
+                    reserved
+                }
+            }
+            CardBought {
+                seat,
+                card,
+                payment,
+                gold,
+            } => message(
+                "event.card_bought",
+                &[seat, catalog.card(*card), &format!("{:?}", payment), gold],
+            ),
+            SlotRefilled {
+                x,
+                y,
+                card: Some(card),
+            } => message("event.slot_refilled", &[x, y, catalog.card(*card)]),
+            SlotRefilled { x, y, card: None } => message("event.slot_emptied", &[x, y]),
+            NobleVisited { seat, noble_tile } => message(
+                "event.noble_visited",
+                &[seat, catalog.noble_tile(*noble_tile)],
+            ),
+            TokensDiscarded { seat, color } => message("event.tokens_discarded", &[seat, color]),
+            TurnEnded {
+                turn,
+                seat,
+                vp,
+                tokens,
+                gold,
+                ..
+            } => message(
+                "event.turn_ended",
+                &[turn, seat, vp, &format!("{:?}", tokens), gold],
+            ),
+            Surrendered { seat } => message("event.surrendered", &[seat]),
+            GameEnded {
+                winner: Some(winner),
+                vps,
+                turns,
+            } => message("event.game_won", &[turns, winner, &format!("{:?}", vps)]),
+            GameEnded {
+                winner: None,
+                vps,
+                turns,
+            } => message("event.game_drawn", &[turns, &format!("{:?}", vps)]),
+        };
+        f.write_str(&line)
+    }
+}
@@ -0,0 +1,305 @@
+use super::{GameEvent, GameObserver, JsonLinesLogger};
+use crate::game::game_command::GameCommand;
+use crate::game::locale::message;
+
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use std::time::Instant;
+
+impl<W: Write> JsonLinesLogger<W> {
+    pub fn new(writer: W) -> JsonLinesLogger<W> {
+        let now = Instant::now();
+        JsonLinesLogger {
+            writer,
+            game: None,
+            players: vec![],
+            start: now,
+            turn_start: now,
+        }
+    }
+
+    pub fn game(mut self, game: u32) -> JsonLinesLogger<W> {
+        self.game = Some(game);
+        self
+    }
+
+    // 席順に並べたエージェントの名前 (player の欄に書く)
+    pub fn players(mut self, players: Vec<String>) -> JsonLinesLogger<W> {
+        self.players = players;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    // トークンは 黒, 白, 赤, 青, 緑 の順の配列
+    // elapsed_ms は最初からの経過時間、turn_ended の turn_ms はその手番にかかった時間
+    fn line(&mut self, event: &GameEvent) -> Value {
+        let now = Instant::now();
+        let mut object = Map::new();
+        if let Some(game) = self.game {
+            object.insert("game".to_string(), json!(game));
+        }
+        object.insert(
+            "elapsed_ms".to_string(),
+            json!(now.duration_since(self.start).as_secs_f64() * 1000.0),
+        );
+        match event {
+            GameEvent::TurnStarted { .. } => self.turn_start = now,
+            GameEvent::TurnEnded { .. } => {
+                object.insert(
+                    "turn_ms".to_string(),
+                    json!(now.duration_since(self.turn_start).as_secs_f64() * 1000.0),
+                );
+            }
+            _ => {}
+        }
+
+        let (name, fields) = JsonLinesLogger::<W>::fields(event);
+        object.insert("event".to_string(), json!(name));
+        if let Some(seat) = fields.get("seat").and_then(Value::as_u64) {
+            if let Some(player) = self.players.get(seat as usize) {
+                object.insert("player".to_string(), json!(player));
+            }
+        }
+        object.extend(fields);
+        Value::Object(object)
+    }
+
+    fn fields(event: &GameEvent) -> (&'static str, Map<String, Value>) {
+        use self::GameEvent::*;
+        let (name, value) = match event {
+            TurnStarted { turn, seat } => ("turn_started", json!({ "turn": turn, "seat": seat })),
+            TokensTaken { seat, tokens } => (
+                "tokens_taken",
+                json!({ "seat": seat, "tokens": tokens.to_array() }),
+            ),
+            CardReserved {
+                seat,
+                card,
+                slot,
+                gold,
+            } => (
+                "card_reserved",
+                json!({
+                    "seat": seat,
+                    "card": card,
+                    "slot": slot.map(|(x, y)| [x, y]),
+                    "gold": gold,
+                }),
+            ),
+            CardBought {
+                seat,
+                card,
+                payment,
+                gold,
+            } => (
+                "card_bought",
+                json!({
+                    "seat": seat,
+                    "card": card,
+                    "payment": payment.to_array(),
+                    "gold": gold,
+                }),
+            ),
+            SlotRefilled { x, y, card } => {
+                ("slot_refilled", json!({ "slot": [x, y], "card": card }))
+            }
+            NobleVisited { seat, noble_tile } => (
+                "noble_visited",
+                json!({ "seat": seat, "noble_tile": noble_tile }),
+            ),
+            TokensDiscarded { seat, color } => {
+                ("tokens_discarded", json!({ "seat": seat, "color": color }))
+            }
+            TurnEnded {
+                turn,
+                seat,
+                command,
+                vp,
+                tokens,
+                gold,
+            } => (
+                "turn_ended",
+                json!({
+                    "turn": turn,
+                    "seat": seat,
+                    "command": command.as_ref().map(JsonLinesLogger::<W>::command),
+                    "vp": vp,
+                    "tokens": tokens.to_array(),
+                    "gold": gold,
+                }),
+            ),
+            Surrendered { seat } => ("surrendered", json!({ "seat": seat })),
+            GameEnded { winner, vps, turns } => (
+                "game_ended",
+                json!({ "winner": winner, "vps": vps, "turns": turns }),
+            ),
+        };
+        match value {
+            Value::Object(fields) => (name, fields),
+            _ => unreachable!(),
+        }
+    }
+
+    // 同じ手が同じ行になるよう、GameCommand::canonical の形で書く
+    // index は GameCommand::to_index の番号 (番号のない形なら null)
+    fn command(command: &GameCommand) -> Value {
+        use self::GameCommand::*;
+        let command = command.canonical();
+        let index = command.to_index();
+        match command {
+            ReserveDevelopmentCard { x, y } => {
+                json!({ "index": index, "action": "reserve", "slot": [x, y] })
+            }
+            BuyDevelopmentCard { x, y } => {
+                json!({ "index": index, "action": "buy", "slot": [x, y] })
+            }
+            SelectTwoSameTokens(color) => {
+                json!({ "index": index, "action": "take_two", "colors": [color] })
+            }
+            SelectThreeTokens(color1, color2, color3) => json!({
+                "index": index,
+                "action": "take_three",
+                "colors": [color1, color2, color3],
+            }),
+            ReserveStackCard(level) => json!({
+                "index": index,
+                "action": "reserve_stack",
+                "level": level as u8 + 1,
+            }),
+            BuyReservedCard(order) => {
+                json!({ "index": index, "action": "buy_reserved", "order": order })
+            }
+        }
+    }
+}
+
+impl<W: Write> GameObserver for JsonLinesLogger<W> {
+    fn notify(&mut self, event: &GameEvent) {
+        let line = self.line(event);
+        let result = writeln!(self.writer, "{}", line);
+        let result = match event {
+            GameEvent::GameEnded { .. } => result.and_then(|_| self.writer.flush()),
+            _ => result,
+        };
+        if let Err(e) = result {
+            eprintln!("{}", message("log.write_failed", &[&e]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::agent::{Agent, HeuristicAgent, RandomAgent};
+    use crate::game::color::Color::*;
+    use crate::game::game_command::GameCommand;
+    use crate::game::game_event::{GameEvent, GameObserver, JsonLinesLogger};
+    use crate::game::gem::Gem;
+    use crate::game::Game;
+
+    use serde_json::Value;
+    use std::env;
+    use std::fs::{self, File};
+
+    #[test]
+    fn test_notify() {
+        let mut logger = JsonLinesLogger::new(vec![])
+            .game(3)
+            .players(vec!["heuristic".to_string(), "random".to_string()]);
+        logger.notify(&GameEvent::TurnEnded {
+            turn: 2,
+            seat: 1,
+            command: Some(GameCommand::SelectThreeTokens(Red, Blue, Green)),
+            vp: 4,
+            tokens: Gem::from([1, 0, 2, 0, 3]),
+            gold: 1,
+        });
+        logger.notify(&GameEvent::SlotRefilled {
+            x: 0,
+            y: 1,
+            card: None,
+        });
+
+        let text = String::from_utf8(logger.into_inner()).unwrap();
+        let lines: Vec<Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["game"], 3);
+        assert_eq!(lines[0]["event"], "turn_ended");
+        assert_eq!(lines[0]["player"], "random");
+        assert_eq!(lines[0]["vp"], 4);
+        assert_eq!(lines[0]["tokens"], serde_json::json!([1, 0, 2, 0, 3]));
+        assert_eq!(lines[0]["command"]["action"], "take_three");
+        assert_eq!(
+            lines[0]["command"]["colors"],
+            serde_json::json!(["Red", "Blue", "Green"])
+        );
+        assert!(lines[0]["turn_ms"].is_f64());
+        assert!(lines[1].get("turn_ms").is_none());
+        assert_eq!(lines[1]["event"], "slot_refilled");
+        assert!(lines[1]["card"].is_null());
+        assert!(lines[1].get("player").is_none());
+    }
+
+    // 色の順番が違っても同じ番号と並びで書く
+    #[test]
+    fn test_permuted_command() {
+        let mut logger = JsonLinesLogger::new(vec![]);
+        for command in [
+            GameCommand::SelectThreeTokens(Green, Red, Blue),
+            GameCommand::SelectThreeTokens(Red, Blue, Green),
+        ]
+        .iter()
+        {
+            logger.notify(&GameEvent::TurnEnded {
+                turn: 1,
+                seat: 0,
+                command: Some(command.clone()),
+                vp: 0,
+                tokens: Gem::from([0, 0, 1, 1, 1]),
+                gold: 0,
+            });
+        }
+
+        let text = String::from_utf8(logger.into_inner()).unwrap();
+        let lines: Vec<Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let index = GameCommand::SelectThreeTokens(Red, Blue, Green)
+            .to_index()
+            .unwrap();
+        assert_eq!(lines[0]["command"]["index"], index);
+        assert_eq!(lines[0]["command"], lines[1]["command"]);
+    }
+
+    #[test]
+    fn test_play() {
+        let path = env::temp_dir().join(format!(
+            "splendor_clone_{}_game_log.jsonl",
+            std::process::id()
+        ));
+        let agents: Vec<Box<dyn Agent>> = vec![
+            Box::new(HeuristicAgent::new()),
+            Box::new(RandomAgent::new()),
+        ];
+        let logger = JsonLinesLogger::new(File::create(&path).unwrap());
+        let result = Game::with_agents(agents)
+            .quiet()
+            .observer(Box::new(logger))
+            .play()
+            .unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        let last: Value = serde_json::from_str(text.lines().last().unwrap()).unwrap();
+        assert_eq!(last["event"], "game_ended");
+        assert_eq!(last["turns"], result.turns);
+        assert!(text
+            .lines()
+            .all(|line| serde_json::from_str::<Value>(line).is_ok()));
+    }
+}
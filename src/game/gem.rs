@@ -1,42 +1,138 @@
 use crate::game::color::Color::{self, *};
 
+use std::fmt;
+use std::ops::{Add, AddAssign};
+
 pub const GEMS: [Color; 5] = [Black, White, Red, Blue, Green];
-pub struct Gem {
-    black: u8,
-    white: u8,
-    red: u8,
-    blue: u8,
-    green: u8,
+
+// 5色の宝石の個数
+// カードのコスト、貴族タイルの条件、ユーザーのボーナス、トークン(金を除く)に共通で使う
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Gem([u8; 5]);
+
+impl fmt::Debug for Gem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {}",
+            self.0[0], self.0[1], self.0[2], self.0[3], self.0[4]
+        )
+    }
+}
+
+impl Add for Gem {
+    type Output = Gem;
+    fn add(mut self, other: Gem) -> Gem {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for Gem {
+    fn add_assign(&mut self, other: Gem) {
+        for (gem, other) in self.0.iter_mut().zip(other.0.iter()) {
+            *gem += other;
+        }
+    }
+}
+
+impl From<[u8; 5]> for Gem {
+    // 黒, 白, 赤, 青, 緑 の順
+    fn from(gems: [u8; 5]) -> Gem {
+        Gem(gems)
+    }
 }
 
 impl Gem {
     pub fn new() -> Gem {
-        Gem {
-            black: 0,
-            white: 0,
-            red: 0,
-            blue: 0,
-            green: 0,
-        }
+        Gem([0; 5])
     }
     pub fn get(&self, color: Color) -> u8 {
-        match color {
-            Black => self.black,
-            White => self.white,
-            Red => self.red,
-            Blue => self.blue,
-            Green => self.green,
-            _ => unreachable!(),
-        }
+        self.0[Gem::index(color)]
+    }
+    pub fn set(&mut self, color: Color, gems: u8) {
+        self.0[Gem::index(color)] = gems;
     }
     pub fn add(&mut self, color: Color, gems: u8) {
+        self.0[Gem::index(color)] += gems;
+    }
+    pub fn to_array(&self) -> [u8; 5] {
+        self.0
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (Color, u8)> + '_ {
+        GEMS.iter().map(move |color| (*color, self.get(*color)))
+    }
+    pub fn total(&self) -> u8 {
+        self.0.iter().sum()
+    }
+
+    // 全ての色で other 以上か
+    pub fn dominates(&self, other: &Gem) -> bool {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .all(|(gem, other)| gem >= other)
+    }
+
+    // 色ごとに other を引き、0 未満は 0 にする
+    pub fn saturating_sub(&self, other: &Gem) -> Gem {
+        let mut gems = *self;
+        for (gem, other) in gems.0.iter_mut().zip(other.0.iter()) {
+            *gem = gem.saturating_sub(*other);
+        }
+        gems
+    }
+
+    // 色ごとの小さいほう
+    pub fn min(&self, other: &Gem) -> Gem {
+        let mut gems = *self;
+        for (gem, other) in gems.0.iter_mut().zip(other.0.iter()) {
+            *gem = (*gem).min(*other);
+        }
+        gems
+    }
+
+    // cost を払うのに足りない宝石の合計(金トークンで補う必要がある数)
+    pub fn shortfall(&self, cost: &Gem) -> u8 {
+        cost.saturating_sub(self).total()
+    }
+
+    fn index(color: Color) -> usize {
         match color {
-            Black => self.black += gems,
-            White => self.white += gems,
-            Red => self.red += gems,
-            Blue => self.blue += gems,
-            Green => self.green += gems,
-            _ => unreachable!(),
+            Gold => unreachable!(),
+            _ => color as usize,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Gem;
+    use crate::game::color::Color::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let a = Gem::from([1, 2, 3, 0, 0]);
+        let b = Gem::from([1, 1, 4, 0, 2]);
+
+        assert_eq!(a + b, Gem::from([2, 3, 7, 0, 2]));
+        assert_eq!(a.saturating_sub(&b), Gem::from([0, 1, 0, 0, 0]));
+        assert_eq!(a.min(&b), Gem::from([1, 1, 3, 0, 0]));
+        assert_eq!(a.total(), 6);
+        assert_eq!(a.get(Red), 3);
+        assert_eq!(
+            a.iter().collect::<Vec<_>>(),
+            vec![(Black, 1), (White, 2), (Red, 3), (Blue, 0), (Green, 0)]
+        );
+    }
+
+    #[test]
+    fn test_dominates_and_shortfall() {
+        let owned = Gem::from([2, 2, 2, 0, 0]);
+
+        assert!(owned.dominates(&Gem::from([2, 1, 0, 0, 0])));
+        assert!(!owned.dominates(&Gem::from([0, 0, 0, 1, 0])));
+        assert_eq!(owned.shortfall(&Gem::from([2, 1, 0, 0, 0])), 0);
+        assert_eq!(owned.shortfall(&Gem::from([3, 0, 4, 1, 0])), 4);
+    }
+}
@@ -6,3 +6,6 @@ pub enum Level {
     Two,
     Three,
 }
+
+// 低いレベルから順に
+pub const LEVELS: [Level; 3] = [Level::One, Level::Two, Level::Three];
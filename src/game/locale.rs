@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+// 言語を選ぶ環境変数 (en か ja)
+pub const LANG_VAR: &str = "SPLENDOR_LANG";
+
+const EN: &str = include_str!("../../data/locale/en.toml");
+const JA: &str = include_str!("../../data/locale/ja.toml");
+
+// 0 はまだ決めていない
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+static CATALOGS: OnceLock<[HashMap<String, String>; 2]> = OnceLock::new();
+
+// 画面に出す文章の言語
+// 文章は data/locale/*.toml に [表] と キー = "文章" で書き、"表.キー" で引く
+// Debug の出力はログ用なので言語によらない
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Locale {
+    En,
+    Ja,
+}
+
+impl FromStr for Locale {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Locale, String> {
+        match s.to_lowercase().as_str() {
+            "en" | "english" => Ok(Locale::En),
+            "ja" | "japanese" => Ok(Locale::Ja),
+            _ => Err(message("main.unknown_lang", &[&s])),
+        }
+    }
+}
+
+impl Locale {
+    // set で選んだ言語、なければ環境変数 SPLENDOR_LANG、それもなければ日本語
+    // 環境変数は最初の 1回だけ読む
+    pub fn current() -> Locale {
+        match CURRENT.load(Ordering::Relaxed) {
+            1 => Locale::En,
+            2 => Locale::Ja,
+            _ => {
+                let locale = env::var(LANG_VAR)
+                    .ok()
+                    .and_then(|lang| lang.parse().ok())
+                    .unwrap_or(Locale::Ja);
+                Locale::set(locale);
+                locale
+            }
+        }
+    }
+
+    pub fn set(locale: Locale) {
+        let value = match locale {
+            Locale::En => 1,
+            Locale::Ja => 2,
+        };
+        CURRENT.store(value, Ordering::Relaxed);
+    }
+
+    // キーがなければキーをそのまま返す
+    pub fn text(self, key: &'static str) -> &'static str {
+        Locale::catalogs()[self as usize]
+            .get(key)
+            .map_or(key, String::as_str)
+    }
+
+    // 文章の {0}, {1}, ... を args で置き換える
+    pub fn format(self, key: &'static str, args: &[&dyn fmt::Display]) -> String {
+        let text = self.text(key);
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let index = after
+                .find('}')
+                .and_then(|end| after[..end].parse::<usize>().ok().map(|i| (i, end)));
+            match index {
+                Some((i, end)) => {
+                    if let Some(arg) = args.get(i) {
+                        result.push_str(&arg.to_string());
+                    }
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    result.push('{');
+                    rest = after;
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+
+    fn catalogs() -> &'static [HashMap<String, String>; 2] {
+        CATALOGS.get_or_init(|| [Locale::parse(EN), Locale::parse(JA)])
+    }
+
+    fn parse(source: &str) -> HashMap<String, String> {
+        let table = match source.parse::<toml::Value>() {
+            Ok(toml::Value::Table(table)) => table,
+            _ => panic!("メッセージのファイルが正しくありません"),
+        };
+        let mut messages = HashMap::new();
+        for (section, values) in table.iter() {
+            if let toml::Value::Table(values) = values {
+                for (key, value) in values.iter() {
+                    if let Some(text) = value.as_str() {
+                        messages.insert(format!("{}.{}", section, key), text.to_string());
+                    }
+                }
+            }
+        }
+        messages
+    }
+}
+
+// 今の言語の文章
+pub fn text(key: &'static str) -> &'static str {
+    Locale::current().text(key)
+}
+
+// 今の言語の文章に引数を埋め込む
+pub fn message(key: &'static str, args: &[&dyn fmt::Display]) -> String {
+    Locale::current().format(key, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Locale;
+
+    fn keys(locale: Locale) -> Vec<&'static str> {
+        let mut keys: Vec<&'static str> = Locale::catalogs()[locale as usize]
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    fn placeholders(text: &str) -> Vec<char> {
+        let mut numbers: Vec<char> = text
+            .split('{')
+            .skip(1)
+            .filter_map(|s| s.chars().next())
+            .collect();
+        numbers.sort();
+        numbers
+    }
+
+    #[test]
+    fn test_catalogs() {
+        let en = keys(Locale::En);
+        assert_eq!(en, keys(Locale::Ja));
+        for key in en {
+            assert_eq!(
+                placeholders(Locale::En.text(key)),
+                placeholders(Locale::Ja.text(key)),
+                "{}",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(Locale::En.text("color.red"), "Red");
+        assert_eq!(Locale::Ja.text("color.red"), "赤");
+        assert_eq!(
+            Locale::En.format("affordability.missing", &[&"Red", &2]),
+            "2 more Red"
+        );
+        assert_eq!(
+            Locale::Ja.format("affordability.missing", &[&"赤", &2]),
+            "赤があと2つ"
+        );
+        assert_eq!(Locale::En.text("no.such_key"), "no.such_key");
+        assert_eq!("EN".parse::<Locale>(), Ok(Locale::En));
+        assert!("fr".parse::<Locale>().is_err());
+    }
+}
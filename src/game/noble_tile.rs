@@ -1,16 +1,29 @@
 use std::fmt;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
 
+use crate::game::card_catalog::{CardCatalog, DataError};
 use crate::game::color::Color;
 use crate::game::color::Color::*;
-use crate::game::gem::{Gem, GEMS};
+use crate::game::gem::Gem;
+use crate::game::locale::message;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use serde_derive::{Deserialize, Serialize};
 
 const MAX_NUMBER_OF_TILES: u8 = 4;
 
+// id は data/noble_tile.json で割り振った CardCatalog の添字
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(from = "NobleTileRecord", into = "NobleTileRecord")]
 pub struct NobleTile {
+    id: u8,
+    point: u8,
+    bonus: Gem,
+}
+
+// data/noble_tile.json の 1行分
+#[derive(Serialize, Deserialize, Clone)]
+struct NobleTileRecord {
+    id: u8,
     point: u8,
     black_bonus: u8,
     white_bonus: u8,
@@ -21,73 +34,80 @@ pub struct NobleTile {
 
 impl fmt::Debug for NobleTile {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}点 {} {} {} {} {}",
-            self.point,
-            self.black_bonus,
-            self.white_bonus,
-            self.red_bonus,
-            self.blue_bonus,
-            self.green_bonus,
-        )
+        write!(f, "#{} {}vp ({:?})", self.id, self.point, self.bonus)
     }
 }
 
 impl fmt::Display for NobleTile {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}点 {} {} {} {} {}",
-            self.point,
-            self.black_bonus,
-            self.white_bonus,
-            self.red_bonus,
-            self.blue_bonus,
-            self.green_bonus,
-        )
+        let bonus = format!("{:?}", self.bonus);
+        f.write_str(&message(
+            "noble_tile.display",
+            &[&self.id, &self.point, &bonus],
+        ))
     }
 }
 
-impl NobleTile {
-    pub fn create_stack() -> Vec<NobleTile> {
-        let mut stack = vec![];
+impl From<NobleTileRecord> for NobleTile {
+    fn from(record: NobleTileRecord) -> NobleTile {
+        NobleTile {
+            id: record.id,
+            point: record.point,
+            bonus: Gem::from([
+                record.black_bonus,
+                record.white_bonus,
+                record.red_bonus,
+                record.blue_bonus,
+                record.green_bonus,
+            ]),
+        }
+    }
+}
 
-        for result in BufReader::new(File::open("data/noble_tile.json").unwrap()).lines() {
-            let l = result.unwrap();
-            let tile: NobleTile = serde_json::from_str(&l).unwrap();
-            stack.push(tile);
+impl From<NobleTile> for NobleTileRecord {
+    fn from(tile: NobleTile) -> NobleTileRecord {
+        NobleTileRecord {
+            id: tile.id,
+            point: tile.point,
+            black_bonus: tile.bonus.get(Black),
+            white_bonus: tile.bonus.get(White),
+            red_bonus: tile.bonus.get(Red),
+            blue_bonus: tile.bonus.get(Blue),
+            green_bonus: tile.bonus.get(Green),
         }
+    }
+}
 
-        use rand::seq::SliceRandom;
-        let mut rng = rand::thread_rng();
-        stack.shuffle(&mut rng);
+impl NobleTile {
+    pub fn create_stack() -> Vec<NobleTile> {
+        NobleTile::create_stack_with_rng(&mut rand::thread_rng())
+    }
+    pub fn create_stack_with_rng<R: Rng + ?Sized>(rng: &mut R) -> Vec<NobleTile> {
+        let mut stack = CardCatalog::global().noble_tiles().to_vec();
+        stack.shuffle(rng);
 
         for _ in 0..10 - MAX_NUMBER_OF_TILES {
             stack.pop();
         }
         stack
     }
+    // 値の範囲は検証しない (CardCatalog::validate を使う)
+    pub fn load(file_name: &str) -> Result<Vec<NobleTile>, DataError> {
+        CardCatalog::read_records(file_name)
+    }
+    pub fn get_id(&self) -> u8 {
+        self.id
+    }
     pub fn get_point(&self) -> u8 {
         self.point
     }
     pub fn can_visit(&self, gems: &Gem) -> bool {
-        for color in GEMS.iter() {
-            if self.get_bonus(*color) > gems.get(*color) {
-                return false;
-            }
-        }
-
-        return true;
+        gems.dominates(&self.bonus)
     }
-    fn get_bonus(&self, color: Color) -> u8 {
-        match color {
-            Black => self.black_bonus,
-            White => self.white_bonus,
-            Red => self.red_bonus,
-            Blue => self.blue_bonus,
-            Green => self.green_bonus,
-            _ => unreachable!(),
-        }
+    pub fn get_bonus(&self, color: Color) -> u8 {
+        self.bonus.get(color)
+    }
+    pub fn get_required(&self) -> &Gem {
+        &self.bonus
     }
 }
@@ -0,0 +1,446 @@
+use crate::game::board::{Board, COORDINATE};
+use crate::game::card_catalog::CardCatalog;
+use crate::game::card_stack::{Card, CardStack};
+use crate::game::card_tracker::CardTracker;
+use crate::game::color::Color;
+use crate::game::level::{Level, LEVELS};
+use crate::game::locale::{message, text};
+use crate::game::noble_tile::NobleTile;
+use crate::game::token_stack::TokenStack;
+use crate::game::user::User;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt;
+
+// seat のユーザーから見える局面
+// 山札は枚数だけ、他のユーザーが山札から伏せたまま確保したカードはレベルだけが見える
+#[derive(Clone)]
+pub struct Observation {
+    seat: usize,
+    turn: u32,
+    grid: [[Option<u8>; 4]; 3],
+    deck_sizes: [u8; 3],
+    token_stack: TokenStack,
+    noble_tile: Vec<NobleTile>,
+    players: Vec<PlayerView>,
+}
+
+// ユーザー 1人分の見えている情報
+#[derive(Clone)]
+pub struct PlayerView {
+    pub id: u8,
+    pub vp: u8,
+    pub acquired_card: Vec<u8>,
+    pub token_stack: TokenStack,
+    pub hand: Vec<Reserved>,
+}
+
+// 確保したカード
+// Hidden は他のユーザーが山札から確保したカードで、レベルしかわからない
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reserved {
+    Card(u8),
+    Hidden(Level),
+}
+
+impl fmt::Display for Reserved {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reserved::Card(id) => write!(f, "{}", CardCatalog::global().card(*id)),
+            Reserved::Hidden(level) => f.write_str(&message("card.hidden", &[&(*level as u8 + 1)])),
+        }
+    }
+}
+
+impl fmt::Display for Observation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let noble_tiles: Vec<String> = self.noble_tile.iter().map(NobleTile::to_string).collect();
+        writeln!(f, "{}", message("observation.header", &[&self.seat]))?;
+        writeln!(
+            f,
+            "{}",
+            message(
+                "board.noble_tiles",
+                &[&format!("[{}]", noble_tiles.join(", "))]
+            )
+        )?;
+        writeln!(f, "{}", "-".repeat(94))?;
+        for x in 0..3 {
+            let slots: Vec<String> = (0..4)
+                .map(|y| match self.peek_card(x, y) {
+                    Some(card) => card.to_string(),
+                    None => text("board.empty").to_string(),
+                })
+                .collect();
+            let remaining = self.get_number_of_stack_cards(Observation::level_of_row(x));
+            writeln!(
+                f,
+                "{}  {}",
+                slots.join("| "),
+                message("board.remaining", &[&remaining])
+            )?;
+        }
+        writeln!(f, "{}", "-".repeat(94))?;
+        let tokens = format!(
+            "{:?} {}",
+            self.token_stack.get_gems(),
+            self.token_stack.len(Color::Gold)
+        );
+        writeln!(f, "{}", message("board.tokens", &[&tokens]))?;
+        for player in self.players.iter() {
+            let hand: Vec<String> = player.hand.iter().map(Reserved::to_string).collect();
+            writeln!(
+                f,
+                "{}",
+                message(
+                    "observation.player",
+                    &[
+                        &player.id,
+                        &player.vp,
+                        &player.acquired_card.len(),
+                        &hand.join(", ")
+                    ]
+                )
+            )?;
+            writeln!(f, "  {}", player.token_stack)?;
+        }
+        Ok(())
+    }
+}
+
+impl Observation {
+    // users[seat] から見える局面を作る
+    pub fn new(users: &[User], board: &Board, seat: usize) -> Observation {
+        let mut grid = [[None; 4]; 3];
+        for (x, y) in COORDINATE.iter() {
+            grid[*x as usize][*y as usize] = board.peek_card_id(*x, *y);
+        }
+
+        let players = users
+            .iter()
+            .enumerate()
+            .map(|(index, user)| PlayerView {
+                id: user.get_id(),
+                vp: user.get_vp(),
+                acquired_card: user.get_acquired_card_ids().to_vec(),
+                token_stack: user.peek_token_stack().clone(),
+                hand: user
+                    .get_hand_ids()
+                    .iter()
+                    .enumerate()
+                    .map(|(order, id)| {
+                        if index != seat && user.is_face_down(order as u8) {
+                            Reserved::Hidden(Observation::level_of(CardCatalog::global().card(*id)))
+                        } else {
+                            Reserved::Card(*id)
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Observation {
+            seat,
+            turn: 1,
+            grid,
+            deck_sizes: [
+                board.get_number_of_stack_cards(Level::One),
+                board.get_number_of_stack_cards(Level::Two),
+                board.get_number_of_stack_cards(Level::Three),
+            ],
+            token_stack: board.peek_token_stack().clone(),
+            noble_tile: board.peek_noble_tile().to_vec(),
+            players,
+        }
+    }
+
+    // 今の手番目 (MAX_NUMBER_OF_TURNS までの残りを読む探索に使う)
+    pub fn turn(mut self, turn: u32) -> Observation {
+        self.turn = turn;
+        self
+    }
+
+    pub fn get_seat(&self) -> usize {
+        self.seat
+    }
+    pub fn get_turn(&self) -> u32 {
+        self.turn
+    }
+    pub fn peek_card(&self, x: u8, y: u8) -> Option<&Card> {
+        self.peek_card_id(x, y)
+            .map(|id| CardCatalog::global().card(id))
+    }
+    pub fn peek_card_id(&self, x: u8, y: u8) -> Option<u8> {
+        *self.grid.get(x as usize)?.get(y as usize)?
+    }
+    pub fn get_number_of_stack_cards(&self, level: Level) -> u8 {
+        self.deck_sizes[level as usize]
+    }
+    pub fn get_number_of_tokens(&self, color: Color) -> u8 {
+        self.token_stack.len(color)
+    }
+    pub fn peek_token_stack(&self) -> &TokenStack {
+        &self.token_stack
+    }
+    pub fn peek_noble_tile(&self) -> &[NobleTile] {
+        &self.noble_tile
+    }
+    pub fn players(&self) -> &[PlayerView] {
+        &self.players
+    }
+    pub fn me(&self) -> &PlayerView {
+        &self.players[self.seat]
+    }
+
+    // level のカードのうち、seat のユーザーから見えていないものの id (id の順)
+    // 山札と、他のユーザーが伏せたまま確保したカードのどちらか
+    pub fn unseen(&self, level: Level) -> Vec<u8> {
+        let mut seen = vec![];
+        for row in self.grid.iter() {
+            seen.extend(row.iter().flatten());
+        }
+        for player in self.players.iter() {
+            seen.extend(player.acquired_card.iter());
+            seen.extend(player.hand.iter().filter_map(|reserved| match reserved {
+                Reserved::Card(id) => Some(*id),
+                Reserved::Hidden(_) => None,
+            }));
+        }
+
+        CardCatalog::global()
+            .card_ids(level)
+            .into_iter()
+            .filter(|id| !seen.contains(id))
+            .collect()
+    }
+
+    // 他のユーザーが伏せたまま確保している level のカードの枚数
+    pub fn hidden(&self, level: Level) -> u8 {
+        self.players
+            .iter()
+            .flat_map(|player| player.hand.iter())
+            .filter(|reserved| **reserved == Reserved::Hidden(level))
+            .count() as u8
+    }
+
+    // seat のユーザーがまだ見ていないカードと、山札から次に引くカードの確率
+    // 一度見えたカードは場、取得したカード、表向きの手札のどこかに残るので、この観測だけで数えられる
+    pub fn card_tracker(&self) -> CardTracker {
+        let mut tracker = CardTracker::new(self.seat);
+        tracker.observe(self);
+        tracker
+    }
+
+    // 見えていないカードを割り当てて局面を組み立てる
+    // 伏せたカードと山札の中身は見えていないカードを id の順に割り当てた推測で、実際の局面とは限らない
+    pub fn to_game(&self) -> (Vec<User>, Board) {
+        let unseen = LEVELS.iter().map(|level| self.unseen(*level)).collect();
+        self.assemble(Some(unseen))
+    }
+
+    // to_game と同じく組み立てるが、見えていないカードは rng で並べ替えてから割り当てる
+    // 探索のたびに呼べば、推測した山札の順番に偏らない
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> (Vec<User>, Board) {
+        let unseen = LEVELS
+            .iter()
+            .map(|level| {
+                let mut ids = self.unseen(*level);
+                ids.shuffle(rng);
+                ids
+            })
+            .collect();
+        self.assemble(Some(unseen))
+    }
+
+    // 見えているカードだけで局面を組み立てる
+    // 山札は空で場のカードを取っても補充されず、他のユーザーが伏せたまま確保したカードは手札から除く
+    // 見えていないカードに頼った読みをしてはいけない探索 (Solver) に使う
+    pub fn to_known_game(&self) -> (Vec<User>, Board) {
+        self.assemble(None)
+    }
+
+    // unseen が None なら伏せたカードは除き、山札は空にする
+    fn assemble(&self, mut unseen: Option<Vec<Vec<u8>>>) -> (Vec<User>, Board) {
+        let users = self
+            .players
+            .iter()
+            .map(|player| {
+                let mut hand = vec![];
+                let mut face_down = vec![];
+                for reserved in player.hand.iter() {
+                    match (reserved, unseen.as_mut()) {
+                        (Reserved::Card(id), _) => hand.push(*id),
+                        (Reserved::Hidden(level), Some(unseen)) => {
+                            let id = unseen[*level as usize].remove(0);
+                            hand.push(id);
+                            face_down.push(id);
+                        }
+                        (Reserved::Hidden(_), None) => {}
+                    }
+                }
+                User::from_parts(
+                    player.id,
+                    player.vp,
+                    hand,
+                    face_down,
+                    player.acquired_card.clone(),
+                    player.token_stack.clone(),
+                )
+            })
+            .collect();
+
+        let mut unseen = unseen.unwrap_or_else(|| vec![vec![]; 3]);
+        let level3 = unseen.pop().unwrap();
+        let level2 = unseen.pop().unwrap();
+        let level1 = unseen.pop().unwrap();
+        let board = Board::from_parts(
+            self.grid,
+            CardStack::from_ids(level1, level2, level3),
+            self.token_stack.clone(),
+            self.noble_tile.clone(),
+        );
+
+        (users, board)
+    }
+
+    fn level_of(card: &Card) -> Level {
+        LEVELS[card.level as usize - 1]
+    }
+
+    // 場の x 行目のカードのレベル
+    fn level_of_row(x: u8) -> Level {
+        LEVELS[2 - x as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Observation, Reserved};
+    use crate::game::board::{Board, COORDINATE};
+    use crate::game::color::Color;
+    use crate::game::game_command::GameCommand;
+    use crate::game::level::Level;
+    use crate::game::user::User;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn setup() -> (Vec<User>, Board, u8, u8) {
+        let mut users = vec![User::new(0), User::new(1)];
+        let mut board = Board::new();
+        let hidden = *board.peek_stack(Level::Two).last().unwrap();
+        let visible = board.peek_card_id(0, 0).unwrap();
+        GameCommand::reserve_stack_card(Level::Two, &mut users[1], &mut board).unwrap();
+        GameCommand::reserve_development_card(0, 0, &mut users[1], &mut board).unwrap();
+        (users, board, hidden, visible)
+    }
+
+    #[test]
+    fn test_hidden_reserve() {
+        let (users, board, hidden, visible) = setup();
+
+        let observation = Observation::new(&users, &board, 0);
+        assert_eq!(
+            observation.players()[1].hand,
+            vec![Reserved::Hidden(Level::Two), Reserved::Card(visible)]
+        );
+        assert_eq!(observation.get_number_of_stack_cards(Level::Two), 25);
+        // 伏せたカードはレベルだけを表示する
+        let hidden_text = Reserved::Hidden(Level::Two).to_string();
+        assert!(observation.to_string().contains(&hidden_text));
+
+        let observation = Observation::new(&users, &board, 1);
+        assert_eq!(observation.get_seat(), 1);
+        assert_eq!(
+            observation.me().hand,
+            vec![Reserved::Card(hidden), Reserved::Card(visible)]
+        );
+    }
+
+    #[test]
+    fn test_unseen() {
+        let (users, board, hidden, visible) = setup();
+
+        let observation = Observation::new(&users, &board, 0);
+        let unseen = observation.unseen(Level::Two);
+        assert_eq!(unseen.len(), 26);
+        assert!(unseen.contains(&hidden));
+        assert_eq!(observation.unseen(Level::Three).len(), 15);
+        assert!(!observation.unseen(Level::Three).contains(&visible));
+
+        let observation = Observation::new(&users, &board, 1);
+        assert_eq!(observation.unseen(Level::Two).len(), 25);
+        assert!(!observation.unseen(Level::Two).contains(&hidden));
+
+        // エージェントは観測から CardTracker を作れる
+        let observation = Observation::new(&users, &board, 0);
+        let tracker = observation.card_tracker();
+        assert_eq!(tracker.get_seat(), 0);
+        assert_eq!(
+            tracker.unseen(Level::Two),
+            &observation.unseen(Level::Two)[..]
+        );
+        assert_eq!(tracker.hidden(Level::Two), observation.hidden(Level::Two));
+        assert_eq!(observation.hidden(Level::Two), 1);
+    }
+
+    #[test]
+    fn test_to_game() {
+        let (users, board, hidden, visible) = setup();
+
+        let (guessed_users, guessed_board) = Observation::new(&users, &board, 0).to_game();
+        assert_eq!(guessed_users.len(), 2);
+        assert_eq!(guessed_users[1].get_number_of_hands(), 2);
+        assert_eq!(guessed_users[1].get_hand_ids()[1], visible);
+        assert!(guessed_users[1].is_face_down(0));
+        assert_eq!(guessed_users[1].get_number_of_tokens(Color::Gold), 2);
+        for (x, y) in COORDINATE.iter() {
+            assert_eq!(
+                guessed_board.peek_card_id(*x, *y),
+                board.peek_card_id(*x, *y)
+            );
+        }
+        for level in [Level::One, Level::Two, Level::Three].iter() {
+            assert_eq!(
+                guessed_board.get_number_of_stack_cards(*level),
+                board.get_number_of_stack_cards(*level)
+            );
+        }
+
+        // 自分の手札はそのまま
+        let (guessed_users, _) = Observation::new(&users, &board, 1).to_game();
+        assert_eq!(guessed_users[1].get_hand_ids(), &[hidden, visible][..]);
+    }
+
+    #[test]
+    fn test_to_known_game() {
+        let (users, board, _, visible) = setup();
+
+        let (known_users, known_board) = Observation::new(&users, &board, 0).to_known_game();
+        assert_eq!(known_users[1].get_hand_ids(), &[visible][..]);
+        for level in [Level::One, Level::Two, Level::Three].iter() {
+            assert_eq!(known_board.get_number_of_stack_cards(*level), 0);
+        }
+        for (x, y) in COORDINATE.iter() {
+            assert_eq!(known_board.peek_card_id(*x, *y), board.peek_card_id(*x, *y));
+        }
+    }
+
+    #[test]
+    fn test_sample() {
+        let (users, board, _, _) = setup();
+        let observation = Observation::new(&users, &board, 0);
+
+        // 並べ替えても見えていないカードの集まりは変わらない
+        let mut rng = StdRng::seed_from_u64(1);
+        let (_, sampled_board) = observation.sample(&mut rng);
+        let mut sampled = sampled_board.peek_stack(Level::One).to_vec();
+        sampled.sort();
+        assert_eq!(sampled, observation.unseen(Level::One));
+        assert_ne!(
+            sampled_board.peek_stack(Level::One),
+            observation.to_game().1.peek_stack(Level::One)
+        );
+    }
+}
@@ -0,0 +1,377 @@
+use crate::game::card_catalog::CardCatalog;
+use crate::game::card_stack::Card;
+use crate::game::color::Color;
+use crate::game::gem::{Gem, GEMS};
+use crate::game::level::Level;
+use crate::game::locale::{message, text};
+use crate::game::noble_tile::NobleTile;
+use crate::game::observation::{Observation, PlayerView, Reserved};
+
+use std::env;
+use std::io::{self, IsTerminal};
+
+// カードの枠の内側の幅
+pub const CARD_WIDTH: usize = 16;
+
+// 色をつけない環境変数 (https://no-color.org)
+const NO_COLOR: &str = "NO_COLOR";
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const REVERSE: &str = "\x1b[7m";
+const ROWS: [(u8, Level); 3] = [(0, Level::Three), (1, Level::Two), (2, Level::One)];
+
+// 局面を端末に出す文字列にする
+// color なら ANSI エスケープで宝石の色をつけ、そうでなければ色の略称 (黒 や K) で表す
+pub struct Renderer {
+    color: bool,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer {
+    // 標準出力が端末で、NO_COLOR がなければ色をつける
+    pub fn new() -> Renderer {
+        Renderer {
+            color: io::stdout().is_terminal() && env::var_os(NO_COLOR).is_none(),
+        }
+    }
+
+    pub fn color(mut self, color: bool) -> Renderer {
+        self.color = color;
+        self
+    }
+
+    pub fn is_color(&self) -> bool {
+        self.color
+    }
+
+    // 貴族タイル、場のカード、残りのトークン、各ユーザーの順に並べる
+    pub fn render(&self, observation: &Observation) -> String {
+        let mut lines = vec![message("observation.header", &[&observation.get_seat()])];
+
+        let noble_tiles: Vec<String> = observation
+            .peek_noble_tile()
+            .iter()
+            .map(|noble_tile| self.noble_tile(noble_tile))
+            .collect();
+        lines.push(format!(
+            "{}: {}",
+            text("render.nobles"),
+            noble_tiles.join("  ")
+        ));
+
+        for (x, level) in ROWS.iter() {
+            let boxes: Vec<Vec<String>> = (0..4)
+                .map(|y| self.card_box(observation.peek_card(*x, y)))
+                .collect();
+            for line in 0..boxes[0].len() {
+                let row: Vec<&str> = boxes.iter().map(|b| b[line].as_str()).collect();
+                let mut row = row.join(" ");
+                if line == 1 {
+                    let deck = observation.get_number_of_stack_cards(*level);
+                    row.push_str("  ");
+                    row.push_str(&message("render.deck", &[&(*level as u8 + 1), &deck]));
+                }
+                lines.push(row);
+            }
+        }
+
+        let supply = observation.peek_token_stack();
+        lines.push(format!(
+            "{}: {}",
+            text("render.supply"),
+            self.tokens(&supply.get_gems(), supply.len(Color::Gold))
+        ));
+        for (seat, player) in observation.players().iter().enumerate() {
+            lines.push(String::new());
+            lines.extend(self.player(seat, player, seat == observation.get_seat()));
+        }
+
+        let mut result = lines.join("\n");
+        result.push('\n');
+        result
+    }
+
+    // 枠で囲んだカード (4行、どの行も幅は CARD_WIDTH + 2)
+    // 1行目はボーナスの色と id と勝利点、2行目はコスト
+    pub fn card_box(&self, card: Option<&Card>) -> Vec<String> {
+        let border = "─".repeat(CARD_WIDTH);
+        let card = match card {
+            Some(card) => card,
+            None => {
+                let empty = text("board.empty");
+                let left = (CARD_WIDTH - width(empty)) / 2;
+                return vec![
+                    format!("┌{}┐", border),
+                    format!(
+                        "│{}│",
+                        pad(&format!("{}{}", " ".repeat(left), empty), CARD_WIDTH)
+                    ),
+                    format!("│{}│", " ".repeat(CARD_WIDTH)),
+                    format!("└{}┘", border),
+                ];
+            }
+        };
+
+        let head = format!("{} #{}", self.gem(card.color), card.id);
+        let points = if card.point > 0 {
+            self.bold(&message("render.points", &[&card.point]))
+        } else {
+            String::new()
+        };
+        let space = CARD_WIDTH.saturating_sub(width(&head) + width(&points));
+        let first = format!("{}{}{}", head, " ".repeat(space), points);
+        let second = self.pips(&card.cost, false);
+        vec![
+            self.paint(card.color, &format!("┌{}┐", border)),
+            format!(
+                "{}{}{}",
+                self.paint(card.color, "│"),
+                pad(&first, CARD_WIDTH),
+                self.paint(card.color, "│")
+            ),
+            format!(
+                "{}{}{}",
+                self.paint(card.color, "│"),
+                pad(&second, CARD_WIDTH),
+                self.paint(card.color, "│")
+            ),
+            self.paint(card.color, &format!("└{}┘", border)),
+        ]
+    }
+
+    // 1行のカード (手札など)
+    pub fn card(&self, card: &Card) -> String {
+        let mut line = format!("{} #{}", self.gem(card.color), card.id);
+        if card.point > 0 {
+            line.push(' ');
+            line.push_str(&self.bold(&message("render.points", &[&card.point])));
+        }
+        format!("{} ({})", line, self.pips(&card.cost, false))
+    }
+
+    pub fn noble_tile(&self, noble_tile: &NobleTile) -> String {
+        format!(
+            "#{} {} ({})",
+            noble_tile.get_id(),
+            self.bold(&message("render.points", &[&noble_tile.get_point()])),
+            self.pips(noble_tile.get_required(), false)
+        )
+    }
+
+    // 宝石トークンと金トークンの個数 (0個の色も出す)
+    pub fn tokens(&self, gems: &Gem, gold: u8) -> String {
+        format!("{} {}", self.pips(gems, true), self.pip(Color::Gold, gold))
+    }
+
+    // 色と個数
+    pub fn pip(&self, color: Color, n: u8) -> String {
+        format!("{}{}", self.gem(color), n)
+    }
+
+    // seat のユーザーの勝利点、色ごとのボーナスとトークン、手札
+    pub fn player(&self, seat: usize, player: &PlayerView, current: bool) -> Vec<String> {
+        let catalog = CardCatalog::global();
+        let mut bonus = Gem::new();
+        for id in player.acquired_card.iter() {
+            bonus.add(catalog.card(*id).color, 1);
+        }
+        let hand: Vec<String> = player
+            .hand
+            .iter()
+            .map(|reserved| match reserved {
+                Reserved::Card(id) => self.card(catalog.card(*id)),
+                Reserved::Hidden(_) => reserved.to_string(),
+            })
+            .collect();
+
+        let marker = if current { "▶" } else { " " };
+        let name = message("render.player", &[&seat]);
+        let points = message("render.points", &[&player.vp]);
+        vec![
+            format!(
+                "{} {}  {}  {}",
+                marker,
+                self.bold(&name),
+                self.bold(&points),
+                message("render.cards", &[&player.acquired_card.len()])
+            ),
+            format!("  {}: {}", text("render.bonus"), self.pips(&bonus, true)),
+            format!(
+                "  {}: {}",
+                text("render.tokens"),
+                self.tokens(
+                    &player.token_stack.get_gems(),
+                    player.token_stack.len(Color::Gold)
+                )
+            ),
+            format!("  {}: {}", text("render.hand"), hand.join(", ")),
+        ]
+    }
+
+    // all でなければ 0個の色を省く
+    fn pips(&self, gems: &Gem, all: bool) -> String {
+        let pips: Vec<String> = GEMS
+            .iter()
+            .filter(|color| all || gems.get(**color) > 0)
+            .map(|color| self.pip(*color, gems.get(*color)))
+            .collect();
+        pips.join(" ")
+    }
+
+    fn gem(&self, color: Color) -> String {
+        if self.color {
+            self.paint(color, "●")
+        } else {
+            Renderer::short_name(color).to_string()
+        }
+    }
+
+    fn paint(&self, color: Color, s: &str) -> String {
+        if !self.color {
+            return s.to_string();
+        }
+        let code = match color {
+            Color::Black => "\x1b[90m",
+            Color::White => "\x1b[97m",
+            Color::Red => "\x1b[31m",
+            Color::Blue => "\x1b[34m",
+            Color::Green => "\x1b[32m",
+            Color::Gold => "\x1b[33m",
+        };
+        format!("{}{}{}", code, s, RESET)
+    }
+
+    pub fn bold(&self, s: &str) -> String {
+        self.style(BOLD, s)
+    }
+
+    // 選べるものを目立たせる
+    pub fn reverse(&self, s: &str) -> String {
+        self.style(REVERSE, s)
+    }
+
+    // 選べないものを目立たなくする
+    pub fn dim(&self, s: &str) -> String {
+        self.style(DIM, s)
+    }
+
+    // s の中で色を戻したところにも code をかけ直す
+    fn style(&self, code: &str, s: &str) -> String {
+        if self.color {
+            let s = s.replace(RESET, &format!("{}{}", RESET, code));
+            format!("{}{}{}", code, s, RESET)
+        } else {
+            s.to_string()
+        }
+    }
+
+    fn short_name(color: Color) -> &'static str {
+        match color {
+            Color::Black => text("color.short_black"),
+            Color::White => text("color.short_white"),
+            Color::Red => text("color.short_red"),
+            Color::Blue => text("color.short_blue"),
+            Color::Green => text("color.short_green"),
+            Color::Gold => text("color.short_gold"),
+        }
+    }
+}
+
+// 端末で表示したときの幅
+// ANSI エスケープは幅 0、全角の文字は幅 2 として数える
+pub fn width(s: &str) -> usize {
+    let mut width = 0;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        width += if is_wide(c) { 2 } else { 1 };
+    }
+    width
+}
+
+// 幅が width になるまで右を空白で埋める
+pub fn pad(s: &str, width: usize) -> String {
+    let space = width.saturating_sub(self::width(s));
+    format!("{}{}", s, " ".repeat(space))
+}
+
+fn is_wide(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{width, Renderer, CARD_WIDTH};
+    use crate::game::board::Board;
+    use crate::game::card_catalog::CardCatalog;
+    use crate::game::game_command::GameCommand;
+    use crate::game::level::Level;
+    use crate::game::observation::Observation;
+    use crate::game::user::User;
+
+    #[test]
+    fn test_width() {
+        assert_eq!(width("abc"), 3);
+        assert_eq!(width("黒3"), 3);
+        assert_eq!(width("\x1b[31m●\x1b[0m2"), 2);
+        assert_eq!(width("┌─┐"), 3);
+    }
+
+    #[test]
+    fn test_card_box() {
+        let catalog = CardCatalog::global();
+        for color in [false, true].iter() {
+            let renderer = Renderer::new().color(*color);
+            for id in 0..90 {
+                for line in renderer.card_box(Some(catalog.card(id))) {
+                    assert_eq!(width(&line), CARD_WIDTH + 2, "{}", line);
+                }
+            }
+            for line in renderer.card_box(None) {
+                assert_eq!(width(&line), CARD_WIDTH + 2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let mut users = vec![User::new(0), User::new(1)];
+        let mut board = Board::new();
+        let id = board.peek_card_id(2, 1).unwrap();
+        GameCommand::reserve_stack_card(Level::One, &mut users[1], &mut board).unwrap();
+        let observation = Observation::new(&users, &board, 0);
+
+        let plain = Renderer::new().color(false).render(&observation);
+        assert!(!plain.contains('\x1b'));
+        assert!(plain.contains(&format!("#{}", id)));
+        assert!(plain.contains("▶"));
+
+        let colored = Renderer::new().color(true).render(&observation);
+        assert!(colored.contains("\x1b[31m"));
+        assert_eq!(plain.lines().count(), colored.lines().count());
+    }
+}
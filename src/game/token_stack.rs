@@ -1,15 +1,21 @@
 use crate::game::color::Color;
 use crate::game::color::Color::*;
+use crate::game::gem::Gem;
+use crate::game::locale::message;
 
-use std::collections::HashMap;
 use std::fmt;
 
 mod token;
 
-const MAX_NUMBER_OF_TOKEN: usize = 5;
+// 場にある色ごとのトークンの数
+pub const MAX_NUMBER_OF_TOKEN: u8 = 5;
 
-#[derive(Clone)]
-pub struct TokenStack(HashMap<Color, Vec<Token>>);
+// 宝石トークンは色ごとの個数、金トークンは別に個数で持つ
+#[derive(Clone, Default)]
+pub struct TokenStack {
+    gems: Gem,
+    gold: u8,
+}
 
 #[derive(Clone, PartialEq)]
 pub struct Token {
@@ -18,55 +24,54 @@ pub struct Token {
 
 impl fmt::Display for TokenStack {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "取得したトークン:\n{:?}", self.0)
+        let gems = format!("{:?}", self.gems);
+        f.write_str(&message("token_stack.display", &[&gems, &self.gold]))
     }
 }
 
 impl TokenStack {
     pub fn new() -> TokenStack {
-        let mut stack = HashMap::new();
-        let colors = [Black, White, Red, Blue, Green, Gold];
-        for color in colors.iter() {
-            stack.insert(*color, vec![]);
+        TokenStack {
+            gems: Gem::new(),
+            gold: 0,
         }
-        TokenStack(stack)
     }
 
     pub fn fill(mut self) -> TokenStack {
-        let colors = [Black, White, Red, Blue, Green, Gold];
-        for color in colors.iter() {
-            self.0.insert(*color, TokenStack::create_stack(*color));
-        }
-        TokenStack(self.0)
-        //self.clone()
+        self.gems = Gem::from([MAX_NUMBER_OF_TOKEN; 5]);
+        self.gold = MAX_NUMBER_OF_TOKEN;
+        self
     }
 
-    fn create_stack(color: Color) -> Vec<Token> {
-        let mut stack = vec![];
-
-        for _ in 0..MAX_NUMBER_OF_TOKEN {
-            stack.push(Token::new(color));
-        }
-
-        stack
-    }
     pub fn len(&self, color: Color) -> u8 {
-        self.0.get(&color).unwrap().len() as u8
+        match color {
+            Gold => self.gold,
+            _ => self.gems.get(color),
+        }
     }
     pub fn len_all(&self) -> u8 {
-        let mut sum = 0;
-        for (_, each_stack) in self.0.iter() {
-            sum += each_stack.len();
-        }
-        sum as u8
+        self.gems.total() + self.gold
+    }
+    // 金を除いた宝石トークンの個数
+    pub fn get_gems(&self) -> Gem {
+        self.gems
     }
     pub fn add(&mut self, token: Token) {
-        let color = token.get_color();
-        self._get(token.get_color()).push(token);
+        match token.get_color() {
+            Gold => self.gold += 1,
+            color => self.gems.add(color, 1),
+        }
     }
 
     pub fn remove(&mut self, color: Color) -> Option<Token> {
-        self._get(color).pop()
+        if self.len(color) == 0 {
+            return None;
+        }
+        match color {
+            Gold => self.gold -= 1,
+            _ => self.gems.set(color, self.gems.get(color) - 1),
+        }
+        Some(Token::new(color))
     }
 
     pub fn addn(&mut self, tokens: Vec<Token>) {
@@ -76,21 +81,21 @@ impl TokenStack {
     }
 
     pub fn removen(&mut self, color: Color, n: u8) -> Vec<Token> {
-        let mut result = vec![];
-        for _ in 0..n {
-            if let Some(token) = self.remove(color) {
-                result.push(token)
-            }
-        }
-
-        if result.len() != n as usize {
+        if self.len(color) < n {
             panic!("n mismatches in removen"); // TODO
         }
-        result
+        (0..n).filter_map(|_| self.remove(color)).collect()
     }
 
-    fn _get(&mut self, color: Color) -> &mut Vec<Token> {
-        self.0.get_mut(&color).unwrap()
+    // other から gems 分の宝石トークンと gold 個の金トークンを移す
+    pub fn transfer(&mut self, other: &mut TokenStack, gems: &Gem, gold: u8) {
+        if !other.gems.dominates(gems) || other.gold < gold {
+            panic!("n mismatches in transfer"); // TODO
+        }
+        other.gems = other.gems.saturating_sub(gems);
+        other.gold -= gold;
+        self.gems += *gems;
+        self.gold += gold;
     }
 }
 
@@ -99,6 +104,7 @@ mod tests {
     use super::Token;
     use super::TokenStack;
     use crate::game::color::Color::*;
+    use crate::game::gem::Gem;
 
     #[test]
     fn test_new() {
@@ -146,4 +152,16 @@ mod tests {
         assert_eq!(tokens.pop(), None);
     }
 
+    #[test]
+    fn test_transfer() {
+        let mut board = TokenStack::new().fill();
+        let mut user = TokenStack::new();
+        user.transfer(&mut board, &Gem::from([2, 0, 1, 0, 0]), 1);
+
+        assert_eq!(user.get_gems(), Gem::from([2, 0, 1, 0, 0]));
+        assert_eq!(user.len(Gold), 1);
+        assert_eq!(user.len_all(), 4);
+        assert_eq!(board.len(Black), 3);
+        assert_eq!(board.len_all(), 26);
+    }
 }
@@ -17,7 +17,7 @@ impl fmt::Display for Token {
 
 impl Token {
     pub fn new(color: Color) -> Token {
-        Token { color: color }
+        Token { color }
     }
     pub fn get_color(&self) -> Color {
         self.color
@@ -0,0 +1,604 @@
+use crate::game::board::Board;
+use crate::game::color::Color::{self, *};
+use crate::game::game_command::GameCommand::{self, *};
+use crate::game::game_event::GameEvent;
+use crate::game::gem::GEMS;
+use crate::game::locale::{message, text};
+use crate::game::token_stack::TokenStack;
+use crate::game::user::User;
+use crate::game::{Game, MAX_NUMBER_OF_TRIALS, MAX_NUMBER_OF_TURNS};
+
+use std::fmt;
+
+pub const MAX_NUMBER_OF_TOKENS: u8 = 10;
+
+// 手番の段階
+// 主な行動 → (トークンが上限を超えたら) 捨てる → (複数の貴族が訪問できるなら) 選ぶ → 手番の終わり
+#[derive(Clone, Debug, PartialEq)]
+pub enum Phase {
+    Main,
+    // 上限を超えた n 個のトークンを 1個ずつ捨てる
+    Discard(u8),
+    // 訪問する貴族タイルを id で 1つ選ぶ
+    Noble(Vec<u8>),
+    Over,
+}
+
+// Phase に対してユーザーが決めること
+#[derive(Clone, Debug, PartialEq)]
+pub enum Decision {
+    Command(GameCommand),
+    Discard(Color),
+    Noble(u8),
+}
+
+// 誰が何を決める番かを持ち、決定を受け取って局面を進める
+// 誰かが勝利点 VP_TO_END に達したら、その巡の最後のユーザーまで手番を回して終わる
+#[derive(Clone)]
+pub struct Turn {
+    seat: usize,
+    turn: u32,
+    phase: Phase,
+    final_round: bool,
+    surrendered: Vec<usize>,
+    failures: u8,
+    number_of_users: usize,
+    events: Vec<GameEvent>,
+    command: Option<GameCommand>,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let line = match self {
+            Phase::Main => text("turn.main").to_string(),
+            Phase::Discard(n) => message("turn.discard", &[n]),
+            Phase::Noble(ids) => message("turn.noble", &[&format!("{:?}", ids)]),
+            Phase::Over => text("turn.over").to_string(),
+        };
+        f.write_str(&line)
+    }
+}
+
+impl Turn {
+    pub fn new(number_of_users: usize) -> Turn {
+        Turn {
+            seat: 0,
+            turn: 1,
+            phase: Phase::Main,
+            final_round: false,
+            surrendered: vec![],
+            failures: 0,
+            number_of_users,
+            events: vec![GameEvent::TurnStarted { turn: 1, seat: 0 }],
+            command: None,
+        }
+    }
+
+    // 決める番のユーザーと決めること (終わっていれば None)
+    pub fn pending(&self) -> Option<(usize, &Phase)> {
+        match self.phase {
+            Phase::Over => None,
+            _ => Some((self.seat, &self.phase)),
+        }
+    }
+    pub fn get_seat(&self) -> usize {
+        self.seat
+    }
+    pub fn get_turn(&self) -> u32 {
+        self.turn
+    }
+    pub fn get_phase(&self) -> &Phase {
+        &self.phase
+    }
+    pub fn get_surrendered(&self) -> &[usize] {
+        &self.surrendered
+    }
+    pub fn is_final_round(&self) -> bool {
+        self.final_round
+    }
+    pub fn is_over(&self) -> bool {
+        self.phase == Phase::Over
+    }
+
+    // 探索やシミュレーション用に、user の手番を command から手番の終わりまで Turn と同じ規則で進める
+    // 捨てるトークンと訪問する貴族タイルは Agent の既定と同じ選び方で決める
+    // command が実行できなければ局面は変えずに Err を返す
+    pub fn play(command: GameCommand, user: &mut User, board: &mut Board) -> Result<(), String> {
+        let users = std::slice::from_mut(user);
+        let mut turn = Turn::new(1);
+        turn.decide(Decision::Command(command), users, board)?;
+        while let Some(decision) = turn.default_decision(users) {
+            turn.decide(decision, users, board)?;
+        }
+        Ok(())
+    }
+
+    // Discard と Noble の段階で、Agent の既定と同じ選び方をした決定 (Main と Over では None)
+    pub fn default_decision(&self, users: &[User]) -> Option<Decision> {
+        match &self.phase {
+            Phase::Discard(_) => Some(Decision::Discard(Turn::default_discard(
+                users[self.seat].peek_token_stack(),
+            ))),
+            Phase::Noble(ids) => Some(Decision::Noble(ids[0])),
+            Phase::Main | Phase::Over => None,
+        }
+    }
+
+    // 一番多く持っている宝石トークン (宝石トークンがなければ金)
+    pub fn default_discard(token_stack: &TokenStack) -> Color {
+        GEMS.iter()
+            .copied()
+            .max_by_key(|color| token_stack.len(*color))
+            .filter(|color| token_stack.len(*color) > 0)
+            .unwrap_or(Gold)
+    }
+
+    // 決める番のユーザーの決定を実行する
+    // 起きたことは take_events で受け取る
+    // 実行できなければ局面は変えずに Err を返し、MAX_NUMBER_OF_TRIALS 回続けば降参とみなす
+    pub fn decide(
+        &mut self,
+        decision: Decision,
+        users: &mut [User],
+        board: &mut Board,
+    ) -> Result<(), String> {
+        let seat = self.seat;
+        let result = match (&self.phase, decision) {
+            (Phase::Main, Decision::Command(command)) => {
+                let before = users[seat].clone();
+                let slot = match command {
+                    ReserveDevelopmentCard { x, y } | BuyDevelopmentCard { x, y } => {
+                        board.peek_card_id(x, y).map(|card| (x, y, card))
+                    }
+                    _ => None,
+                };
+                command
+                    .execute(&mut users[seat], board)
+                    .map(|_| {
+                        self.record(&command, seat, &before, slot, &users[seat], board);
+                        self.command = Some(command);
+                    })
+                    .map_err(str::to_string)
+            }
+            (Phase::Discard(_), Decision::Discard(color)) => {
+                Turn::discard(color, &mut users[seat], board)
+                    .map(|_| self.events.push(GameEvent::TokensDiscarded { seat, color }))
+            }
+            (Phase::Noble(ids), Decision::Noble(id)) if ids.contains(&id) => {
+                self.visit(id, &mut users[seat], board);
+                Ok(())
+            }
+            (Phase::Over, _) => Err(text("turn.game_over").to_string()),
+            (phase, _) => Err(message("turn.wrong_phase", &[phase])),
+        };
+
+        match result {
+            Ok(()) => {
+                self.failures = 0;
+                self.advance(users, board);
+                Ok(())
+            }
+            Err(message) => {
+                self.failures += 1;
+                if self.failures >= MAX_NUMBER_OF_TRIALS {
+                    self.surrender(users);
+                }
+                Err(message)
+            }
+        }
+    }
+
+    // decide と surrender で起きたことを起きた順に取り出す
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        self.events.drain(..).collect()
+    }
+
+    // 決める番のユーザーを降参させ、次のユーザーに手番を回す
+    pub fn surrender(&mut self, users: &[User]) {
+        if self.is_over() {
+            return;
+        }
+        self.surrendered.push(self.seat);
+        self.events.push(GameEvent::Surrendered { seat: self.seat });
+        self.end_turn(users);
+    }
+
+    // 今の段階が終わったら次の段階に進める
+    // 訪問できる貴族タイルが 1つだけなら選ばせずに訪問させる
+    fn advance(&mut self, users: &mut [User], board: &mut Board) {
+        if !matches!(self.phase, Phase::Noble(_)) {
+            let user = &mut users[self.seat];
+            let excess = user
+                .peek_token_stack()
+                .len_all()
+                .saturating_sub(MAX_NUMBER_OF_TOKENS);
+            if excess > 0 {
+                self.phase = Phase::Discard(excess);
+                return;
+            }
+
+            let jewelries = user.get_jewelries();
+            let ids: Vec<u8> = board
+                .peek_noble_tile()
+                .iter()
+                .filter(|tile| tile.can_visit(&jewelries))
+                .map(|tile| tile.get_id())
+                .collect();
+            match ids.as_slice() {
+                [] => {}
+                [id] => self.visit(*id, user, board),
+                _ => {
+                    self.phase = Phase::Noble(ids);
+                    return;
+                }
+            }
+        }
+
+        self.end_turn(users);
+    }
+
+    fn end_turn(&mut self, users: &[User]) {
+        self.failures = 0;
+        let user = &users[self.seat];
+        self.events.push(GameEvent::TurnEnded {
+            turn: self.turn,
+            seat: self.seat,
+            command: self.command.take(),
+            vp: user.get_vp(),
+            tokens: user.peek_token_stack().get_gems(),
+            gold: user.get_number_of_tokens(Gold),
+        });
+        if Game::is_over(users) {
+            self.final_round = true;
+        }
+        if self.surrendered.len() + 1 >= self.number_of_users {
+            return self.finish(users);
+        }
+
+        let next =
+            (self.seat + 1..self.number_of_users).find(|seat| !self.surrendered.contains(seat));
+        self.seat = match next {
+            Some(seat) => seat,
+            None => {
+                if self.final_round || self.turn >= MAX_NUMBER_OF_TURNS {
+                    return self.finish(users);
+                }
+                self.turn += 1;
+                (0..self.number_of_users)
+                    .find(|seat| !self.surrendered.contains(seat))
+                    .unwrap()
+            }
+        };
+        self.phase = Phase::Main;
+        self.events.push(GameEvent::TurnStarted {
+            turn: self.turn,
+            seat: self.seat,
+        });
+    }
+
+    fn finish(&mut self, users: &[User]) {
+        self.phase = Phase::Over;
+        let result = Game::result(users, &self.surrendered, self.turn);
+        self.events.push(GameEvent::GameEnded {
+            winner: result.winner,
+            vps: result.vps,
+            turns: result.turns,
+        });
+    }
+
+    // 実行したコマンドで起きたことを、実行前の user と場のカード slot (x, y, id) との差から記録する
+    fn record(
+        &mut self,
+        command: &GameCommand,
+        seat: usize,
+        before: &User,
+        slot: Option<(u8, u8, u8)>,
+        user: &User,
+        board: &Board,
+    ) {
+        let (before_tokens, after_tokens) = (before.peek_token_stack(), user.peek_token_stack());
+        let gold = after_tokens.len(Gold) > before_tokens.len(Gold);
+        match *command {
+            SelectTwoSameTokens(_) | SelectThreeTokens(..) => {
+                self.events.push(GameEvent::TokensTaken {
+                    seat,
+                    tokens: after_tokens
+                        .get_gems()
+                        .saturating_sub(&before_tokens.get_gems()),
+                })
+            }
+            ReserveStackCard(_) => self.events.push(GameEvent::CardReserved {
+                seat,
+                card: *user.get_hand_ids().last().unwrap(),
+                slot: None,
+                gold,
+            }),
+            ReserveDevelopmentCard { .. } | BuyDevelopmentCard { .. } | BuyReservedCard(_) => {
+                let card = match (command, slot) {
+                    (BuyReservedCard(order), _) => before.get_hand_ids()[*order as usize],
+                    (_, Some((_, _, card))) => card,
+                    _ => unreachable!(),
+                };
+                self.events.push(match command {
+                    ReserveDevelopmentCard { x, y } => GameEvent::CardReserved {
+                        seat,
+                        card,
+                        slot: Some((*x, *y)),
+                        gold,
+                    },
+                    _ => GameEvent::CardBought {
+                        seat,
+                        card,
+                        payment: before_tokens
+                            .get_gems()
+                            .saturating_sub(&after_tokens.get_gems()),
+                        gold: before_tokens.len(Gold) - after_tokens.len(Gold),
+                    },
+                });
+                if let Some((x, y, _)) = slot {
+                    self.events.push(GameEvent::SlotRefilled {
+                        x,
+                        y,
+                        card: board.peek_card_id(x, y),
+                    });
+                }
+            }
+        }
+    }
+
+    fn discard(color: Color, user: &mut User, board: &mut Board) -> Result<(), String> {
+        match user.remove_token(color) {
+            Some(token) => {
+                board.get_token_stack().add(token);
+                Ok(())
+            }
+            None => Err(message("turn.no_token", &[&color])),
+        }
+    }
+
+    fn visit(&mut self, id: u8, user: &mut User, board: &mut Board) {
+        let noble_tiles = board.get_noble_tile();
+        let order = noble_tiles
+            .iter()
+            .position(|tile| tile.get_id() == id)
+            .unwrap();
+        let tile = noble_tiles.remove(order);
+        user.add_vp(tile.get_point());
+        self.events.push(GameEvent::NobleVisited {
+            seat: self.seat,
+            noble_tile: id,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Decision, Phase, Turn};
+    use crate::game::board::Board;
+    use crate::game::card_catalog::CardCatalog;
+    use crate::game::color::Color::*;
+    use crate::game::game_command::GameCommand;
+    use crate::game::game_event::GameEvent;
+    use crate::game::gem::Gem;
+    use crate::game::user::User;
+    use crate::game::MAX_NUMBER_OF_TRIALS;
+
+    fn setup() -> (Turn, Vec<User>, Board) {
+        (Turn::new(2), vec![User::new(0), User::new(1)], Board::new())
+    }
+
+    // 9個持っていても 3個取れて、超えた 2個を 1個ずつ捨てる
+    #[test]
+    fn test_discard() {
+        let (mut turn, mut users, mut board) = setup();
+        for color in [Black, White, Red].iter() {
+            for _ in 0..3 {
+                users[0].add_token(board.uget_token(*color));
+            }
+        }
+
+        let command = GameCommand::SelectThreeTokens(Red, Blue, Green);
+        assert!(turn
+            .decide(Decision::Command(command), &mut users, &mut board)
+            .is_ok());
+        assert_eq!(users[0].peek_token_stack().len_all(), 12);
+        assert_eq!(turn.pending(), Some((0, &Phase::Discard(2))));
+        assert!(turn
+            .decide(Decision::Discard(Gold), &mut users, &mut board)
+            .is_err());
+        assert!(turn
+            .decide(Decision::Noble(0), &mut users, &mut board)
+            .is_err());
+        assert!(turn
+            .decide(Decision::Discard(Blue), &mut users, &mut board)
+            .is_ok());
+        assert_eq!(turn.pending(), Some((0, &Phase::Discard(1))));
+        assert!(turn
+            .decide(Decision::Discard(Green), &mut users, &mut board)
+            .is_ok());
+        assert_eq!(users[0].peek_token_stack().len_all(), 10);
+        assert_eq!(board.get_number_of_tokens(Blue), 5);
+        assert_eq!(turn.pending(), Some((1, &Phase::Main)));
+    }
+
+    // 上限の 10個を持っていても 3個取れて、3個捨てる
+    #[test]
+    fn test_discard_at_limit() {
+        let (mut turn, mut users, mut board) = setup();
+        for color in [Black, White, Red].iter() {
+            for _ in 0..3 {
+                users[0].add_token(board.uget_token(*color));
+            }
+        }
+        users[0].add_token(board.uget_token(Black));
+
+        let command = GameCommand::SelectThreeTokens(Red, Blue, Green);
+        assert!(turn
+            .decide(Decision::Command(command), &mut users, &mut board)
+            .is_ok());
+        assert_eq!(turn.pending(), Some((0, &Phase::Discard(3))));
+        for color in [Black, Red, Green].iter() {
+            assert!(turn
+                .decide(Decision::Discard(*color), &mut users, &mut board)
+                .is_ok());
+        }
+        assert_eq!(users[0].peek_token_stack().len_all(), 10);
+        assert_eq!(turn.pending(), Some((1, &Phase::Main)));
+    }
+
+    #[test]
+    fn test_play() {
+        let mut user = User::new(0);
+        let mut board = Board::new();
+        for color in [Black, White, Red].iter() {
+            for _ in 0..3 {
+                user.add_token(board.uget_token(*color));
+            }
+        }
+
+        // 上限を超えた分は一番多い宝石トークンを捨てる (同数なら後の色)
+        let command = GameCommand::SelectThreeTokens(Red, Blue, Green);
+        assert!(Turn::play(command, &mut user, &mut board).is_ok());
+        assert_eq!(user.peek_token_stack().len_all(), 10);
+        // 赤 4個から 1個、黒・白・赤が 3個で並んだら後の赤から 1個
+        assert_eq!(user.get_number_of_tokens(Red), 2);
+        assert_eq!(user.get_number_of_tokens(White), 3);
+        assert!(Turn::play(GameCommand::BuyReservedCard(0), &mut user, &mut board).is_err());
+    }
+
+    #[test]
+    fn test_noble_choice() {
+        let (mut turn, mut users, mut board) = setup();
+        let catalog = CardCatalog::global();
+        *board.get_noble_tile() =
+            vec![catalog.noble_tile(8).clone(), catalog.noble_tile(1).clone()];
+        for color in [Black, Red, Blue, Green].iter() {
+            for card in catalog
+                .cards()
+                .iter()
+                .filter(|card| card.color == *color)
+                .take(3)
+            {
+                users[0].obtain(*card);
+            }
+        }
+
+        let command = GameCommand::SelectThreeTokens(Red, Blue, Green);
+        assert!(turn
+            .decide(Decision::Command(command), &mut users, &mut board)
+            .is_ok());
+        assert_eq!(turn.pending(), Some((0, &Phase::Noble(vec![8, 1]))));
+        assert!(turn
+            .decide(Decision::Noble(2), &mut users, &mut board)
+            .is_err());
+
+        let vp = users[0].get_vp();
+        assert!(turn
+            .decide(Decision::Noble(1), &mut users, &mut board)
+            .is_ok());
+        assert_eq!(users[0].get_vp(), vp + 3);
+        assert_eq!(board.peek_noble_tile().len(), 1);
+        assert_eq!(board.peek_noble_tile()[0].get_id(), 8);
+        assert_eq!(turn.pending(), Some((1, &Phase::Main)));
+
+        // 次の手番では残った 1枚が選ばずに訪問する
+        let command = GameCommand::SelectThreeTokens(Red, Blue, Green);
+        assert!(turn
+            .decide(Decision::Command(command.clone()), &mut users, &mut board)
+            .is_ok());
+        turn.take_events();
+        assert!(turn
+            .decide(Decision::Command(command), &mut users, &mut board)
+            .is_ok());
+        assert!(board.peek_noble_tile().is_empty());
+        assert!(turn.take_events().contains(&GameEvent::NobleVisited {
+            seat: 0,
+            noble_tile: 8,
+        }));
+    }
+
+    #[test]
+    fn test_final_round() {
+        let (mut turn, mut users, mut board) = setup();
+        users[0].add_vp(15);
+
+        let command = GameCommand::SelectThreeTokens(Red, Blue, Green);
+        assert!(turn
+            .decide(Decision::Command(command.clone()), &mut users, &mut board)
+            .is_ok());
+        assert!(turn.is_final_round());
+        assert_eq!(turn.pending(), Some((1, &Phase::Main)));
+        assert!(turn
+            .decide(Decision::Command(command), &mut users, &mut board)
+            .is_ok());
+        assert_eq!(turn.pending(), None);
+        assert_eq!(turn.get_turn(), 1);
+    }
+
+    #[test]
+    fn test_surrender() {
+        let (mut turn, mut users, mut board) = setup();
+        for _ in 0..MAX_NUMBER_OF_TRIALS {
+            assert!(turn
+                .decide(Decision::Discard(Black), &mut users, &mut board)
+                .is_err());
+        }
+        assert_eq!(turn.get_surrendered(), &[0][..]);
+        assert!(turn.is_over());
+    }
+
+    #[test]
+    fn test_events() {
+        let (mut turn, mut users, mut board) = setup();
+        let card = board.peek_card_id(1, 2).unwrap();
+        let command = GameCommand::ReserveDevelopmentCard { x: 1, y: 2 };
+        assert!(turn
+            .decide(Decision::Command(command), &mut users, &mut board)
+            .is_ok());
+        let command = GameCommand::SelectThreeTokens(Red, Blue, Green);
+        assert!(turn
+            .decide(Decision::Command(command), &mut users, &mut board)
+            .is_ok());
+
+        assert_eq!(
+            turn.take_events(),
+            vec![
+                GameEvent::TurnStarted { turn: 1, seat: 0 },
+                GameEvent::CardReserved {
+                    seat: 0,
+                    card,
+                    slot: Some((1, 2)),
+                    gold: true,
+                },
+                GameEvent::SlotRefilled {
+                    x: 1,
+                    y: 2,
+                    card: board.peek_card_id(1, 2),
+                },
+                GameEvent::TurnEnded {
+                    turn: 1,
+                    seat: 0,
+                    command: Some(GameCommand::ReserveDevelopmentCard { x: 1, y: 2 }),
+                    vp: 0,
+                    tokens: Gem::new(),
+                    gold: 1,
+                },
+                GameEvent::TurnStarted { turn: 1, seat: 1 },
+                GameEvent::TokensTaken {
+                    seat: 1,
+                    tokens: Gem::from([0, 0, 1, 1, 1]),
+                },
+                GameEvent::TurnEnded {
+                    turn: 1,
+                    seat: 1,
+                    command: Some(GameCommand::SelectThreeTokens(Red, Blue, Green)),
+                    vp: 0,
+                    tokens: Gem::from([0, 0, 1, 1, 1]),
+                    gold: 0,
+                },
+                GameEvent::TurnStarted { turn: 2, seat: 0 },
+            ]
+        );
+        assert!(turn.take_events().is_empty());
+    }
+}
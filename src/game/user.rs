@@ -1,56 +1,70 @@
+use crate::game::card_catalog::CardCatalog;
 use crate::game::card_stack::Card;
 use crate::game::color::Color;
-use crate::game::game_command::GameCommand;
-use crate::game::gem::{Gem, GEMS};
+use crate::game::gem::Gem;
+use crate::game::locale::{message, text};
 use crate::game::token_stack::{Token, TokenStack};
 
-use rand::rngs::ThreadRng;
-use rand::Rng;
 use std::fmt;
 
-const MAX_NUMBER_OF_HANDS: usize = 3;
+pub const MAX_NUMBER_OF_HANDS: usize = 3;
 
+// 手札と取得したカードはカードの id で持つ
+// face_down は手札のうち山札から伏せたまま確保したカードで、他のユーザーにはレベルしか見えない
 #[derive(Clone)]
 pub struct User {
     id: u8,
-    hand: Vec<Card>,
-    acquired_card: Vec<Card>,
+    hand: Vec<u8>,
+    face_down: Vec<u8>,
+    acquired_card: Vec<u8>,
     vp: u8,
     token_stack: TokenStack,
-    rng: ThreadRng,
 }
 
 impl fmt::Display for User {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
+        let hand: Vec<String> = self.get_cards_in_hands().map(Card::to_string).collect();
+        let acquired: Vec<String> = self.get_acquired_cards().map(Card::to_string).collect();
+        writeln!(f)?;
+        writeln!(f, "{}", message("user.header", &[&self.id, &self.vp]))?;
+        writeln!(
             f,
-            "
-ID: {} 勝利点: {}
-手札: {:?}
-取得したカード:
-{:?}
-{}
-        ",
-            self.id, self.vp, self.hand, self.acquired_card, self.token_stack,
-        )
+            "{}",
+            message("user.hand", &[&format!("[{}]", hand.join(", "))])
+        )?;
+        writeln!(f, "{}", text("user.acquired"))?;
+        writeln!(f, "[{}]", acquired.join(", "))?;
+        writeln!(f, "{}", self.token_stack)
     }
 }
 impl User {
     pub fn new(id: u8) -> User {
-        let user = User {
-            id: id,
+        User {
+            id,
             vp: 0,
             hand: vec![],
+            face_down: vec![],
             acquired_card: vec![],
             token_stack: TokenStack::new(),
-            rng: rand::thread_rng(),
-        };
-
-        user
+        }
     }
-    pub fn read(&mut self) -> GameCommand {
-        let random_value = self.rng.gen::<u8>() % 45;
-        GameCommand::to_command(random_value)
+    // 観測から局面を組み立てるときに使う
+    pub(crate) fn from_parts(
+        id: u8,
+        vp: u8,
+        hand: Vec<u8>,
+        face_down: Vec<u8>,
+        acquired_card: Vec<u8>,
+        token_stack: TokenStack,
+    ) -> User {
+        User {
+            id,
+            hand,
+            face_down,
+            acquired_card,
+            vp,
+            token_stack,
+        }
     }
     pub fn get_id(&self) -> u8 {
         self.id
@@ -68,115 +82,97 @@ impl User {
         self.hand.len() as u8
     }
     pub fn add_to_hands(&mut self, card: Card) {
-        self.hand.push(card);
+        self.hand.push(card.id);
+    }
+    pub fn add_face_down_to_hands(&mut self, card: Card) {
+        self.face_down.push(card.id);
+        self.add_to_hands(card);
+    }
+    pub fn is_face_down(&self, order: u8) -> bool {
+        match self.hand.get(order as usize) {
+            Some(id) => self.face_down.contains(id),
+            None => false,
+        }
     }
     pub fn obtain(&mut self, card: Card) {
         self.vp += card.get_point();
-        self.acquired_card.push(card);
+        self.acquired_card.push(card.id);
     }
     pub fn is_over_capacity_of_hand(&self) -> bool {
         self.hand.len() >= MAX_NUMBER_OF_HANDS
     }
-    pub fn can_get_token(&self) -> bool {
-        self.token_stack.len_all() <= 10
-    }
     pub fn add_token(&mut self, token: Token) {
         self.token_stack.add(token);
     }
-    pub fn remove_token(&mut self, color: Color) {
-        self.token_stack.remove(color);
+    pub fn remove_token(&mut self, color: Color) -> Option<Token> {
+        self.token_stack.remove(color)
     }
+    // ボーナスで足りない分を宝石トークンで払い、残りを金トークンで払う
     pub fn pay(&mut self, card: &Card, board_token_stack: &mut TokenStack) {
-        let jewelries = self.get_jewelries();
-        let mut paid_tokens = vec![];
-
-        for color in GEMS.iter() {
-            let cost = card.get_cost(*color);
-            let number_of_token = self.get_number_of_tokens(*color);
-            let jewelry = jewelries.get(*color);
-            paid_tokens.append(&mut self.pay_each_tokens(cost, number_of_token, jewelry, *color));
-        }
-
-        board_token_stack.addn(paid_tokens);
+        let need = card.cost.saturating_sub(&self.get_jewelries());
+        let gems = need.min(&self.token_stack.get_gems());
+        board_token_stack.transfer(&mut self.token_stack, &gems, need.total() - gems.total());
+    }
+    pub fn get_acquired_cards(&self) -> impl Iterator<Item = &Card> {
+        let catalog = CardCatalog::global();
+        self.acquired_card.iter().map(move |id| catalog.card(*id))
     }
-    pub fn get_acquired_cards(&self) -> &Vec<Card> {
+    pub fn get_acquired_card_ids(&self) -> &[u8] {
         &self.acquired_card
     }
+    pub fn get_number_of_acquired_cards(&self) -> usize {
+        self.acquired_card.len()
+    }
+    pub fn get_cards_in_hands(&self) -> impl Iterator<Item = &Card> {
+        let catalog = CardCatalog::global();
+        self.hand.iter().map(move |id| catalog.card(*id))
+    }
+    pub fn get_hand_ids(&self) -> &[u8] {
+        &self.hand
+    }
     pub fn peek_card_in_hands(&self, order: u8) -> Option<&Card> {
-        self.hand.get(order as usize)
+        self.hand
+            .get(order as usize)
+            .map(|id| CardCatalog::global().card(*id))
     }
     pub fn uget_card_in_hands(&mut self, order: u8) -> Card {
-        let card = self.hand.get(order as usize).unwrap();
-        card.clone()
+        *self.peek_card_in_hands(order).unwrap()
     }
     pub fn remove_card_in_hands(&mut self, order: u8) {
-        self.hand.remove(order as usize);
+        let id = self.hand.remove(order as usize);
+        self.face_down.retain(|face_down| *face_down != id);
     }
     pub fn get_jewelries(&self) -> Gem {
         let mut gems = Gem::new();
-        for card in self.get_acquired_cards().iter() {
-            gems.add(card.get_color(), card.get_point());
+        // 取得したカード 1枚につき、その色の宝石が 1つ
+        for card in self.get_acquired_cards() {
+            gems.add(card.get_color(), 1);
         }
         gems
     }
 
-    pub fn get_owned_gems(&self) -> Gem {
-        let mut owned = Gem::new();
-        for card in self.acquired_card.iter() {
-            owned.add(card.get_color(), card.get_point())
-        }
-
-        for gem in GEMS.iter() {
-            owned.add(gem.clone(), self.token_stack.len(gem.clone()))
-        }
-        owned
+    pub fn peek_token_stack(&self) -> &TokenStack {
+        &self.token_stack
     }
 
-    fn pay_each_tokens(
-        &mut self,
-        cost: u8,
-        user_tokens: u8,
-        user_jewelries: u8,
-        color: Color,
-    ) -> Vec<Token> {
-        let mut paid_tokens = vec![];
-        if user_jewelries >= cost {
-            return paid_tokens;
-        }
-
-        let new_cost = cost - user_jewelries;
-
-        // 宝石トークンを優先的に支払う
-        if user_tokens == 0 {
-            paid_tokens.append(&mut self.token_stack.removen(Color::Gold, new_cost));
-        } else if user_tokens > new_cost {
-            paid_tokens.append(&mut self.token_stack.removen(color, new_cost));
-        } else {
-            paid_tokens.append(&mut self.token_stack.removen(color, user_tokens));
-            paid_tokens.append(
-                &mut self
-                    .token_stack
-                    .removen(Color::Gold, new_cost - user_tokens),
-            );
-        }
-
-        paid_tokens
+    pub fn get_owned_gems(&self) -> Gem {
+        self.get_jewelries() + self.token_stack.get_gems()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::User;
+    use crate::game::card_catalog::CardCatalog;
     use crate::game::card_stack::Card;
-    use crate::game::color::Color::{self, *};
+    use crate::game::color::Color::*;
+    use crate::game::gem::Gem;
     use crate::game::token_stack::{Token, TokenStack};
 
     fn get_user() -> User {
         let mut user = User::new(1);
-        let cards = Card::load("data/test_card.json");
-        for card in cards.into_iter() {
-            user.acquired_card.push(card);
-        }
+        // 黒のカード
+        user.obtain(*CardCatalog::global().card(0));
         user.token_stack
             .addn(vec![Token::new(Black), Token::new(Blue)]);
 
@@ -195,38 +191,38 @@ mod tests {
 
     #[test]
     fn test_get_jewelries() {
-        let mut user = get_user();
+        let user = get_user();
         let gems = user.get_jewelries();
         assert_eq!(gems.get(Black), 1)
     }
 
     #[test]
-    fn test_pay_each_token() {
-        let mut user = User::new(1);
-
-        let tokens = user.pay_each_tokens(0, 0, 0, Black);
-        assert_eq!(tokens, vec![]);
-
-        let tokens = user.pay_each_tokens(2, 0, 2, Black);
-        assert_eq!(tokens, vec![]);
-
-        user.token_stack.addn(vec![Token::new(Black)]);
-        let tokens = user.pay_each_tokens(2, 1, 1, Black);
-        assert_eq!(tokens, vec![Token::new(Black)]);
-
-        user.token_stack.addn(vec![Token::new(Gold)]);
-        let tokens = user.pay_each_tokens(2, 0, 1, White);
-        assert_eq!(tokens, vec![Token::new(Gold)]);
+    fn test_pay() {
+        let mut board_token_stack = TokenStack::new();
+        let card = Card {
+            cost: Gem::from([2, 3, 0, 0, 0]),
+            ..Default::default()
+        };
 
-        user.token_stack
-            .addn(vec![Token::new(Gold), Token::new(White)]);
-        let tokens = user.pay_each_tokens(3, 1, 1, White);
-        assert_eq!(tokens, vec![Token::new(White), Token::new(Gold)]);
+        // ボーナスで足りる色は払わない
+        let mut user = get_user();
+        user.add_token(Token::new(Black));
+        user.add_token(Token::new(White));
+        user.add_token(Token::new(White));
+        user.add_token(Token::new(Gold));
+        user.pay(&card, &mut board_token_stack);
+        assert_eq!(user.get_number_of_tokens(Black), 1);
+        assert_eq!(user.get_number_of_tokens(White), 0);
+        assert_eq!(user.get_number_of_tokens(Gold), 0);
+        assert_eq!(user.get_number_of_tokens(Blue), 1);
+        assert_eq!(board_token_stack.len(Black), 1);
+        assert_eq!(board_token_stack.len(White), 2);
+        assert_eq!(board_token_stack.len(Gold), 1);
     }
 
     #[test]
     fn test_get_owned_gems() {
-        let mut user = get_user();
+        let user = get_user();
         let gems = user.get_owned_gems();
 
         assert_eq!(gems.get(Black), 2);
@@ -235,6 +231,23 @@ mod tests {
         assert_eq!(gems.get(Blue), 1);
         assert_eq!(gems.get(Green), 0);
     }
+
+    // 勝利点によらず、取得したカード 1枚でその色の宝石が 1つ
+    #[test]
+    fn test_card_bonus() {
+        let catalog = CardCatalog::global();
+        let mut user = User::new(1);
+        for (n, point) in [0, 3].iter().enumerate() {
+            let card = catalog
+                .cards()
+                .iter()
+                .find(|card| card.color == Red && card.point == *point)
+                .unwrap();
+            user.obtain(*card);
+            assert_eq!(user.get_jewelries().get(Red), n as u8 + 1);
+            assert_eq!(user.get_owned_gems().get(Red), n as u8 + 1);
+        }
+    }
 }
 
 //     #[test]
@@ -0,0 +1,290 @@
+use crate::game::board::{Board, COORDINATE};
+use crate::game::color::Color::{self, *};
+use crate::game::encoder::MAX_NUMBER_OF_USERS;
+use crate::game::gem::GEMS;
+use crate::game::level::Level;
+use crate::game::noble_tile::NobleTile;
+use crate::game::user::{User, MAX_NUMBER_OF_HANDS};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// 乱数表を固定の種で作るので、別々に作った Zobrist でも同じ局面は同じハッシュ値になる
+const SEED: u64 = 0x5350_4c45_4e44_4f52;
+
+const TOKEN_COLORS: usize = 6;
+const MAX_TOKENS: usize = 16;
+const MAX_STACK_CARDS: usize = 41;
+const MAX_VP: usize = 64;
+const MAX_BONUS: usize = 32;
+const MAX_CARDS: usize = 64;
+const MAX_TURNS: usize = 128;
+const NUMBER_OF_PLACES: usize = COORDINATE.len() + MAX_NUMBER_OF_USERS * MAX_NUMBER_OF_HANDS;
+
+// 局面の各要素に割り当てた乱数の排他的論理和を局面のハッシュ値とする
+// 要素が変わったときは、古い値と新しい値の乱数を排他的論理和するだけで更新できる
+//
+// カードと貴族タイルは種類が多いため、id を置き場所の乱数と混ぜて使う
+// 取得したカードは色ごとの枚数(ボーナス)と合計枚数で表す
+pub struct Zobrist {
+    board_tokens: [[u64; MAX_TOKENS]; TOKEN_COLORS],
+    stacks: [[u64; MAX_STACK_CARDS]; 3],
+    places: [u64; NUMBER_OF_PLACES],
+    noble_tile: u64,
+    user_tokens: [[[u64; MAX_TOKENS]; TOKEN_COLORS]; MAX_NUMBER_OF_USERS],
+    vps: [[u64; MAX_VP]; MAX_NUMBER_OF_USERS],
+    bonuses: [[[u64; MAX_BONUS]; 5]; MAX_NUMBER_OF_USERS],
+    cards: [[u64; MAX_CARDS]; MAX_NUMBER_OF_USERS],
+    sides: [u64; MAX_NUMBER_OF_USERS],
+    surrendered: [u64; MAX_NUMBER_OF_USERS],
+    turns: [u64; MAX_TURNS],
+}
+
+impl Default for Zobrist {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Zobrist {
+    pub fn new() -> Zobrist {
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let mut zobrist = Zobrist {
+            board_tokens: [[0; MAX_TOKENS]; TOKEN_COLORS],
+            stacks: [[0; MAX_STACK_CARDS]; 3],
+            places: [0; NUMBER_OF_PLACES],
+            noble_tile: rng.gen(),
+            user_tokens: [[[0; MAX_TOKENS]; TOKEN_COLORS]; MAX_NUMBER_OF_USERS],
+            vps: [[0; MAX_VP]; MAX_NUMBER_OF_USERS],
+            bonuses: [[[0; MAX_BONUS]; 5]; MAX_NUMBER_OF_USERS],
+            cards: [[0; MAX_CARDS]; MAX_NUMBER_OF_USERS],
+            sides: [0; MAX_NUMBER_OF_USERS],
+            surrendered: [0; MAX_NUMBER_OF_USERS],
+            turns: [0; MAX_TURNS],
+        };
+
+        for keys in zobrist.board_tokens.iter_mut() {
+            rng.fill(&mut keys[..]);
+        }
+        for keys in zobrist.stacks.iter_mut() {
+            rng.fill(&mut keys[..]);
+        }
+        rng.fill(&mut zobrist.places[..]);
+        for user in 0..MAX_NUMBER_OF_USERS {
+            for keys in zobrist.user_tokens[user].iter_mut() {
+                rng.fill(&mut keys[..]);
+            }
+            rng.fill(&mut zobrist.vps[user][..]);
+            for keys in zobrist.bonuses[user].iter_mut() {
+                rng.fill(&mut keys[..]);
+            }
+            rng.fill(&mut zobrist.cards[user][..]);
+        }
+        rng.fill(&mut zobrist.sides[..]);
+        rng.fill(&mut zobrist.surrendered[..]);
+        rng.fill(&mut zobrist.turns[..]);
+
+        zobrist
+    }
+
+    // 盤面、全ユーザー、手番のユーザー(seat)からハッシュ値を計算する
+    pub fn hash(&self, users: &[User], board: &Board, seat: usize) -> u64 {
+        let mut hash = self.board(board) ^ self.side(seat);
+        for (index, user) in users.iter().enumerate() {
+            hash ^= self.user(index, user);
+        }
+        hash
+    }
+
+    // users[index] がコマンドを実行して before から after になったときのハッシュ値
+    // 変化した要素の乱数だけを入れ替える
+    pub fn update(
+        &self,
+        hash: u64,
+        index: usize,
+        before: (&User, &Board),
+        after: (&User, &Board),
+    ) -> u64 {
+        let (user, board) = before;
+        let (next_user, next_board) = after;
+        let mut hash = hash;
+
+        for (place, (x, y)) in COORDINATE.iter().enumerate() {
+            let card = board.peek_card_id(*x, *y);
+            let next_card = next_board.peek_card_id(*x, *y);
+            if card != next_card {
+                hash ^= self.card(place, card) ^ self.card(place, next_card);
+            }
+        }
+        for (level, keys) in Zobrist::levels().iter().zip(self.stacks.iter()) {
+            let len = board.get_number_of_stack_cards(*level);
+            let next_len = next_board.get_number_of_stack_cards(*level);
+            if len != next_len {
+                hash ^= Zobrist::at(keys, len as usize) ^ Zobrist::at(keys, next_len as usize);
+            }
+        }
+        for (color, keys) in Zobrist::token_colors().iter().zip(self.board_tokens.iter()) {
+            let len = board.get_number_of_tokens(*color);
+            let next_len = next_board.get_number_of_tokens(*color);
+            if len != next_len {
+                hash ^= Zobrist::at(keys, len as usize) ^ Zobrist::at(keys, next_len as usize);
+            }
+        }
+        if board.peek_noble_tile().len() != next_board.peek_noble_tile().len() {
+            hash ^= self.noble_tiles(board) ^ self.noble_tiles(next_board);
+        }
+
+        hash ^ self.user(index, user) ^ self.user(index, next_user)
+    }
+
+    pub fn side(&self, seat: usize) -> u64 {
+        self.sides[seat % MAX_NUMBER_OF_USERS]
+    }
+
+    pub fn surrendered(&self, seat: usize) -> u64 {
+        self.surrendered[seat % MAX_NUMBER_OF_USERS]
+    }
+
+    pub fn turn(&self, turn: u32) -> u64 {
+        Zobrist::at(&self.turns, turn as usize)
+    }
+
+    fn board(&self, board: &Board) -> u64 {
+        let mut hash = self.noble_tiles(board);
+        for (place, (x, y)) in COORDINATE.iter().enumerate() {
+            hash ^= self.card(place, board.peek_card_id(*x, *y));
+        }
+        for (level, keys) in Zobrist::levels().iter().zip(self.stacks.iter()) {
+            hash ^= Zobrist::at(keys, board.get_number_of_stack_cards(*level) as usize);
+        }
+        for (color, keys) in Zobrist::token_colors().iter().zip(self.board_tokens.iter()) {
+            hash ^= Zobrist::at(keys, board.get_number_of_tokens(*color) as usize);
+        }
+        hash
+    }
+
+    // ユーザーの要素は席(index)ごとに別の乱数を使う
+    fn user(&self, index: usize, user: &User) -> u64 {
+        let index = index % MAX_NUMBER_OF_USERS;
+        let mut hash = Zobrist::at(&self.vps[index], user.get_vp() as usize)
+            ^ Zobrist::at(&self.cards[index], user.get_number_of_acquired_cards());
+
+        let jewelries = user.get_jewelries();
+        for (color, keys) in GEMS.iter().zip(self.bonuses[index].iter()) {
+            hash ^= Zobrist::at(keys, jewelries.get(*color) as usize);
+        }
+        for (color, keys) in Zobrist::token_colors()
+            .iter()
+            .zip(self.user_tokens[index].iter())
+        {
+            hash ^= Zobrist::at(keys, user.get_number_of_tokens(*color) as usize);
+        }
+        for order in 0..MAX_NUMBER_OF_HANDS {
+            let place = COORDINATE.len() + index * MAX_NUMBER_OF_HANDS + order;
+            hash ^= self.card(place, user.get_hand_ids().get(order).cloned());
+        }
+        hash
+    }
+
+    // 空きは 0
+    fn card(&self, place: usize, id: Option<u8>) -> u64 {
+        match id {
+            Some(id) => Zobrist::mix(id as u64 ^ self.places[place]),
+            None => 0,
+        }
+    }
+
+    fn noble_tiles(&self, board: &Board) -> u64 {
+        board
+            .peek_noble_tile()
+            .iter()
+            .map(|tile| self.noble_tile_key(tile))
+            .fold(0, |hash, key| hash ^ key)
+    }
+
+    fn noble_tile_key(&self, tile: &NobleTile) -> u64 {
+        Zobrist::mix(tile.get_id() as u64 ^ self.noble_tile)
+    }
+
+    // splitmix64 の最終段
+    fn mix(value: u64) -> u64 {
+        let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // 表の大きさを超える値は最後の乱数を使う
+    fn at(keys: &[u64], value: usize) -> u64 {
+        keys[value.min(keys.len() - 1)]
+    }
+
+    fn levels() -> [Level; 3] {
+        [Level::One, Level::Two, Level::Three]
+    }
+
+    fn token_colors() -> [Color; TOKEN_COLORS] {
+        [Black, White, Red, Blue, Green, Gold]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Zobrist;
+    use crate::game::board::Board;
+    use crate::game::color::Color::*;
+    use crate::game::game_command::{GameCommand, NUMBER_OF_COMMANDS};
+    use crate::game::turn::Turn;
+    use crate::game::user::User;
+
+    #[test]
+    fn test_update() {
+        let zobrist = Zobrist::new();
+        let board = Board::new();
+        let users = vec![User::new(0), User::new(1)];
+        let hash = zobrist.hash(&users, &board, 0);
+
+        for input in 0..NUMBER_OF_COMMANDS {
+            let mut next_users = users.clone();
+            let mut next_board = board.clone();
+            let command = GameCommand::to_command(input);
+            if Turn::play(command, &mut next_users[0], &mut next_board).is_err() {
+                continue;
+            }
+            let updated =
+                zobrist.update(hash, 0, (&users[0], &board), (&next_users[0], &next_board));
+            assert_eq!(updated, zobrist.hash(&next_users, &next_board, 0));
+            assert_ne!(updated, hash);
+        }
+    }
+
+    #[test]
+    fn test_transposition() {
+        let zobrist = Zobrist::new();
+        let board = Board::new();
+        let users = vec![User::new(0), User::new(1)];
+
+        // 同じトークンを別の順番で取っても同じ局面になる
+        let mut first = (users.clone(), board.clone());
+        GameCommand::select_three_tokens(Black, White, Red, &mut first.0[0], &mut first.1).unwrap();
+        GameCommand::select_three_tokens(Blue, Green, Red, &mut first.0[0], &mut first.1).unwrap();
+        let mut second = (users.clone(), board.clone());
+        GameCommand::select_three_tokens(Blue, Green, Red, &mut second.0[0], &mut second.1)
+            .unwrap();
+        GameCommand::select_three_tokens(Black, White, Red, &mut second.0[0], &mut second.1)
+            .unwrap();
+
+        assert_eq!(
+            zobrist.hash(&first.0, &first.1, 1),
+            zobrist.hash(&second.0, &second.1, 1)
+        );
+        assert_ne!(
+            zobrist.hash(&first.0, &first.1, 0),
+            zobrist.hash(&first.0, &first.1, 1)
+        );
+        assert_eq!(
+            Zobrist::new().hash(&users, &board, 0),
+            zobrist.hash(&users, &board, 0)
+        );
+    }
+}
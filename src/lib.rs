@@ -0,0 +1,9 @@
+pub mod env;
+pub mod game;
+pub mod network;
+pub mod q_learning;
+pub mod solver;
+pub mod tournament;
+pub mod transposition_table;
+pub mod tui;
+pub mod tuner;
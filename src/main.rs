@@ -1,6 +1,410 @@
-mod game;
-use crate::game::Game;
+use splendor_clone::game::action_reward_table::Weights;
+use splendor_clone::game::agent::{Agent, HeuristicAgent, RandomAgent};
+use splendor_clone::game::card_catalog::{CardCatalog, DataFormat, CARD_FILE, NOBLE_TILE_FILE};
+use splendor_clone::game::locale::{message, text, Locale};
+use splendor_clone::game::Game;
+use splendor_clone::network::self_play::{SelfPlay, Trainer};
+use splendor_clone::network::{Network, NetworkAgent, Optimizer, DEFAULT_HIDDEN_SIZES};
+use splendor_clone::q_learning::{QAgent, QLearner, QModel};
+use splendor_clone::solver::EndgameAgent;
+use splendor_clone::tournament::Tournament;
+use splendor_clone::tui::Tui;
+use splendor_clone::tuner::Tuner;
 
+use std::env;
+use std::fs::File;
+use std::process;
+use std::time::Duration;
+
+const DEFAULT_NUMBER_OF_GAMES: u32 = 100;
+const DEFAULT_WEIGHTS_FILE: &str = "weights.json";
+const DEFAULT_NETWORK_FILE: &str = "network.json";
+const DEFAULT_LEARNING_RATE: f32 = 0.001;
+const DEFAULT_Q_MODEL_FILE: &str = "q_model.json";
+const REPORT_INTERVAL: u32 = 100;
+
+// どのサブコマンドにも --lang en|ja を付けられる (なければ環境変数 SPLENDOR_LANG)
 fn main() {
-    Game::new().play();
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--lang") {
+        let lang = args.drain(index..(index + 2).min(args.len())).nth(1);
+        match lang.unwrap_or_default().parse() {
+            Ok(locale) => Locale::set(locale),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+    match args.first().map(String::as_str) {
+        Some("tournament") => tournament(&args[1..]),
+        Some("tune") => tune(&args[1..]),
+        Some("train") => train(&args[1..]),
+        Some("qlearn") => qlearn(&args[1..]),
+        Some("validate") => validate(&args[1..]),
+        Some("export") => export(&args[1..]),
+        Some("tui") => tui(&args[1..]),
+        _ => {
+            if let Err(e) = Game::new().play() {
+                eprintln!("{}", e);
+            }
+        }
+    }
+}
+
+// tournament [--games N] [--weights FILE]... [--network FILE]... [--simulations N] [--q-model FILE]... [--endgame] [--json FILE] [--csv FILE] [--log FILE]
+fn tournament(args: &[String]) {
+    let mut games = DEFAULT_NUMBER_OF_GAMES;
+    let mut weights = vec![];
+    let mut networks = vec![];
+    let mut simulations = 0;
+    let mut q_models = vec![];
+    let mut endgame = false;
+    let mut json = None;
+    let mut csv = None;
+    let mut log = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => games = args.next().and_then(|n| n.parse().ok()).unwrap_or(games),
+            "--weights" => weights.extend(args.next()),
+            "--network" => networks.extend(args.next()),
+            "--simulations" => {
+                simulations = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(simulations)
+            }
+            "--q-model" => q_models.extend(args.next()),
+            "--endgame" => endgame = true,
+            "--json" => json = args.next(),
+            "--csv" => csv = args.next(),
+            "--log" => log = args.next(),
+            other => eprintln!("{}", message("main.unknown_option", &[&other])),
+        }
+    }
+
+    let mut tournament = Tournament::new(games)
+        .add("random", || Box::new(RandomAgent::new()) as Box<dyn Agent>)
+        .add("heuristic", || {
+            Box::new(HeuristicAgent::new()) as Box<dyn Agent>
+        });
+    if endgame {
+        tournament = tournament.add("heuristic+endgame", || {
+            Box::new(EndgameAgent::new(Box::new(HeuristicAgent::new()))) as Box<dyn Agent>
+        });
+    }
+    for file_name in weights {
+        match Weights::load(file_name) {
+            Ok(weights) => {
+                tournament = tournament.add(file_name, move || {
+                    Box::new(HeuristicAgent::with_weights(weights.clone())) as Box<dyn Agent>
+                })
+            }
+            Err(e) => eprintln!("{}: {}", file_name, e),
+        }
+    }
+
+    for file_name in networks {
+        match Network::load(file_name) {
+            Ok(network) => {
+                tournament = tournament.add(file_name, move || {
+                    Box::new(NetworkAgent::new(network.clone(), simulations)) as Box<dyn Agent>
+                })
+            }
+            Err(e) => eprintln!("{}: {}", file_name, e),
+        }
+    }
+    for file_name in q_models {
+        match QModel::load(file_name) {
+            Ok(model) => {
+                tournament = tournament.add(file_name, move || {
+                    Box::new(QAgent::new(model.clone())) as Box<dyn Agent>
+                })
+            }
+            Err(e) => eprintln!("{}: {}", file_name, e),
+        }
+    }
+
+    if let Some(file_name) = log {
+        match File::create(file_name) {
+            Ok(file) => tournament = tournament.log(file),
+            Err(e) => eprintln!("{}: {}", file_name, e),
+        }
+    }
+    let report = tournament.run();
+    print!("{}", report);
+
+    if let Some(file_name) = json {
+        if let Err(e) = report.write_json(file_name) {
+            eprintln!("{}: {}", file_name, e);
+        }
+    }
+    if let Some(file_name) = csv {
+        if let Err(e) = report.write_csv(file_name) {
+            eprintln!("{}: {}", file_name, e);
+        }
+    }
+}
+
+// tune [--generations N] [--games N] [--weights FILE] [--out FILE]
+fn tune(args: &[String]) {
+    let mut generations = None;
+    let mut games = None;
+    let mut weights = Weights::default();
+    let mut out = DEFAULT_WEIGHTS_FILE.to_string();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--generations" => generations = args.next().and_then(|n| n.parse().ok()),
+            "--games" => games = args.next().and_then(|n| n.parse().ok()),
+            "--weights" => {
+                if let Some(file_name) = args.next() {
+                    match Weights::load(file_name) {
+                        Ok(loaded) => weights = loaded,
+                        Err(e) => eprintln!("{}: {}", file_name, e),
+                    }
+                }
+            }
+            "--out" => out = args.next().cloned().unwrap_or(out),
+            other => eprintln!("{}", message("main.unknown_option", &[&other])),
+        }
+    }
+
+    let mut tuner = Tuner::new(weights);
+    if let Some(generations) = generations {
+        tuner = tuner.generations(generations);
+    }
+    if let Some(games) = games {
+        tuner = tuner.games(games);
+    }
+
+    let weights = tuner.run(|generation, weights, score| {
+        println!(
+            "{}",
+            message("main.generation", &[&generation, &format!("{:.3}", score)])
+        );
+        if let Err(e) = weights.save(&out) {
+            eprintln!("{}: {}", out, e);
+        }
+    });
+    println!("{:#?}", weights);
+    if let Err(e) = weights.save(&out) {
+        eprintln!("{}: {}", out, e);
+    }
+}
+
+// train [--iterations N] [--games N] [--simulations N] [--network FILE] [--out FILE]
+fn train(args: &[String]) {
+    let mut iterations = 10;
+    let mut games = None;
+    let mut simulations = None;
+    let mut network = None;
+    let mut out = DEFAULT_NETWORK_FILE.to_string();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--iterations" => {
+                iterations = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(iterations)
+            }
+            "--games" => games = args.next().and_then(|n| n.parse().ok()),
+            "--simulations" => simulations = args.next().and_then(|n| n.parse().ok()),
+            "--network" => {
+                if let Some(file_name) = args.next() {
+                    match Network::load(file_name) {
+                        Ok(loaded) => network = Some(loaded),
+                        Err(e) => eprintln!("{}: {}", file_name, e),
+                    }
+                }
+            }
+            "--out" => out = args.next().cloned().unwrap_or(out),
+            other => eprintln!("{}", message("main.unknown_option", &[&other])),
+        }
+    }
+
+    let mut network = network.unwrap_or_else(|| Network::new(&DEFAULT_HIDDEN_SIZES));
+    let mut self_play = SelfPlay::new();
+    if let Some(simulations) = simulations {
+        self_play = self_play.simulations(simulations);
+    }
+    let mut trainer = Trainer::new(self_play);
+    if let Some(games) = games {
+        trainer = trainer.games(games);
+    }
+
+    let mut optimizer = Optimizer::adam(DEFAULT_LEARNING_RATE);
+    trainer.run(
+        &mut network,
+        &mut optimizer,
+        iterations,
+        |iteration, loss, network| {
+            println!(
+                "{}",
+                message("main.iteration", &[&iteration, &format!("{:.4}", loss)])
+            );
+            if let Err(e) = network.save(&out) {
+                eprintln!("{}: {}", out, e);
+            }
+        },
+    );
+}
+
+// qlearn [--episodes N] [--opponent random|heuristic] [--model FILE] [--out FILE]
+fn qlearn(args: &[String]) {
+    let mut episodes = 1000;
+    let mut opponent: Box<dyn Agent> = Box::new(RandomAgent::new());
+    let mut model = QModel::new();
+    let mut out = DEFAULT_Q_MODEL_FILE.to_string();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--episodes" => episodes = args.next().and_then(|n| n.parse().ok()).unwrap_or(episodes),
+            "--opponent" => match args.next().map(String::as_str) {
+                Some("random") => opponent = Box::new(RandomAgent::new()),
+                Some("heuristic") => opponent = Box::new(HeuristicAgent::new()),
+                other => eprintln!(
+                    "{}",
+                    message("main.unknown_opponent", &[&other.unwrap_or("")])
+                ),
+            },
+            "--model" => {
+                if let Some(file_name) = args.next() {
+                    match QModel::load(file_name) {
+                        Ok(loaded) => model = loaded,
+                        Err(e) => eprintln!("{}: {}", file_name, e),
+                    }
+                }
+            }
+            "--out" => out = args.next().cloned().unwrap_or(out),
+            other => eprintln!("{}", message("main.unknown_option", &[&other])),
+        }
+    }
+
+    let mut wins = 0;
+    QLearner::new(opponent).run(&mut model, episodes, |episode, won, model| {
+        if won {
+            wins += 1;
+        }
+        if episode % REPORT_INTERVAL == 0 || episode == episodes {
+            let games = (episode - 1) % REPORT_INTERVAL + 1;
+            let rate = format!("{:.2}", wins as f64 / games as f64);
+            println!("{}", message("main.episode", &[&episode, &games, &rate]));
+            wins = 0;
+            if let Err(e) = model.save(&out) {
+                eprintln!("{}: {}", out, e);
+            }
+        }
+    });
+}
+
+// validate [--cards FILE] [--noble-tiles FILE]
+fn validate(args: &[String]) {
+    let mut card_file = CARD_FILE.to_string();
+    let mut noble_tile_file = NOBLE_TILE_FILE.to_string();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cards" => card_file = args.next().cloned().unwrap_or(card_file),
+            "--noble-tiles" => noble_tile_file = args.next().cloned().unwrap_or(noble_tile_file),
+            other => eprintln!("{}", message("main.unknown_option", &[&other])),
+        }
+    }
+
+    let errors = CardCatalog::validate(&card_file, &noble_tile_file);
+    if errors.is_empty() {
+        println!("{}", message("main.valid", &[&card_file, &noble_tile_file]));
+        return;
+    }
+    for error in errors.iter() {
+        println!("{}", error);
+    }
+    println!("{}", message("main.errors", &[&errors.len()]));
+    process::exit(1);
+}
+
+// export [--noble-tiles] [--format jsonl|json|toml|csv] INPUT OUTPUT
+// 形式を指定しなければ OUTPUT の拡張子で決める
+// CSV の列は id, level, color, point, cost_black, cost_white, cost_red, cost_blue, cost_green
+// (貴族タイルは id, point, black_bonus, ...) で、id は 0 からの連番
+fn export(args: &[String]) {
+    let mut noble_tiles = false;
+    let mut format = None;
+    let mut files = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--noble-tiles" => noble_tiles = true,
+            "--format" => match args.next().map(|f| f.parse::<DataFormat>()) {
+                Some(Ok(f)) => format = Some(f),
+                Some(Err(e)) => eprintln!("{}", e),
+                None => eprintln!("{}", text("main.format_required")),
+            },
+            file => files.push(file),
+        }
+    }
+    let (input, output) = match files.as_slice() {
+        [input, output] => (*input, *output),
+        _ => {
+            eprintln!("{}", text("main.files_required"));
+            process::exit(1);
+        }
+    };
+
+    let result = if noble_tiles {
+        CardCatalog::export_noble_tiles(input, output, format)
+    } else {
+        CardCatalog::export_cards(input, output, format)
+    };
+    match result {
+        Ok(len) => println!("{}", message("main.exported", &[&len, &output])),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+// tui [--human]... [--bot random|heuristic|endgame]... [--delay MS]
+// 席は指定した順 (指定しなければ人と heuristic)、人が 2人以上ならホットシート
+fn tui(args: &[String]) {
+    let mut agents: Vec<Option<Box<dyn Agent>>> = vec![];
+    let mut delay = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--human" => agents.push(None),
+            "--bot" => match args.next().map(String::as_str) {
+                Some("random") => agents.push(Some(Box::new(RandomAgent::new()))),
+                Some("heuristic") => agents.push(Some(Box::new(HeuristicAgent::new()))),
+                Some("endgame") => agents.push(Some(Box::new(EndgameAgent::new(Box::new(
+                    HeuristicAgent::new(),
+                ))))),
+                other => eprintln!(
+                    "{}",
+                    message("main.unknown_opponent", &[&other.unwrap_or("")])
+                ),
+            },
+            "--delay" => delay = args.next().and_then(|ms| ms.parse().ok()),
+            other => eprintln!("{}", message("main.unknown_option", &[&other])),
+        }
+    }
+    if agents.is_empty() {
+        agents = vec![None, Some(Box::new(HeuristicAgent::new()))];
+    }
+    if agents.len() < 2 {
+        eprintln!("{}", text("main.players_required"));
+        process::exit(1);
+    }
+
+    let mut tui = Tui::new(agents);
+    if let Some(delay) = delay {
+        tui = tui.delay(Duration::from_millis(delay));
+    }
+    if let Err(e) = tui.run() {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,294 @@
+use crate::game::encoder::NUMBER_OF_FEATURES;
+use crate::game::game_command::NUMBER_OF_COMMANDS;
+
+use ndarray::{Array1, Array2, Axis};
+use rand::rngs::StdRng;
+use serde_derive::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+
+mod layer;
+mod network_agent;
+mod optimizer;
+pub mod search;
+pub mod self_play;
+
+pub const DEFAULT_HIDDEN_SIZES: [usize; 2] = [128, 64];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Layer {
+    weight: Array2<f32>,
+    bias: Array1<f32>,
+}
+
+// 全結合層を重ねた共通部分に、方策(コマンドごとのロジット)と価値(-1.0 から 1.0)の2つの出力を持つ
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Network {
+    hidden: Vec<Layer>,
+    policy: Layer,
+    value: Layer,
+}
+
+// 学習データ 1件分
+// policy は合法手上の目標分布、value は手番のユーザーから見た最終結果
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub features: Array1<f32>,
+    pub mask: Array1<f32>,
+    pub policy: Array1<f32>,
+    pub value: f32,
+}
+
+pub enum Optimizer {
+    Sgd {
+        learning_rate: f32,
+    },
+    Adam {
+        learning_rate: f32,
+        beta1: f32,
+        beta2: f32,
+        epsilon: f32,
+        step: i32,
+        m: Option<Box<Network>>,
+        v: Option<Box<Network>>,
+    },
+}
+
+// simulations が 0 なら方策の最大値を、それ以外は探索で最も訪問したコマンドを選ぶ
+pub struct NetworkAgent {
+    network: Network,
+    simulations: u32,
+    rng: StdRng,
+}
+
+// 逆伝播のために各層の入力と活性化前の値を保持する
+struct Trace {
+    inputs: Vec<Array1<f32>>,
+    pre_activations: Vec<Array1<f32>>,
+    hidden: Array1<f32>,
+}
+
+impl Network {
+    pub fn new(hidden_sizes: &[usize]) -> Network {
+        let mut rng = rand::thread_rng();
+        let mut hidden = vec![];
+        let mut input_size = NUMBER_OF_FEATURES;
+        for size in hidden_sizes.iter() {
+            hidden.push(Layer::new(input_size, *size, &mut rng));
+            input_size = *size;
+        }
+
+        Network {
+            hidden,
+            policy: Layer::new(input_size, NUMBER_OF_COMMANDS as usize, &mut rng),
+            value: Layer::new(input_size, 1, &mut rng),
+        }
+    }
+
+    pub fn load(file_name: &str) -> io::Result<Network> {
+        let network = serde_json::from_reader(BufReader::new(File::open(file_name)?))?;
+        Ok(network)
+    }
+
+    pub fn save(&self, file_name: &str) -> io::Result<()> {
+        let json = serde_json::to_string(self)?;
+        File::create(file_name)?.write_all(json.as_bytes())
+    }
+
+    // 合法手だけで正規化した方策と価値を返す
+    pub fn predict(&self, features: &Array1<f32>, mask: &Array1<f32>) -> (Array1<f32>, f32) {
+        let trace = self.trace(features);
+        let policy = Network::softmax(&self.policy.forward(&trace.hidden), mask);
+        let value = self.value.forward(&trace.hidden)[0].tanh();
+        (policy, value)
+    }
+
+    // ミニバッチの平均損失 (方策の交差エントロピー + 価値の二乗誤差) を返し、重みを更新する
+    pub fn train(&mut self, samples: &[Sample], optimizer: &mut Optimizer) -> f32 {
+        if samples.is_empty() {
+            return 0.0;
+        }
+
+        let mut gradient = self.zeros();
+        let mut loss = 0.0;
+        for sample in samples.iter() {
+            loss += self.backward(sample, &mut gradient);
+        }
+
+        let scale = 1.0 / samples.len() as f32;
+        for layer in gradient.layers_mut() {
+            layer.scale(scale);
+        }
+        optimizer.update(self, &gradient);
+
+        loss * scale
+    }
+
+    fn backward(&self, sample: &Sample, gradient: &mut Network) -> f32 {
+        let trace = self.trace(&sample.features);
+
+        let policy = Network::softmax(&self.policy.forward(&trace.hidden), &sample.mask);
+        let value = self.value.forward(&trace.hidden)[0].tanh();
+
+        let mut loss = (value - sample.value).powi(2);
+        for (p, target) in policy.iter().zip(sample.policy.iter()) {
+            if *target > 0.0 {
+                loss -= target * p.max(1e-8).ln();
+            }
+        }
+
+        // 非合法手の目標は 0 で、softmax の出力も 0 なので勾配も 0 になる
+        let policy_delta = &policy - &sample.policy;
+        let value_delta =
+            Array1::from_elem(1, 2.0 * (value - sample.value) * (1.0 - value * value));
+
+        let mut delta = gradient
+            .policy
+            .accumulate(&self.policy, &trace.hidden, &policy_delta);
+        delta += &gradient
+            .value
+            .accumulate(&self.value, &trace.hidden, &value_delta);
+
+        for index in (0..self.hidden.len()).rev() {
+            let pre_activation = &trace.pre_activations[index];
+            delta.zip_mut_with(pre_activation, |d, z| {
+                if *z <= 0.0 {
+                    *d = 0.0
+                }
+            });
+            delta = gradient.hidden[index].accumulate(
+                &self.hidden[index],
+                &trace.inputs[index],
+                &delta,
+            );
+        }
+
+        loss
+    }
+
+    fn trace(&self, features: &Array1<f32>) -> Trace {
+        let mut inputs = vec![];
+        let mut pre_activations = vec![];
+        let mut hidden = features.clone();
+        for layer in self.hidden.iter() {
+            let z = layer.forward(&hidden);
+            inputs.push(hidden);
+            hidden = z.mapv(|v| v.max(0.0));
+            pre_activations.push(z);
+        }
+        Trace {
+            inputs,
+            pre_activations,
+            hidden,
+        }
+    }
+
+    fn softmax(logits: &Array1<f32>, mask: &Array1<f32>) -> Array1<f32> {
+        let max = logits
+            .iter()
+            .zip(mask.iter())
+            .filter(|(_, m)| **m > 0.0)
+            .map(|(l, _)| *l)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let mut exp = Array1::<f32>::zeros(logits.len());
+        for (index, e) in exp.iter_mut().enumerate() {
+            if mask[index] > 0.0 {
+                *e = (logits[index] - max).exp();
+            }
+        }
+        let sum = exp.sum_axis(Axis(0))[()];
+        if sum > 0.0 {
+            exp /= sum;
+        }
+        exp
+    }
+
+    fn zeros(&self) -> Network {
+        Network {
+            hidden: self.hidden.iter().map(Layer::zeros_like).collect(),
+            policy: self.policy.zeros_like(),
+            value: self.value.zeros_like(),
+        }
+    }
+
+    fn layers(&self) -> impl Iterator<Item = &Layer> {
+        self.hidden
+            .iter()
+            .chain(Some(&self.policy))
+            .chain(Some(&self.value))
+    }
+
+    fn layers_mut(&mut self) -> Vec<&mut Layer> {
+        let mut layers: Vec<&mut Layer> = self.hidden.iter_mut().collect();
+        layers.push(&mut self.policy);
+        layers.push(&mut self.value);
+        layers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Network, Optimizer, Sample};
+    use crate::game::board::Board;
+    use crate::game::encoder::Encoder;
+    use crate::game::user::User;
+    use ndarray::Array1;
+    use std::env;
+    use std::process;
+
+    fn sample() -> Sample {
+        let board = Board::new();
+        let users = vec![User::new(0), User::new(1)];
+        let mask = Encoder::legal_action_mask(&users[0], &board);
+        let mut policy = Array1::<f32>::zeros(mask.len());
+        policy[3] = 1.0;
+        Sample {
+            features: Encoder::encode(&users[0], &users, &board),
+            mask,
+            policy,
+            value: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_predict() {
+        let network = Network::new(&[16]);
+        let sample = sample();
+        let (policy, value) = network.predict(&sample.features, &sample.mask);
+
+        assert!((policy.sum() - 1.0).abs() < 1e-4);
+        for (p, m) in policy.iter().zip(sample.mask.iter()) {
+            if *m == 0.0 {
+                assert_eq!(*p, 0.0);
+            }
+        }
+        assert!((-1.0..=1.0).contains(&value));
+    }
+
+    #[test]
+    fn test_train() {
+        let samples = vec![sample()];
+        for mut optimizer in [Optimizer::sgd(0.05), Optimizer::adam(0.01)] {
+            let mut network = Network::new(&[16]);
+            let first = network.train(&samples, &mut optimizer);
+            let mut last = first;
+            for _ in 0..30 {
+                last = network.train(&samples, &mut optimizer);
+            }
+            assert!(last < first);
+        }
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let network = Network::new(&[4]);
+        let file_name = env::temp_dir().join(format!(
+            "splendor_clone_{}_test_network.json",
+            process::id()
+        ));
+        let file_name = file_name.to_str().unwrap();
+
+        network.save(file_name).unwrap();
+        assert_eq!(Network::load(file_name).unwrap(), network);
+    }
+}
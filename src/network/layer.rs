@@ -0,0 +1,57 @@
+use super::Layer;
+
+use ndarray::{Array1, Array2, Axis};
+use rand::Rng;
+
+impl Layer {
+    // Xavier の一様分布で初期化する
+    pub fn new<R: Rng + ?Sized>(input_size: usize, output_size: usize, rng: &mut R) -> Layer {
+        let limit = (6.0 / (input_size + output_size) as f32).sqrt();
+        Layer {
+            weight: Array2::from_shape_fn((output_size, input_size), |_| {
+                rng.gen_range(-limit, limit)
+            }),
+            bias: Array1::zeros(output_size),
+        }
+    }
+
+    pub fn zeros_like(&self) -> Layer {
+        Layer {
+            weight: Array2::zeros(self.weight.dim()),
+            bias: Array1::zeros(self.bias.len()),
+        }
+    }
+
+    pub fn forward(&self, input: &Array1<f32>) -> Array1<f32> {
+        self.weight.dot(input) + &self.bias
+    }
+
+    // self を勾配として layer の勾配を加算し、入力側に伝える誤差を返す
+    pub fn accumulate(
+        &mut self,
+        layer: &Layer,
+        input: &Array1<f32>,
+        delta: &Array1<f32>,
+    ) -> Array1<f32> {
+        let outer = delta
+            .view()
+            .insert_axis(Axis(1))
+            .dot(&input.view().insert_axis(Axis(0)));
+        self.weight += &outer;
+        self.bias += delta;
+        layer.weight.t().dot(delta)
+    }
+
+    pub fn scale(&mut self, scale: f32) {
+        self.weight *= scale;
+        self.bias *= scale;
+    }
+
+    pub fn update<F>(&mut self, gradient: &Layer, mut f: F)
+    where
+        F: FnMut(&mut f32, f32),
+    {
+        self.weight.zip_mut_with(&gradient.weight, |w, g| f(w, *g));
+        self.bias.zip_mut_with(&gradient.bias, |b, g| f(b, *g));
+    }
+}
@@ -0,0 +1,76 @@
+use super::search::Search;
+use super::{Network, NetworkAgent};
+use crate::game::agent::Agent;
+use crate::game::encoder::Encoder;
+use crate::game::game_command::GameCommand;
+use crate::game::observation::Observation;
+
+use ndarray::Array1;
+use rand::rngs::StdRng;
+use rand::{FromEntropy, SeedableRng};
+
+impl NetworkAgent {
+    pub fn new(network: Network, simulations: u32) -> NetworkAgent {
+        NetworkAgent {
+            network,
+            simulations,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    fn argmax(values: &Array1<f32>) -> u8 {
+        let mut best = 0;
+        for (index, value) in values.iter().enumerate() {
+            if *value > values[best] {
+                best = index;
+            }
+        }
+        best as u8
+    }
+}
+
+impl Agent for NetworkAgent {
+    fn name(&self) -> &str {
+        "network"
+    }
+    fn read(&mut self, observation: &Observation) -> GameCommand {
+        let seat = observation.get_seat();
+        // 見えていないカードは探索のたびに並べ替えて割り当て、特定の山札の順番に頼らない
+        let (users, board) = observation.sample(&mut self.rng);
+        let values = if self.simulations == 0 {
+            let mask = Encoder::legal_action_mask(&users[seat], &board);
+            let features = Encoder::encode_observation(observation);
+            self.network.predict(&features, &mask).0
+        } else {
+            Search::new(&self.network, self.simulations).run(
+                &users,
+                &board,
+                seat,
+                observation.get_turn(),
+            )
+        };
+        GameCommand::to_command(NetworkAgent::argmax(&values))
+    }
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::agent::{Agent, RandomAgent};
+    use crate::game::Game;
+    use crate::network::{Network, NetworkAgent};
+
+    #[test]
+    fn test_play() {
+        for simulations in [0, 4].iter() {
+            let agents: Vec<Box<dyn Agent>> = vec![
+                Box::new(NetworkAgent::new(Network::new(&[8]), *simulations)),
+                Box::new(RandomAgent::new()),
+            ];
+            let result = Game::with_agents(agents).quiet().play().unwrap();
+            assert_eq!(result.vps.len(), 2);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use super::{Network, Optimizer};
+
+impl Optimizer {
+    pub fn sgd(learning_rate: f32) -> Optimizer {
+        Optimizer::Sgd { learning_rate }
+    }
+
+    pub fn adam(learning_rate: f32) -> Optimizer {
+        Optimizer::Adam {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+            step: 0,
+            m: None,
+            v: None,
+        }
+    }
+
+    pub fn update(&mut self, network: &mut Network, gradient: &Network) {
+        match self {
+            Optimizer::Sgd { learning_rate } => {
+                let learning_rate = *learning_rate;
+                for (layer, g) in network.layers_mut().into_iter().zip(gradient.layers()) {
+                    layer.update(g, |w, g| *w -= learning_rate * g);
+                }
+            }
+            Optimizer::Adam {
+                learning_rate,
+                beta1,
+                beta2,
+                epsilon,
+                step,
+                m,
+                v,
+            } => {
+                let (learning_rate, beta1, beta2, epsilon) =
+                    (*learning_rate, *beta1, *beta2, *epsilon);
+                *step += 1;
+                let correction1 = 1.0 - beta1.powi(*step);
+                let correction2 = 1.0 - beta2.powi(*step);
+
+                let m = m.get_or_insert_with(|| Box::new(network.zeros()));
+                let v = v.get_or_insert_with(|| Box::new(network.zeros()));
+                for (m, g) in m.layers_mut().into_iter().zip(gradient.layers()) {
+                    m.update(g, |m, g| *m = beta1 * *m + (1.0 - beta1) * g);
+                }
+                for (v, g) in v.layers_mut().into_iter().zip(gradient.layers()) {
+                    v.update(g, |v, g| *v = beta2 * *v + (1.0 - beta2) * g * g);
+                }
+
+                let layers = network.layers_mut();
+                for (layer, (m, v)) in layers.into_iter().zip(m.layers().zip(v.layers())) {
+                    let mut delta = m.clone();
+                    delta.update(v, |m, v| {
+                        *m = learning_rate * (*m / correction1)
+                            / ((v / correction2).sqrt() + epsilon)
+                    });
+                    layer.update(&delta, |w, d| *w -= d);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use super::Network;
+use crate::game::board::Board;
+use crate::game::encoder::Encoder;
+use crate::game::game_command::{GameCommand, NUMBER_OF_COMMANDS};
+use crate::game::turn::Turn;
+use crate::game::user::User;
+use crate::game::{Game, MAX_NUMBER_OF_TURNS};
+
+use ndarray::Array1;
+
+pub const DEFAULT_C_PUCT: f32 = 1.5;
+
+// ネットワークの方策を事前確率、価値を評価値とするモンテカルロ木探索 (PUCT)
+// 相手の手番も同じネットワークで読み、価値は手番が変わるたびに符号を反転する
+pub struct Search<'a> {
+    network: &'a Network,
+    simulations: u32,
+    c_puct: f32,
+}
+
+#[derive(Clone)]
+struct State {
+    users: Vec<User>,
+    board: Board,
+    seat: usize,
+    turn: u32,
+    surrendered: Vec<usize>,
+    is_over: bool,
+}
+
+// value_sum は親の手番のユーザーから見た価値の合計
+struct Node {
+    seat: usize,
+    visits: u32,
+    value_sum: f32,
+    prior: f32,
+    expanded: bool,
+    children: Vec<(u8, Node)>,
+}
+
+impl<'a> Search<'a> {
+    pub fn new(network: &'a Network, simulations: u32) -> Search<'a> {
+        Search {
+            network,
+            simulations,
+            c_puct: DEFAULT_C_PUCT,
+        }
+    }
+
+    // turn 手番目の seat の手番から探索し、コマンドごとの訪問回数の割合を返す
+    // turn は MAX_NUMBER_OF_TURNS による打ち切りの判定に使う
+    pub fn run(&self, users: &[User], board: &Board, seat: usize, turn: u32) -> Array1<f32> {
+        let state = State {
+            users: users.to_vec(),
+            board: board.clone(),
+            seat,
+            turn,
+            surrendered: vec![],
+            is_over: false,
+        };
+        let mut root = Node::new(seat, 1.0);
+        for _ in 0..self.simulations.max(1) {
+            self.simulate(&mut root, state.clone());
+        }
+
+        let mut visits = Array1::<f32>::zeros(NUMBER_OF_COMMANDS as usize);
+        for (action, child) in root.children.iter() {
+            visits[*action as usize] = child.visits as f32;
+        }
+        let sum = visits.sum();
+        if sum > 0.0 {
+            visits /= sum;
+        }
+        visits
+    }
+
+    // node の手番のユーザーから見た価値を返す
+    fn simulate(&self, node: &mut Node, mut state: State) -> f32 {
+        if state.is_over {
+            let result = Game::result(&state.users, &state.surrendered, state.turn);
+            return match result.winner {
+                Some(winner) if winner as usize == node.seat => 1.0,
+                Some(_) => -1.0,
+                None => 0.0,
+            };
+        }
+
+        if !node.expanded {
+            return self.expand(node, &state);
+        }
+        if node.children.is_empty() {
+            return -1.0;
+        }
+
+        let sqrt_visits = (node.visits.max(1) as f32).sqrt();
+        let c_puct = self.c_puct;
+        let (action, child) = node
+            .children
+            .iter_mut()
+            .max_by(|(_, a), (_, b)| {
+                a.score(c_puct, sqrt_visits)
+                    .total_cmp(&b.score(c_puct, sqrt_visits))
+            })
+            .unwrap();
+
+        let command = GameCommand::to_command(*action);
+        let seat = state.seat;
+        // 合法手だけを展開しているので失敗しない
+        let _ = Turn::play(command, &mut state.users[seat], &mut state.board);
+        state.next();
+        child.seat = state.seat;
+
+        let value = self.simulate(child, state);
+        let value = if child.seat == node.seat {
+            value
+        } else {
+            -value
+        };
+        child.visits += 1;
+        child.value_sum += value;
+        node.visits += 1;
+        value
+    }
+
+    fn expand(&self, node: &mut Node, state: &State) -> f32 {
+        node.expanded = true;
+        let user = &state.users[state.seat];
+        let mask = Encoder::legal_action_mask(user, &state.board);
+        if mask.iter().all(|m| *m == 0.0) {
+            return -1.0;
+        }
+
+        let features = Encoder::encode(user, &state.users, &state.board);
+        let (policy, value) = self.network.predict(&features, &mask);
+        for (action, m) in mask.iter().enumerate() {
+            if *m > 0.0 {
+                node.children
+                    .push((action as u8, Node::new(state.seat, policy[action])));
+            }
+        }
+        value
+    }
+}
+
+impl Node {
+    fn new(seat: usize, prior: f32) -> Node {
+        Node {
+            seat,
+            visits: 0,
+            value_sum: 0.0,
+            prior,
+            expanded: false,
+            children: vec![],
+        }
+    }
+
+    fn score(&self, c_puct: f32, sqrt_parent_visits: f32) -> f32 {
+        let q = if self.visits > 0 {
+            self.value_sum / self.visits as f32
+        } else {
+            0.0
+        };
+        q + c_puct * self.prior * sqrt_parent_visits / (1.0 + self.visits as f32)
+    }
+}
+
+impl State {
+    fn next(&mut self) {
+        self.seat = (self.seat + 1) % self.users.len();
+        if self.seat == 0 {
+            if Game::is_over(&self.users) || self.turn >= MAX_NUMBER_OF_TURNS {
+                self.is_over = true;
+            }
+            self.turn += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Search, State};
+    use crate::game::board::Board;
+    use crate::game::encoder::Encoder;
+    use crate::game::user::User;
+    use crate::game::MAX_NUMBER_OF_TURNS;
+    use crate::network::Network;
+
+    #[test]
+    fn test_run() {
+        let network = Network::new(&[8]);
+        let board = Board::new();
+        let users = vec![User::new(0), User::new(1)];
+        let visits = Search::new(&network, 20).run(&users, &board, 0, 1);
+        let mask = Encoder::legal_action_mask(&users[0], &board);
+
+        assert!((visits.sum() - 1.0).abs() < 1e-4);
+        for (v, m) in visits.iter().zip(mask.iter()) {
+            if *m == 0.0 {
+                assert_eq!(*v, 0.0);
+            }
+        }
+    }
+
+    // 探索を始めた手番目から数えて、手番数の上限で打ち切る
+    #[test]
+    fn test_turn_limit() {
+        let mut state = State {
+            users: vec![User::new(0), User::new(1)],
+            board: Board::new(),
+            seat: 0,
+            turn: MAX_NUMBER_OF_TURNS,
+            surrendered: vec![],
+            is_over: false,
+        };
+        state.next();
+        assert!(!state.is_over);
+        state.next();
+        assert!(state.is_over);
+    }
+}
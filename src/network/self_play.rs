@@ -0,0 +1,222 @@
+use super::search::Search;
+use super::{Network, Optimizer, Sample};
+use crate::game::board::Board;
+use crate::game::encoder::Encoder;
+use crate::game::game_command::GameCommand;
+use crate::game::observation::Observation;
+use crate::game::turn::{Decision, Phase, Turn};
+use crate::game::user::User;
+use crate::game::Game;
+
+use ndarray::Array1;
+use rand::rngs::ThreadRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+const DEFAULT_NUMBER_OF_USERS: usize = 2;
+const DEFAULT_SIMULATIONS: u32 = 32;
+// 序盤はこの手数まで訪問回数に比例した確率でコマンドを選び、以降は最多訪問のコマンドを選ぶ
+const DEFAULT_EXPLORATION_MOVES: u32 = 10;
+
+// 同じネットワーク同士で対局させ、探索の訪問分布と最終結果を学習データにする
+pub struct SelfPlay {
+    number_of_users: usize,
+    simulations: u32,
+    exploration_moves: u32,
+    rng: ThreadRng,
+}
+
+pub struct Trainer {
+    self_play: SelfPlay,
+    games: u32,
+    epochs: u32,
+    batch_size: usize,
+}
+
+impl Default for SelfPlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SelfPlay {
+    pub fn new() -> SelfPlay {
+        SelfPlay {
+            number_of_users: DEFAULT_NUMBER_OF_USERS,
+            simulations: DEFAULT_SIMULATIONS,
+            exploration_moves: DEFAULT_EXPLORATION_MOVES,
+            rng: rand::thread_rng(),
+        }
+    }
+
+    pub fn simulations(mut self, simulations: u32) -> SelfPlay {
+        self.simulations = simulations;
+        self
+    }
+
+    pub fn generate(&mut self, network: &Network, games: u32) -> Vec<Sample> {
+        let mut samples = vec![];
+        for _ in 0..games {
+            samples.append(&mut self.play(network));
+        }
+        samples
+    }
+
+    fn play(&mut self, network: &Network) -> Vec<Sample> {
+        let mut board = Board::new();
+        let mut users: Vec<User> = (0..self.number_of_users as u8).map(User::new).collect();
+        let mut seats = vec![];
+        let mut samples = vec![];
+        let mut turn = Turn::new(users.len());
+        let mut moves = 0;
+
+        while let Some((seat, phase)) = turn.pending() {
+            // トークンを捨てる、貴族タイルを選ぶ段階は既定の選び方で決める
+            if *phase != Phase::Main {
+                let decision = turn.default_decision(&users).unwrap();
+                let _ = turn.decide(decision, &mut users, &mut board);
+                continue;
+            }
+
+            let mask = Encoder::legal_action_mask(&users[seat], &board);
+            if mask.iter().all(|m| *m == 0.0) {
+                turn.surrender(&users);
+                continue;
+            }
+
+            // NetworkAgent と同じく、seat から見える情報だけで特徴量を作って探索する
+            let observation = Observation::new(&users, &board, seat).turn(turn.get_turn());
+            let features = Encoder::encode_observation(&observation);
+            let (guessed_users, guessed_board) = observation.sample(&mut self.rng);
+            let visits = Search::new(network, self.simulations).run(
+                &guessed_users,
+                &guessed_board,
+                seat,
+                observation.get_turn(),
+            );
+            let action = self.select(&visits, moves);
+            let command = GameCommand::to_command(action);
+            let _ = turn.decide(Decision::Command(command), &mut users, &mut board);
+            seats.push(seat);
+            samples.push(Sample {
+                features,
+                mask,
+                policy: visits,
+                value: 0.0,
+            });
+            moves += 1;
+        }
+
+        let result = Game::result(&users, turn.get_surrendered(), turn.get_turn());
+        for (sample, seat) in samples.iter_mut().zip(seats) {
+            sample.value = match result.winner {
+                Some(winner) if winner as usize == seat => 1.0,
+                Some(_) => -1.0,
+                None => 0.0,
+            };
+        }
+        samples
+    }
+
+    fn select(&mut self, visits: &Array1<f32>, moves: u32) -> u8 {
+        let mut action = 0;
+        if moves < self.exploration_moves {
+            let mut threshold = self.rng.gen::<f32>();
+            for (index, v) in visits.iter().enumerate() {
+                if *v > 0.0 {
+                    action = index;
+                    threshold -= v;
+                    if threshold <= 0.0 {
+                        break;
+                    }
+                }
+            }
+        } else {
+            for (index, v) in visits.iter().enumerate() {
+                if *v > visits[action] {
+                    action = index;
+                }
+            }
+        }
+        action as u8
+    }
+}
+
+impl Trainer {
+    pub fn new(self_play: SelfPlay) -> Trainer {
+        Trainer {
+            self_play,
+            games: 10,
+            epochs: 4,
+            batch_size: 32,
+        }
+    }
+
+    pub fn games(mut self, games: u32) -> Trainer {
+        self.games = games;
+        self
+    }
+
+    pub fn epochs(mut self, epochs: u32) -> Trainer {
+        self.epochs = epochs;
+        self
+    }
+
+    // 自己対局と学習を iterations 回繰り返し、反復ごとに (反復, 平均損失) を on_iteration に渡す
+    pub fn run<F>(
+        &mut self,
+        network: &mut Network,
+        optimizer: &mut Optimizer,
+        iterations: u32,
+        mut on_iteration: F,
+    ) where
+        F: FnMut(u32, f32, &Network),
+    {
+        for iteration in 1..=iterations {
+            let mut samples = self.self_play.generate(network, self.games);
+            let mut loss = 0.0;
+            let mut batches = 0;
+            for _ in 0..self.epochs {
+                samples.shuffle(&mut self.self_play.rng);
+                for batch in samples.chunks(self.batch_size) {
+                    loss += network.train(batch, optimizer);
+                    batches += 1;
+                }
+            }
+            on_iteration(iteration, loss / batches.max(1) as f32, network);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SelfPlay, Trainer};
+    use crate::network::{Network, Optimizer};
+
+    #[test]
+    fn test_generate() {
+        let network = Network::new(&[8]);
+        let samples = SelfPlay::new().simulations(2).generate(&network, 1);
+
+        assert!(!samples.is_empty());
+        for sample in samples.iter() {
+            assert!((sample.policy.sum() - 1.0).abs() < 1e-4);
+            assert!([-1.0, 0.0, 1.0].contains(&sample.value));
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let mut network = Network::new(&[8]);
+        let mut optimizer = Optimizer::adam(0.001);
+        let mut iterations = 0;
+        Trainer::new(SelfPlay::new().simulations(1))
+            .games(1)
+            .epochs(1)
+            .run(&mut network, &mut optimizer, 1, |_, loss, _| {
+                assert!(loss.is_finite());
+                iterations += 1;
+            });
+        assert_eq!(iterations, 1);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::game::agent::Agent;
+use crate::game::locale::message;
+
+use rand::rngs::StdRng;
+use serde_derive::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+
+mod features;
+mod q_agent;
+mod q_learner;
+
+pub use self::features::NUMBER_OF_FEATURES;
+
+// コマンドを実行した直後の盤面(事後状態)から計算する手作りの特徴量
+//
+// 並び順:
+//   ボーナス(黒, 白, 赤, 青, 緑), トークン(黒, 白, 赤, 青, 緑, 金),
+//   購入できる場のカード, あと2個以内で購入できる場のカード, 貴族タイルの達成度,
+//   勝利点, 相手の最大勝利点, 手札の枚数, 定数項
+pub struct Features;
+
+// 事後状態の特徴量に対する線形な行動価値 Q(s, a) = w・φ(s, a)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QModel {
+    weights: Vec<f32>,
+}
+
+// ε-greedy で行動価値が最大のコマンドを選ぶ
+// 対局に使うときは ε を 0 にする
+pub struct QAgent {
+    model: QModel,
+    epsilon: f32,
+    rng: StdRng,
+}
+
+// 固定の相手(ランダムやヒューリスティック)と対局しながら Q 学習で重みを更新する
+pub struct QLearner {
+    opponent: Box<dyn Agent>,
+    learning_rate: f32,
+    discount: f32,
+    epsilon: f32,
+}
+
+impl Default for QModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QModel {
+    pub fn new() -> QModel {
+        QModel {
+            weights: vec![0.0; NUMBER_OF_FEATURES],
+        }
+    }
+
+    pub fn load(file_name: &str) -> io::Result<QModel> {
+        let model: QModel = serde_json::from_reader(BufReader::new(File::open(file_name)?))?;
+        if model.weights.len() != NUMBER_OF_FEATURES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                message(
+                    "q_learning.weights",
+                    &[&NUMBER_OF_FEATURES, &model.weights.len()],
+                ),
+            ));
+        }
+        Ok(model)
+    }
+
+    pub fn save(&self, file_name: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        File::create(file_name)?.write_all(json.as_bytes())
+    }
+
+    pub fn get_weights(&self) -> &[f32] {
+        &self.weights
+    }
+
+    pub fn value(&self, features: &[f32]) -> f32 {
+        self.weights
+            .iter()
+            .zip(features.iter())
+            .map(|(w, f)| w * f)
+            .sum()
+    }
+
+    // TD 誤差 (target - Q) の方向に重みを動かし、更新前の TD 誤差を返す
+    pub fn update(&mut self, features: &[f32], target: f32, learning_rate: f32) -> f32 {
+        let error = target - self.value(features);
+        for (w, f) in self.weights.iter_mut().zip(features.iter()) {
+            *w += learning_rate * error * f;
+        }
+        error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QModel, NUMBER_OF_FEATURES};
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn test_update() {
+        let mut model = QModel::new();
+        let features = [1.0; NUMBER_OF_FEATURES];
+
+        let first = model.update(&features, 1.0, 0.01);
+        let second = model.update(&features, 1.0, 0.01);
+        assert!(second.abs() < first.abs());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut model = QModel::new();
+        model.update(&[0.5; NUMBER_OF_FEATURES], 1.0, 0.1);
+        let file_name = env::temp_dir().join(format!(
+            "splendor_clone_{}_test_q_model.json",
+            process::id()
+        ));
+        let file_name = file_name.to_str().unwrap();
+
+        model.save(file_name).unwrap();
+        assert_eq!(QModel::load(file_name).unwrap(), model);
+    }
+}
@@ -0,0 +1,114 @@
+use super::Features;
+use crate::game::board::{Board, COORDINATE};
+use crate::game::color::Color::*;
+use crate::game::game_command::GameCommand;
+use crate::game::gem::GEMS;
+use crate::game::turn::Turn;
+use crate::game::user::{User, MAX_NUMBER_OF_HANDS};
+use crate::game::VP_TO_END;
+
+pub const NUMBER_OF_FEATURES: usize = 5 + 6 + 7;
+
+const MAX_BONUS: f32 = 7.0;
+const MAX_TOKENS: f32 = 5.0;
+// この個数以内の不足なら「手が届く」カードとみなす
+const REACHABLE_SHORTFALL: u8 = 2;
+
+impl Features {
+    // コマンドを試行した事後状態の特徴量を返す
+    // 実行できないコマンドなら None
+    pub fn after(
+        command: GameCommand,
+        user: &User,
+        users: &[User],
+        board: &Board,
+    ) -> Option<Vec<f32>> {
+        let mut next_user = user.clone();
+        let mut next_board = board.clone();
+        Turn::play(command, &mut next_user, &mut next_board).ok()?;
+        Some(Features::extract(&next_user, users, &next_board))
+    }
+
+    pub fn extract(user: &User, users: &[User], board: &Board) -> Vec<f32> {
+        let mut features = Vec::with_capacity(NUMBER_OF_FEATURES);
+
+        let jewelries = user.get_jewelries();
+        for color in GEMS.iter() {
+            features.push(jewelries.get(*color) as f32 / MAX_BONUS);
+        }
+        for color in [Black, White, Red, Blue, Green, Gold].iter() {
+            features.push(user.get_number_of_tokens(*color) as f32 / MAX_TOKENS);
+        }
+
+        let mut available = 0;
+        let mut reachable = 0;
+        for (x, y) in COORDINATE.iter() {
+            if let Some(card) = board.peek_card(*x, *y) {
+                let affordability = card.affordability(user);
+                if affordability.is_affordable() {
+                    available += 1;
+                } else if affordability.gold_missing() <= REACHABLE_SHORTFALL {
+                    reachable += 1;
+                }
+            }
+        }
+        features.push(available as f32 / COORDINATE.len() as f32);
+        features.push(reachable as f32 / COORDINATE.len() as f32);
+
+        // 最も達成に近い貴族タイルの達成率
+        let mut progress: f32 = 0.0;
+        for tile in board.peek_noble_tile().iter() {
+            let required = tile.get_required().total();
+            let remaining = jewelries.shortfall(tile.get_required());
+            if required > 0 {
+                progress = progress.max(1.0 - remaining as f32 / required as f32);
+            }
+        }
+        features.push(progress);
+
+        features.push(user.get_vp() as f32 / VP_TO_END as f32);
+        let opponent_vp = users
+            .iter()
+            .filter(|opponent| opponent.get_id() != user.get_id())
+            .map(|opponent| opponent.get_vp())
+            .max()
+            .unwrap_or(0);
+        features.push(opponent_vp as f32 / VP_TO_END as f32);
+        features.push(user.get_number_of_hands() as f32 / MAX_NUMBER_OF_HANDS as f32);
+        features.push(1.0);
+
+        features
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Features, NUMBER_OF_FEATURES};
+    use crate::game::board::Board;
+    use crate::game::color::Color::*;
+    use crate::game::game_command::GameCommand;
+    use crate::game::user::User;
+
+    #[test]
+    fn test_extract() {
+        let board = Board::new();
+        let users = vec![User::new(0), User::new(1)];
+        let features = Features::extract(&users[0], &users, &board);
+
+        assert_eq!(features.len(), NUMBER_OF_FEATURES);
+        assert!(features.iter().all(|v| (0.0..=1.0).contains(v)));
+    }
+
+    #[test]
+    fn test_after() {
+        let board = Board::new();
+        let users = vec![User::new(0), User::new(1)];
+
+        let command = GameCommand::SelectTwoSameTokens(Red);
+        let features = Features::after(command, &users[0], &users, &board).unwrap();
+        assert_eq!(features[7], 0.4);
+
+        let command = GameCommand::BuyReservedCard(0);
+        assert!(Features::after(command, &users[0], &users, &board).is_none());
+    }
+}
@@ -0,0 +1,107 @@
+use super::{Features, QAgent, QModel};
+use crate::game::agent::Agent;
+use crate::game::board::Board;
+use crate::game::game_command::{GameCommand, NUMBER_OF_COMMANDS};
+use crate::game::observation::Observation;
+use crate::game::user::User;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{FromEntropy, Rng, SeedableRng};
+
+impl QAgent {
+    pub fn new(model: QModel) -> QAgent {
+        QAgent {
+            model,
+            epsilon: 0.0,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    pub fn epsilon(mut self, epsilon: f32) -> QAgent {
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn get_model(&self) -> &QModel {
+        &self.model
+    }
+
+    pub fn get_model_mut(&mut self) -> &mut QModel {
+        &mut self.model
+    }
+
+    // 実行できるコマンドと事後状態の特徴量の組を ε-greedy で選ぶ
+    // 実行できるコマンドがなければ None
+    pub fn select(
+        &mut self,
+        user: &User,
+        users: &[User],
+        board: &Board,
+    ) -> Option<(GameCommand, Vec<f32>)> {
+        let candidates: Vec<(GameCommand, Vec<f32>)> = (0..NUMBER_OF_COMMANDS)
+            .map(GameCommand::to_command)
+            .filter_map(|command| {
+                Features::after(command.clone(), user, users, board)
+                    .map(|features| (command, features))
+            })
+            .collect();
+
+        if self.rng.gen::<f32>() < self.epsilon {
+            return candidates.choose(&mut self.rng).cloned();
+        }
+
+        let mut best: Option<(f32, GameCommand, Vec<f32>)> = None;
+        for (command, features) in candidates {
+            let value = self.model.value(&features);
+            match best {
+                Some((max, _, _)) if max >= value => {}
+                _ => best = Some((value, command, features)),
+            }
+        }
+        best.map(|(_, command, features)| (command, features))
+    }
+
+    // 次の手番の状態での最大の行動価値
+    pub fn max_value(&self, user: &User, users: &[User], board: &Board) -> Option<f32> {
+        (0..NUMBER_OF_COMMANDS)
+            .filter_map(|input| Features::after(GameCommand::to_command(input), user, users, board))
+            .map(|features| self.model.value(&features))
+            .fold(None, |max: Option<f32>, value| {
+                Some(max.map_or(value, |max| max.max(value)))
+            })
+    }
+}
+
+impl Agent for QAgent {
+    fn name(&self) -> &str {
+        "q-learning"
+    }
+    fn read(&mut self, observation: &Observation) -> GameCommand {
+        let (users, board) = observation.to_game();
+        match self.select(&users[observation.get_seat()], &users, &board) {
+            Some((command, _)) => command,
+            None => GameCommand::ReserveDevelopmentCard { x: 0, y: 0 },
+        }
+    }
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::agent::{Agent, RandomAgent};
+    use crate::game::Game;
+    use crate::q_learning::{QAgent, QModel};
+
+    #[test]
+    fn test_play() {
+        let agents: Vec<Box<dyn Agent>> = vec![
+            Box::new(QAgent::new(QModel::new())),
+            Box::new(RandomAgent::new()),
+        ];
+        let result = Game::with_agents(agents).quiet().play().unwrap();
+        assert_eq!(result.vps.len(), 2);
+    }
+}
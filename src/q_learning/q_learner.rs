@@ -0,0 +1,122 @@
+use super::{QAgent, QLearner, QModel};
+use crate::game::agent::Agent;
+use crate::game::board::Board;
+use crate::game::observation::Observation;
+use crate::game::turn::{Decision, Phase, Turn};
+use crate::game::user::User;
+use crate::game::Game;
+
+const DEFAULT_LEARNING_RATE: f32 = 0.01;
+const DEFAULT_DISCOUNT: f32 = 0.99;
+const DEFAULT_EPSILON: f32 = 0.1;
+
+impl QLearner {
+    pub fn new(opponent: Box<dyn Agent>) -> QLearner {
+        QLearner {
+            opponent,
+            learning_rate: DEFAULT_LEARNING_RATE,
+            discount: DEFAULT_DISCOUNT,
+            epsilon: DEFAULT_EPSILON,
+        }
+    }
+
+    pub fn learning_rate(mut self, learning_rate: f32) -> QLearner {
+        self.learning_rate = learning_rate;
+        self
+    }
+
+    pub fn discount(mut self, discount: f32) -> QLearner {
+        self.discount = discount;
+        self
+    }
+
+    pub fn epsilon(mut self, epsilon: f32) -> QLearner {
+        self.epsilon = epsilon;
+        self
+    }
+
+    // episodes 回対局して重みを更新し、対局ごとに (対局, 勝ったか, モデル) を on_episode に渡す
+    // 先手と後手は交互に入れ替える
+    pub fn run<F>(&mut self, model: &mut QModel, episodes: u32, mut on_episode: F)
+    where
+        F: FnMut(u32, bool, &QModel),
+    {
+        let mut agent = QAgent::new(std::mem::take(model)).epsilon(self.epsilon);
+        for episode in 1..=episodes {
+            let seat = (episode % 2) as usize;
+            let won = self.play(&mut agent, seat);
+            on_episode(episode, won, agent.get_model());
+        }
+        *model = agent.model;
+    }
+
+    // 自分の手番ごとに、前回選んだ事後状態の価値を
+    // 今回の最大の行動価値(終局なら勝ち 1.0, 負け -1.0, 引き分け 0.0)に近づける
+    fn play(&mut self, agent: &mut QAgent, seat: usize) -> bool {
+        let mut board = Board::new();
+        let mut users: Vec<User> = (0..2).map(User::new).collect();
+        let mut previous: Option<Vec<f32>> = None;
+        let mut turn = Turn::new(users.len());
+
+        while let Some((index, phase)) = turn.pending() {
+            let phase = phase.clone();
+            if index != seat {
+                let observation = Observation::new(&users, &board, index).turn(turn.get_turn());
+                if let Some(decision) = self.opponent.decide(&observation, &phase) {
+                    let _ = turn.decide(decision, &mut users, &mut board);
+                }
+                continue;
+            }
+            // トークンを捨てる、貴族タイルを選ぶ段階は既定の選び方で決める
+            if phase != Phase::Main {
+                let decision = turn.default_decision(&users).unwrap();
+                let _ = turn.decide(decision, &mut users, &mut board);
+                continue;
+            }
+
+            let max_value = agent.max_value(&users[seat], &users, &board);
+            let (command, features) = match agent.select(&users[seat], &users, &board) {
+                Some(selected) => selected,
+                None => {
+                    turn.surrender(&users);
+                    continue;
+                }
+            };
+            if let (Some(features), Some(max_value)) = (previous.take(), max_value) {
+                let target = self.discount * max_value;
+                agent.model.update(&features, target, self.learning_rate);
+            }
+            let _ = turn.decide(Decision::Command(command), &mut users, &mut board);
+            previous = Some(features);
+        }
+
+        let result = Game::result(&users, turn.get_surrendered(), turn.get_turn());
+        let reward = match result.winner {
+            Some(winner) if winner as usize == seat => 1.0,
+            Some(_) => -1.0,
+            None => 0.0,
+        };
+        if let Some(features) = previous {
+            agent.model.update(&features, reward, self.learning_rate);
+        }
+        reward > 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::agent::RandomAgent;
+    use crate::q_learning::{QLearner, QModel};
+
+    #[test]
+    fn test_run() {
+        let mut model = QModel::new();
+        let mut episodes = 0;
+        QLearner::new(Box::new(RandomAgent::new())).run(&mut model, 2, |_, _, _| {
+            episodes += 1;
+        });
+
+        assert_eq!(episodes, 2);
+        assert!(model.get_weights().iter().all(|w| w.is_finite()));
+    }
+}
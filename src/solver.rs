@@ -0,0 +1,59 @@
+use crate::game::agent::Agent;
+use crate::game::board::Board;
+use crate::game::game_command::GameCommand;
+use crate::game::user::User;
+use crate::game::zobrist::Zobrist;
+use crate::transposition_table::SharedTable;
+
+mod endgame_agent;
+mod search;
+
+// 勝利点の条件に近い局面を残りの手番数を区切って全探索し、
+// 手番のユーザーが相手の応手にかかわらず勝てるかどうかを判定する
+//
+// 山札は盤面が持っている順番どおりに引かれるものとして扱う
+// 相手は全員で手番のユーザーの勝ちを妨げるように指すとみなす
+pub struct Solver {
+    rounds: u32,
+    node_limit: u64,
+    nodes: u64,
+    zobrist: Zobrist,
+    table: SharedTable<Entry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Solution {
+    // 必ず勝てる手
+    Win(GameCommand),
+    // 残りの手番数の範囲では必勝手がない
+    NoWin,
+    // 探索するノード数の上限を超えたため判定できなかった
+    Unknown,
+}
+
+// 誰かの勝利点が VP_TO_END - margin 以上になったら探索で手を選び、
+// それ以外や必勝手が見つからないときは fallback に任せる
+pub struct EndgameAgent {
+    fallback: Box<dyn Agent>,
+    solver: Solver,
+    margin: u8,
+}
+
+// 置換表に保存する root から見た結果
+// 勝ちは保存した残り手番数以上でも勝ち、勝てないは保存した残り手番数以下でも勝てない
+#[derive(Clone, Copy)]
+pub struct Entry {
+    root: usize,
+    win: bool,
+}
+
+// 探索中の局面
+#[derive(Clone)]
+struct Position {
+    users: Vec<User>,
+    board: Board,
+    seat: usize,
+    turn: u32,
+    surrendered: Vec<usize>,
+    hash: u64,
+}
@@ -0,0 +1,71 @@
+use super::{EndgameAgent, Solution, Solver};
+use crate::game::agent::Agent;
+use crate::game::game_command::GameCommand;
+use crate::game::observation::Observation;
+use crate::game::VP_TO_END;
+
+const DEFAULT_MARGIN: u8 = 3;
+
+impl EndgameAgent {
+    pub fn new(fallback: Box<dyn Agent>) -> EndgameAgent {
+        EndgameAgent {
+            fallback,
+            solver: Solver::new(),
+            margin: DEFAULT_MARGIN,
+        }
+    }
+
+    pub fn solver(mut self, solver: Solver) -> EndgameAgent {
+        self.solver = solver;
+        self
+    }
+
+    pub fn margin(mut self, margin: u8) -> EndgameAgent {
+        self.margin = margin;
+        self
+    }
+}
+
+impl Agent for EndgameAgent {
+    fn name(&self) -> &str {
+        "endgame"
+    }
+    fn read(&mut self, observation: &Observation) -> GameCommand {
+        let threshold = VP_TO_END.saturating_sub(self.margin);
+        if observation.players().iter().any(|p| p.vp >= threshold) {
+            // 見えていないカードに頼った勝ちを返さないよう、山札は空、伏せたカードはないものとして解く
+            let (users, board) = observation.to_known_game();
+            let seat = observation.get_seat();
+            if let Solution::Win(command) =
+                self.solver
+                    .solve(&users, &board, seat, observation.get_turn())
+            {
+                return command;
+            }
+        }
+        self.fallback.read(observation)
+    }
+    fn seed(&mut self, seed: u64) {
+        self.fallback.seed(seed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::agent::{Agent, HeuristicAgent, RandomAgent};
+    use crate::game::Game;
+    use crate::solver::{EndgameAgent, Solver};
+
+    #[test]
+    fn test_play() {
+        let agents: Vec<Box<dyn Agent>> = vec![
+            Box::new(
+                EndgameAgent::new(Box::new(HeuristicAgent::new()))
+                    .solver(Solver::new().rounds(1).node_limit(10_000)),
+            ),
+            Box::new(RandomAgent::new()),
+        ];
+        let result = Game::with_agents(agents).quiet().play().unwrap();
+        assert_eq!(result.vps.len(), 2);
+    }
+}
@@ -0,0 +1,273 @@
+use super::{Entry, Position, Solution, Solver};
+use crate::game::board::Board;
+use crate::game::game_command::GameCommand;
+use crate::game::turn::Turn;
+use crate::game::user::User;
+use crate::game::zobrist::Zobrist;
+use crate::game::{Game, MAX_NUMBER_OF_TURNS};
+use crate::transposition_table::{SharedTable, TranspositionTable};
+
+const DEFAULT_ROUNDS: u32 = 2;
+const DEFAULT_NODE_LIMIT: u64 = 1_000_000;
+const DEFAULT_TABLE_SIZE: usize = 1 << 16;
+
+// 勝利点に直結しやすい購入から順に試す
+const MOVE_ORDER: [std::ops::Range<u8>; 5] = [12..24, 42..45, 24..39, 0..12, 39..42];
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver {
+            rounds: DEFAULT_ROUNDS,
+            node_limit: DEFAULT_NODE_LIMIT,
+            nodes: 0,
+            zobrist: Zobrist::new(),
+            table: TranspositionTable::shared(DEFAULT_TABLE_SIZE),
+        }
+    }
+
+    // 探索する手番数(全員が 1回ずつ指すと 1)
+    pub fn rounds(mut self, rounds: u32) -> Solver {
+        self.rounds = rounds;
+        self
+    }
+
+    pub fn node_limit(mut self, node_limit: u64) -> Solver {
+        self.node_limit = node_limit;
+        self
+    }
+
+    // 山札の順番は対局の初めに決まるため、同じ対局の中であれば他の探索と置換表を共有できる
+    pub fn table(mut self, table: SharedTable<Entry>) -> Solver {
+        self.table = table;
+        self
+    }
+
+    pub fn get_nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    // users[seat] の手番の局面を解く
+    // turn は現在の手番目で、MAX_NUMBER_OF_TURNS による打ち切りの判定に使う
+    // 短い手数で勝てる手を優先するため、手番数を 1 から増やしながら探索する
+    pub fn solve(&mut self, users: &[User], board: &Board, seat: usize, turn: u32) -> Solution {
+        self.nodes = 0;
+        let position = Position {
+            users: users.to_vec(),
+            board: board.clone(),
+            seat,
+            turn,
+            surrendered: vec![],
+            hash: self.zobrist.hash(users, board, seat) ^ self.zobrist.turn(turn),
+        };
+
+        for rounds in 1..=self.rounds {
+            for (command, child) in self.children(&position) {
+                match self.search(&child, seat, rounds) {
+                    Some(true) => return Solution::Win(command),
+                    Some(false) => continue,
+                    None => return Solution::Unknown,
+                }
+            }
+        }
+        Solution::NoWin
+    }
+
+    // 局面 position の手番のユーザーがコマンドを実行した直後の局面から、
+    // 次に指すユーザーを決めて探索する
+    // root が必ず勝てるなら Some(true)、ノード数の上限を超えたら None
+    fn search(&mut self, position: &Position, root: usize, rounds: u32) -> Option<bool> {
+        let mut next = position.clone();
+        match self.advance(&mut next) {
+            Some(()) => {}
+            None => return Some(Solver::wins(&next, root)),
+        }
+        let rounds = if next.turn > position.turn {
+            rounds - 1
+        } else {
+            rounds
+        };
+        if rounds == 0 {
+            return Some(false);
+        }
+        self.evaluate(&next, root, rounds)
+    }
+
+    fn evaluate(&mut self, position: &Position, root: usize, rounds: u32) -> Option<bool> {
+        if let Some((depth, entry)) = self.table.borrow_mut().get(position.hash) {
+            if entry.root == root && (entry.win && depth <= rounds || !entry.win && depth >= rounds)
+            {
+                return Some(entry.win);
+            }
+        }
+
+        self.nodes += 1;
+        if self.nodes > self.node_limit {
+            return None;
+        }
+
+        let children = self.children(position);
+        let win = if children.is_empty() {
+            // 実行できるコマンドがなければ降参する
+            let mut child = position.clone();
+            child.surrendered.push(position.seat);
+            child.hash ^= self.zobrist.surrendered(position.seat);
+            if position.seat == root {
+                false
+            } else {
+                self.search(&child, root, rounds)?
+            }
+        } else if position.seat == root {
+            let mut win = false;
+            for (_, child) in children.iter() {
+                if self.search(child, root, rounds)? {
+                    win = true;
+                    break;
+                }
+            }
+            win
+        } else {
+            let mut win = true;
+            for (_, child) in children.iter() {
+                if !self.search(child, root, rounds)? {
+                    win = false;
+                    break;
+                }
+            }
+            win
+        };
+
+        self.table
+            .borrow_mut()
+            .insert(position.hash, rounds, Entry { root, win });
+        Some(win)
+    }
+
+    fn children(&self, position: &Position) -> Vec<(GameCommand, Position)> {
+        let mut children = vec![];
+        for range in MOVE_ORDER.iter() {
+            for input in range.clone() {
+                let command = GameCommand::to_command(input);
+                let mut child = position.clone();
+                let seat = child.seat;
+                if Turn::play(command.clone(), &mut child.users[seat], &mut child.board).is_ok() {
+                    child.hash = self.zobrist.update(
+                        position.hash,
+                        seat,
+                        (&position.users[seat], &position.board),
+                        (&child.users[seat], &child.board),
+                    );
+                    children.push((command, child));
+                }
+            }
+        }
+        children
+    }
+
+    // 次に指すユーザーへ手番を移す
+    // 一巡してゲームが終わっていれば None
+    fn advance(&self, position: &mut Position) -> Option<()> {
+        let number_of_users = position.users.len();
+        position.hash ^= self.zobrist.side(position.seat);
+        let mut seat = position.seat + 1;
+        while seat < number_of_users && position.surrendered.contains(&seat) {
+            seat += 1;
+        }
+        if seat < number_of_users {
+            position.seat = seat;
+            position.hash ^= self.zobrist.side(seat);
+            return Some(());
+        }
+
+        if Game::is_over(&position.users)
+            || position.surrendered.len() + 1 >= number_of_users
+            || position.turn >= MAX_NUMBER_OF_TURNS
+        {
+            return None;
+        }
+        position.hash ^= self.zobrist.turn(position.turn) ^ self.zobrist.turn(position.turn + 1);
+        position.turn += 1;
+        position.seat = (0..number_of_users)
+            .find(|seat| !position.surrendered.contains(seat))
+            .unwrap_or(0);
+        position.hash ^= self.zobrist.side(position.seat);
+        Some(())
+    }
+
+    fn wins(position: &Position, root: usize) -> bool {
+        let result = Game::result(&position.users, &position.surrendered, position.turn);
+        result.winner == Some(position.users[root].get_id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::board::Board;
+    use crate::game::color::Color::*;
+    use crate::game::token_stack::Token;
+    use crate::game::turn::Turn;
+    use crate::game::user::User;
+    use crate::solver::{Solution, Solver};
+    use crate::transposition_table::TranspositionTable;
+
+    #[test]
+    fn test_solve_win() {
+        let board = Board::new();
+        let mut users = vec![User::new(0), User::new(1)];
+        users[0].add_vp(14);
+        for color in [Black, White, Red, Blue, Green].iter() {
+            for _ in 0..7 {
+                users[0].add_token(Token::new(*color));
+            }
+        }
+
+        let mut solver = Solver::new().rounds(1);
+        match solver.solve(&users, &board, 0, 1) {
+            Solution::Win(command) => {
+                let (mut user, mut board) = (users[0].clone(), board.clone());
+                Turn::play(command, &mut user, &mut board).unwrap();
+                assert!(user.get_vp() >= 15);
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_solve_no_win() {
+        let board = Board::new();
+        let users = vec![User::new(0), User::new(1)];
+
+        let mut solver = Solver::new().rounds(1);
+        assert_eq!(solver.solve(&users, &board, 0, 1), Solution::NoWin);
+        assert!(solver.get_nodes() > 0);
+    }
+
+    #[test]
+    fn test_solve_unknown() {
+        let board = Board::new();
+        let users = vec![User::new(0), User::new(1)];
+
+        let mut solver = Solver::new().rounds(3).node_limit(10);
+        assert_eq!(solver.solve(&users, &board, 0, 1), Solution::Unknown);
+    }
+
+    #[test]
+    fn test_shared_table() {
+        let board = Board::new();
+        let users = vec![User::new(0), User::new(1)];
+        let table = TranspositionTable::shared(1 << 12);
+
+        let mut first = Solver::new().rounds(1).table(table.clone());
+        assert_eq!(first.solve(&users, &board, 0, 1), Solution::NoWin);
+        assert!(!table.borrow().is_empty());
+
+        let mut second = Solver::new().rounds(1).table(table);
+        assert_eq!(second.solve(&users, &board, 0, 1), Solution::NoWin);
+        assert!(second.get_nodes() < first.get_nodes());
+    }
+}
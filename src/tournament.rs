@@ -0,0 +1,278 @@
+use crate::game::agent::Agent;
+use crate::game::game_event::JsonLinesLogger;
+use crate::game::locale::{message, text};
+use crate::game::Game;
+
+use ndarray::Array2;
+use serde_derive::Serialize;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+mod rating;
+
+pub struct Entrant {
+    name: String,
+    create: Box<dyn Fn() -> Box<dyn Agent>>,
+}
+
+// 全ての組み合わせで対局させ、席順は対局ごとに入れ替える
+pub struct Tournament {
+    entrants: Vec<Entrant>,
+    games_per_pairing: u32,
+    log: Option<File>,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct Standing {
+    pub name: String,
+    pub games: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    pub average_vp: f64,
+    pub average_turns: f64,
+    pub rating: f64,
+    pub rating_error: f64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Pairing {
+    pub first: String,
+    pub second: String,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+#[derive(Serialize, Clone)]
+pub struct TournamentReport {
+    pub standings: Vec<Standing>,
+    pub pairings: Vec<Pairing>,
+}
+
+impl Tournament {
+    pub fn new(games_per_pairing: u32) -> Tournament {
+        Tournament {
+            entrants: vec![],
+            games_per_pairing,
+            log: None,
+        }
+    }
+
+    // 全ての対局の GameEvent を JSON Lines で file に書き出す
+    pub fn log(mut self, file: File) -> Tournament {
+        self.log = Some(file);
+        self
+    }
+
+    pub fn add<F>(mut self, name: &str, create: F) -> Tournament
+    where
+        F: Fn() -> Box<dyn Agent> + 'static,
+    {
+        self.entrants.push(Entrant {
+            name: name.to_string(),
+            create: Box::new(create),
+        });
+        self
+    }
+
+    pub fn run(&self) -> TournamentReport {
+        let n = self.entrants.len();
+        let mut standings: Vec<Standing> = self
+            .entrants
+            .iter()
+            .map(|entrant| Standing {
+                name: entrant.name.clone(),
+                ..Default::default()
+            })
+            .collect();
+        let mut total_vp = vec![0u32; n];
+        let mut total_turns = vec![0u32; n];
+        let mut scores = Array2::<f64>::zeros((n, n));
+        let mut games = Array2::<f64>::zeros((n, n));
+        let mut pairings = vec![];
+        let mut number_of_games = 0;
+
+        for i in 0..n {
+            for j in i + 1..n {
+                let mut pairing = Pairing {
+                    first: self.entrants[i].name.clone(),
+                    second: self.entrants[j].name.clone(),
+                    wins: 0,
+                    losses: 0,
+                    draws: 0,
+                };
+
+                for game in 0..self.games_per_pairing {
+                    let seats = if game % 2 == 0 { [i, j] } else { [j, i] };
+                    let agents = seats
+                        .iter()
+                        .map(|seat| (self.entrants[*seat].create)())
+                        .collect();
+                    let mut game = Game::with_agents(agents).quiet();
+                    if let Some(file) = self.log.as_ref().and_then(|f| f.try_clone().ok()) {
+                        let players = seats
+                            .iter()
+                            .map(|seat| self.entrants[*seat].name.clone())
+                            .collect();
+                        game.add_observer(Box::new(
+                            JsonLinesLogger::new(BufWriter::new(file))
+                                .game(number_of_games)
+                                .players(players),
+                        ));
+                    }
+                    number_of_games += 1;
+                    // エージェントは席の数だけいる
+                    let result = game.play().unwrap();
+
+                    for (position, seat) in seats.iter().enumerate() {
+                        standings[*seat].games += 1;
+                        total_vp[*seat] += u32::from(result.vps[position]);
+                        total_turns[*seat] += result.turns;
+                    }
+                    games[[i, j]] += 1.0;
+                    games[[j, i]] += 1.0;
+
+                    match result.winner.map(|winner| seats[winner as usize]) {
+                        Some(winner) => {
+                            let loser = if winner == i { j } else { i };
+                            standings[winner].wins += 1;
+                            standings[loser].losses += 1;
+                            scores[[winner, loser]] += 1.0;
+                            if winner == i {
+                                pairing.wins += 1;
+                            } else {
+                                pairing.losses += 1;
+                            }
+                        }
+                        None => {
+                            standings[i].draws += 1;
+                            standings[j].draws += 1;
+                            scores[[i, j]] += 0.5;
+                            scores[[j, i]] += 0.5;
+                            pairing.draws += 1;
+                        }
+                    }
+                }
+                pairings.push(pairing);
+            }
+        }
+
+        let ratings = rating::estimate(&scores, &games);
+        for (index, standing) in standings.iter_mut().enumerate() {
+            if standing.games > 0 {
+                standing.average_vp = f64::from(total_vp[index]) / f64::from(standing.games);
+                standing.average_turns = f64::from(total_turns[index]) / f64::from(standing.games);
+            }
+            standing.rating = ratings[index].0;
+            standing.rating_error = ratings[index].1;
+        }
+        standings.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+
+        TournamentReport {
+            standings,
+            pairings,
+        }
+    }
+}
+
+impl fmt::Display for TournamentReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<16} {:>6} {:>6} {:>6} {:>6} {:>10} {:>10} {:>14}",
+            text("tournament.agent"),
+            text("tournament.games"),
+            text("tournament.wins"),
+            text("tournament.losses"),
+            text("tournament.draws"),
+            text("tournament.average_vp"),
+            text("tournament.average_turns"),
+            text("tournament.rating")
+        )?;
+        for s in self.standings.iter() {
+            writeln!(
+                f,
+                "{:<16} {:>6} {:>6} {:>6} {:>6} {:>10.2} {:>10.1} {:>7.0} ±{:<5.0}",
+                s.name,
+                s.games,
+                s.wins,
+                s.losses,
+                s.draws,
+                s.average_vp,
+                s.average_turns,
+                s.rating,
+                s.rating_error
+            )?;
+        }
+        writeln!(f)?;
+        for p in self.pairings.iter() {
+            writeln!(
+                f,
+                "{}",
+                message(
+                    "tournament.pairing",
+                    &[&p.first, &p.second, &p.wins, &p.losses, &p.draws]
+                )
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl TournamentReport {
+    pub fn write_json(&self, file_name: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        File::create(file_name)?.write_all(json.as_bytes())
+    }
+
+    pub fn write_csv(&self, file_name: &str) -> io::Result<()> {
+        let mut file = File::create(file_name)?;
+        writeln!(
+            file,
+            "name,games,wins,losses,draws,average_vp,average_turns,rating,rating_error"
+        )?;
+        for s in self.standings.iter() {
+            writeln!(
+                file,
+                "{},{},{},{},{},{},{},{},{}",
+                s.name,
+                s.games,
+                s.wins,
+                s.losses,
+                s.draws,
+                s.average_vp,
+                s.average_turns,
+                s.rating,
+                s.rating_error
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tournament;
+    use crate::game::agent::{Agent, HeuristicAgent, RandomAgent};
+
+    #[test]
+    fn test_run() {
+        let report = Tournament::new(2)
+            .add("random", || Box::new(RandomAgent::new()) as Box<dyn Agent>)
+            .add("heuristic", || {
+                Box::new(HeuristicAgent::new()) as Box<dyn Agent>
+            })
+            .run();
+
+        assert_eq!(report.standings.len(), 2);
+        assert_eq!(report.pairings.len(), 1);
+        for standing in report.standings.iter() {
+            assert_eq!(standing.games, 2);
+            assert_eq!(standing.wins + standing.losses + standing.draws, 2);
+        }
+        let pairing = &report.pairings[0];
+        assert_eq!(pairing.wins + pairing.losses + pairing.draws, 2);
+    }
+}
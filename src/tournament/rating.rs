@@ -0,0 +1,95 @@
+use ndarray::Array2;
+
+const INITIAL_RATING: f64 = 1500.0;
+const MAX_NUMBER_OF_ITERATIONS: usize = 1000;
+const Z_95: f64 = 1.96;
+
+// Bradley-Terry モデルを MM 法で推定し、Elo 換算の (レーティング, 95%信頼区間の幅) を返す
+// scores[[i, j]] は i が j から得た勝ち点(引き分けは 0.5)、games[[i, j]] は対局数
+pub fn estimate(scores: &Array2<f64>, games: &Array2<f64>) -> Vec<(f64, f64)> {
+    let n = scores.rows();
+
+    // 全勝・全敗でも発散しないように、対局した組ごとに1局分の引き分けを加える
+    let mut scores = scores.clone();
+    let mut games = games.clone();
+    for i in 0..n {
+        for j in 0..n {
+            if i != j && games[[i, j]] > 0.0 {
+                scores[[i, j]] += 0.5;
+                games[[i, j]] += 1.0;
+            }
+        }
+    }
+
+    let mut strength = vec![1.0; n];
+    for _ in 0..MAX_NUMBER_OF_ITERATIONS {
+        let mut next = strength.clone();
+        for i in 0..n {
+            let won: f64 = scores.row(i).sum();
+            let expected: f64 = (0..n)
+                .filter(|j| *j != i)
+                .map(|j| games[[i, j]] / (strength[i] + strength[j]))
+                .sum();
+            if expected > 0.0 {
+                next[i] = won / expected;
+            }
+        }
+
+        // 幾何平均が1になるように正規化する
+        let mean = next.iter().map(|s: &f64| s.ln()).sum::<f64>() / n as f64;
+        for s in next.iter_mut() {
+            *s /= mean.exp();
+        }
+
+        let delta = next
+            .iter()
+            .zip(strength.iter())
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max);
+        strength = next;
+        if delta < 1e-9 {
+            break;
+        }
+    }
+
+    let scale = 400.0 / std::f64::consts::LN_10;
+    (0..n)
+        .map(|i| {
+            let information: f64 = (0..n)
+                .filter(|j| *j != i)
+                .map(|j| {
+                    let p = strength[i] / (strength[i] + strength[j]);
+                    games[[i, j]] * p * (1.0 - p)
+                })
+                .sum();
+            let rating = INITIAL_RATING + scale * strength[i].ln();
+            let error = if information > 0.0 {
+                Z_95 * scale / information.sqrt()
+            } else {
+                f64::INFINITY
+            };
+            (rating, error)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::estimate;
+    use ndarray::arr2;
+
+    #[test]
+    fn test_estimate() {
+        let scores = arr2(&[[0.0, 5.0], [5.0, 0.0]]);
+        let games = arr2(&[[0.0, 10.0], [10.0, 0.0]]);
+        let ratings = estimate(&scores, &games);
+        assert!((ratings[0].0 - 1500.0).abs() < 1e-6);
+        assert!((ratings[1].0 - 1500.0).abs() < 1e-6);
+
+        let scores = arr2(&[[0.0, 9.0], [1.0, 0.0]]);
+        let ratings = estimate(&scores, &games);
+        assert!(ratings[0].0 > ratings[1].0);
+        assert!((ratings[0].0 + ratings[1].0 - 3000.0).abs() < 1e-6);
+        assert!(ratings[0].1 > 0.0 && ratings[0].1.is_finite());
+    }
+}
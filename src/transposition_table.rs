@@ -0,0 +1,127 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// 複数の探索エージェントで共有するための置換表
+pub type SharedTable<T> = Rc<RefCell<TranspositionTable<T>>>;
+
+// ハッシュ値の下位ビットで場所を決める固定長の置換表
+// 同じ場所に別の局面が来たときは、探索の深さが同じか深いほうで上書きする
+// 衝突の確認にはハッシュ値全体を保存して比べる
+pub struct TranspositionTable<T> {
+    slots: Vec<Option<Slot<T>>>,
+    mask: u64,
+    len: usize,
+    hits: u64,
+    probes: u64,
+}
+
+struct Slot<T> {
+    hash: u64,
+    depth: u32,
+    value: T,
+}
+
+impl<T: Clone> TranspositionTable<T> {
+    // capacity は 2 の累乗に切り上げる
+    pub fn new(capacity: usize) -> TranspositionTable<T> {
+        let capacity = capacity.max(1).next_power_of_two();
+        TranspositionTable {
+            slots: (0..capacity).map(|_| None).collect(),
+            mask: capacity as u64 - 1,
+            len: 0,
+            hits: 0,
+            probes: 0,
+        }
+    }
+
+    pub fn shared(capacity: usize) -> SharedTable<T> {
+        Rc::new(RefCell::new(TranspositionTable::new(capacity)))
+    }
+
+    pub fn get(&mut self, hash: u64) -> Option<(u32, T)> {
+        self.probes += 1;
+        match &self.slots[(hash & self.mask) as usize] {
+            Some(slot) if slot.hash == hash => {
+                self.hits += 1;
+                Some((slot.depth, slot.value.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, hash: u64, depth: u32, value: T) {
+        let slot = &mut self.slots[(hash & self.mask) as usize];
+        match slot {
+            Some(old) if old.hash != hash && old.depth > depth => {}
+            _ => {
+                if slot.is_none() {
+                    self.len += 1;
+                }
+                *slot = Some(Slot { hash, depth, value });
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for slot in self.slots.iter_mut() {
+            *slot = None;
+        }
+        self.len = 0;
+        self.hits = 0;
+        self.probes = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    // 参照した回数のうち見つかった割合
+    pub fn hit_rate(&self) -> f64 {
+        if self.probes == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.probes as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TranspositionTable;
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut table = TranspositionTable::new(3);
+        assert_eq!(table.capacity(), 4);
+
+        table.insert(1, 2, "a");
+        assert_eq!(table.get(1), Some((2, "a")));
+        assert_eq!(table.get(5), None);
+
+        // 同じ場所の浅い探索では上書きしない
+        table.insert(5, 1, "b");
+        assert_eq!(table.get(1), Some((2, "a")));
+        table.insert(5, 3, "c");
+        assert_eq!(table.get(5), Some((3, "c")));
+        assert_eq!(table.len(), 1);
+
+        table.clear();
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn test_shared() {
+        let table = TranspositionTable::shared(16);
+        let other = table.clone();
+        table.borrow_mut().insert(7, 0, 1.0);
+        assert_eq!(other.borrow_mut().get(7), Some((0, 1.0)));
+    }
+}
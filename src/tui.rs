@@ -0,0 +1,144 @@
+use crate::game::agent::Agent;
+use crate::game::color::Color;
+use crate::game::game_event::{GameEvent, GameObserver};
+use crate::game::level::Level;
+use crate::game::renderer::Renderer;
+use crate::game::Game;
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::Duration;
+
+mod input;
+mod screen;
+mod terminal;
+
+// ログに残す出来事の数
+const LOG_SIZE: usize = 100;
+const DEFAULT_DELAY: Duration = Duration::from_millis(500);
+
+// 端末の画面全体を使って対局する
+// agents の None の席は人が、Some の席はエージェントが指す
+// 画面は人の席の視点で描き、他のユーザーが伏せて確保したカードは見えない
+// 人が複数いればホットシートにして、人の手番が終わるたびに画面を消して交代を待つ
+pub struct Tui {
+    game: Game,
+    agents: Vec<Option<Box<dyn Agent>>>,
+    renderer: Renderer,
+    log: Rc<RefCell<VecDeque<String>>>,
+    // 画面に出している席 (エージェントの手番では直前の人の席のまま)
+    viewer: usize,
+    // 手の一覧で選んでいる位置
+    cursor: usize,
+    // 場のカードなど、クリックや b, r で選んだもの
+    selected: Option<Target>,
+    // 取るつもりで選んだトークン
+    tokens: Vec<Color>,
+    status: String,
+    // 最後に描いた画面でクリックできる場所
+    regions: Vec<Region>,
+    // ホットシートで次の人が Enter を押すまで局面を隠している
+    handoff: bool,
+    delay: Duration,
+    quit: bool,
+}
+
+// 画面で選べるもの
+// Card は場の (x, y)、Reserved は手札の何枚目か、Noble は貴族タイルの id、Move は手の一覧の何番目か
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    Card(u8, u8),
+    Deck(Level),
+    Reserved(u8),
+    Token(Color),
+    Noble(u8),
+    Move(usize),
+}
+
+// 画面の row 行目の col 列目から width 列分
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    pub row: u16,
+    pub col: u16,
+    pub width: u16,
+    pub target: Target,
+}
+
+// 端末によらない入力
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Input {
+    Up,
+    Down,
+    Enter,
+    Cancel,
+    Quit,
+    Char(char),
+    Click { col: u16, row: u16 },
+}
+
+// 最近の出来事を LOG_SIZE 個まで覚えておく GameObserver
+struct EventLog(Rc<RefCell<VecDeque<String>>>);
+
+impl GameObserver for EventLog {
+    fn notify(&mut self, event: &GameEvent) {
+        let mut log = self.0.borrow_mut();
+        if log.len() == LOG_SIZE {
+            log.pop_front();
+        }
+        log.push_back(event.to_string());
+    }
+}
+
+impl Tui {
+    pub fn new(agents: Vec<Option<Box<dyn Agent>>>) -> Tui {
+        let log = Rc::new(RefCell::new(VecDeque::new()));
+        let game = Game::with_users(agents.len())
+            .quiet()
+            .observer(Box::new(EventLog(log.clone())));
+        let viewer = agents.iter().position(Option::is_none).unwrap_or(0);
+        Tui {
+            game,
+            agents,
+            renderer: Renderer::new(),
+            log,
+            viewer,
+            cursor: 0,
+            selected: None,
+            tokens: vec![],
+            status: String::new(),
+            regions: vec![],
+            handoff: false,
+            delay: DEFAULT_DELAY,
+            quit: false,
+        }
+    }
+
+    // エージェントが 1手指すごとに待つ時間
+    pub fn delay(mut self, delay: Duration) -> Tui {
+        self.delay = delay;
+        self
+    }
+
+    pub fn renderer(mut self, renderer: Renderer) -> Tui {
+        self.renderer = renderer;
+        self
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.game.pending().is_none()
+    }
+
+    // 決める番の席に人が座っているか
+    pub fn is_human_turn(&self) -> bool {
+        match self.game.pending() {
+            Some((seat, _)) => self.agents[seat].is_none(),
+            None => false,
+        }
+    }
+
+    // 人が 2人以上座っているか
+    pub fn is_hot_seat(&self) -> bool {
+        self.agents.iter().filter(|agent| agent.is_none()).count() > 1
+    }
+}
@@ -0,0 +1,379 @@
+use super::{Input, Target, Tui};
+use crate::game::color::Color::{self, *};
+use crate::game::encoder::Encoder;
+use crate::game::game_command::{GameCommand, NUMBER_OF_COMMANDS};
+use crate::game::gem::GEMS;
+use crate::game::locale::text;
+use crate::game::turn::{Decision, Phase};
+
+// 捨てるときの数字キーの順 (6 は金トークン)
+const DISCARD_COLORS: [Color; 6] = [Black, White, Red, Blue, Green, Gold];
+
+impl Tui {
+    // 人の入力で画面の選択を変えたり、決めたことを Game に渡したりする
+    // エージェントの手番や終局後は q だけを受け付ける
+    // 交代を待っているときは Enter で次の人の視点に切り替える
+    pub fn handle(&mut self, input: Input) {
+        if input == Input::Quit {
+            self.quit = true;
+            return;
+        }
+        if !self.is_human_turn() {
+            return;
+        }
+        if self.handoff {
+            if input == Input::Enter {
+                self.handoff = false;
+                self.update_viewer();
+            }
+            return;
+        }
+        self.update_viewer();
+        let phase = match self.game.pending() {
+            Some((_, phase)) => phase.clone(),
+            None => return,
+        };
+
+        match input {
+            Input::Click { col, row } => {
+                if let Some(target) = self.target_at(col, row) {
+                    self.select(target, &phase);
+                }
+            }
+            _ => match phase {
+                Phase::Main => self.handle_main(input),
+                Phase::Discard(_) => {
+                    if let Some(color) = Tui::digit(input).and_then(|n| DISCARD_COLORS.get(n)) {
+                        self.decide(Decision::Discard(*color));
+                    }
+                }
+                Phase::Noble(ids) => {
+                    if let Some(id) = Tui::digit(input).and_then(|n| ids.get(n)) {
+                        self.decide(Decision::Noble(*id));
+                    }
+                }
+                Phase::Over => {}
+            },
+        }
+    }
+
+    // 決める番のエージェントに決めさせる
+    // 実行できない手を選んだときは Turn が数えて、続けば降参になる
+    pub fn play_agent(&mut self) {
+        let (seat, phase) = match self.game.pending() {
+            Some((seat, phase)) => (seat, phase.clone()),
+            None => return,
+        };
+        let observation = self.game.observation(seat);
+        let agent = match self.agents[seat].as_mut() {
+            Some(agent) => agent,
+            None => return,
+        };
+        if let Some(decision) = agent.decide(&observation, &phase) {
+            let _ = self.game.decide(decision);
+        }
+    }
+
+    // 画面に出している席が今指せる手 (コマンドの番号順)
+    pub fn moves(&self) -> Vec<GameCommand> {
+        match self.game.pending() {
+            Some((seat, Phase::Main)) if seat == self.viewer && self.agents[seat].is_none() => {}
+            _ => return vec![],
+        }
+        let (users, board) = self.game.observation(self.viewer).to_game();
+        let mask = Encoder::legal_action_mask(&users[self.viewer], &board);
+        (0..NUMBER_OF_COMMANDS)
+            .filter(|input| mask[*input as usize] > 0.0)
+            .map(GameCommand::to_command)
+            .collect()
+    }
+
+    // 選んでいるトークンに color を足せるか
+    // 同じ色 2個は場に 4個以上あるときだけ、違う色は 3色まで
+    pub(crate) fn can_add(&self, color: Color, moves: &[GameCommand]) -> bool {
+        let supply = self
+            .game
+            .observation(self.viewer)
+            .get_number_of_tokens(color);
+        match self.tokens.as_slice() {
+            [] => supply > 0,
+            [first] if *first == color => moves.contains(&GameCommand::SelectTwoSameTokens(color)),
+            tokens => tokens.len() < 3 && !self.is_pair() && !tokens.contains(&color) && supply > 0,
+        }
+    }
+
+    // 人の番なら、その人の視点で画面を描く
+    pub(crate) fn update_viewer(&mut self) {
+        if let Some((seat, _)) = self.game.pending() {
+            if self.agents[seat].is_none() {
+                self.viewer = seat;
+            }
+        }
+    }
+
+    fn handle_main(&mut self, input: Input) {
+        let moves = self.moves();
+        match input {
+            Input::Up => self.cursor = self.cursor.saturating_sub(1),
+            Input::Down => self.cursor = (self.cursor + 1).min(moves.len().saturating_sub(1)),
+            Input::Enter => {
+                if let Some(command) = moves.get(self.cursor) {
+                    self.decide(Decision::Command(command.clone()));
+                }
+            }
+            Input::Cancel => self.clear(),
+            Input::Char('b') | Input::Char('r') => {
+                if let Some(Target::Card(x, y)) = self.selected {
+                    let command = match input {
+                        Input::Char('b') => GameCommand::BuyDevelopmentCard { x, y },
+                        _ => GameCommand::ReserveDevelopmentCard { x, y },
+                    };
+                    self.focus(&command, &moves);
+                }
+            }
+            _ => {
+                if let Some(color) = Tui::digit(input).and_then(|n| GEMS.get(n)) {
+                    self.toggle(*color, &moves);
+                }
+            }
+        }
+    }
+
+    fn select(&mut self, target: Target, phase: &Phase) {
+        let moves = self.moves();
+        match (phase, target) {
+            (Phase::Main, Target::Card(x, y)) => {
+                self.selected = Some(target);
+                let buy = GameCommand::BuyDevelopmentCard { x, y };
+                if !moves.contains(&buy) {
+                    self.focus(&GameCommand::ReserveDevelopmentCard { x, y }, &moves);
+                } else {
+                    self.focus(&buy, &moves);
+                }
+            }
+            (Phase::Main, Target::Deck(level)) => {
+                self.selected = Some(target);
+                self.focus(&GameCommand::ReserveStackCard(level), &moves);
+            }
+            (Phase::Main, Target::Reserved(order)) => {
+                self.selected = Some(target);
+                self.focus(&GameCommand::BuyReservedCard(order), &moves);
+            }
+            (Phase::Main, Target::Token(color)) => self.toggle(color, &moves),
+            (Phase::Main, Target::Move(index)) => {
+                if self.cursor != index {
+                    self.cursor = index;
+                } else if let Some(command) = moves.get(index) {
+                    self.decide(Decision::Command(command.clone()));
+                }
+            }
+            (Phase::Discard(_), Target::Token(color)) => self.decide(Decision::Discard(color)),
+            (Phase::Noble(ids), Target::Noble(id)) if ids.contains(&id) => {
+                self.decide(Decision::Noble(id))
+            }
+            _ => {}
+        }
+    }
+
+    // 選んだトークンを取る手があれば、手の一覧でその手を選ぶ
+    fn toggle(&mut self, color: Color, moves: &[GameCommand]) {
+        if self.can_add(color, moves) {
+            self.tokens.push(color);
+        } else if self.tokens.contains(&color) {
+            self.tokens.retain(|c| *c != color);
+        } else {
+            self.status = text("tui.illegal").to_string();
+            return;
+        }
+        self.status.clear();
+
+        let found = moves.iter().position(|command| match command {
+            GameCommand::SelectTwoSameTokens(c) => self.is_pair() && self.tokens[0] == *c,
+            GameCommand::SelectThreeTokens(c1, c2, c3) => {
+                self.tokens.len() == 3 && [c1, c2, c3].iter().all(|c| self.tokens.contains(c))
+            }
+            _ => false,
+        });
+        if let Some(index) = found {
+            self.cursor = index;
+        }
+    }
+
+    fn focus(&mut self, command: &GameCommand, moves: &[GameCommand]) {
+        match moves.iter().position(|m| m == command) {
+            Some(index) => {
+                self.cursor = index;
+                self.status.clear();
+            }
+            None => self.status = text("tui.illegal").to_string(),
+        }
+    }
+
+    fn decide(&mut self, decision: Decision) {
+        match self.game.decide(decision) {
+            Ok(()) => {
+                self.clear();
+                self.cursor = 0;
+                // 手番が他の席に移ったら、次の人が来るまで画面を隠す
+                let moved = self
+                    .game
+                    .pending()
+                    .is_some_and(|(seat, _)| seat != self.viewer);
+                self.handoff = self.is_hot_seat() && moved;
+            }
+            Err(message) => self.status = message,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.selected = None;
+        self.tokens.clear();
+        self.status.clear();
+    }
+
+    fn is_pair(&self) -> bool {
+        self.tokens.len() == 2 && self.tokens[0] == self.tokens[1]
+    }
+
+    fn target_at(&self, col: u16, row: u16) -> Option<Target> {
+        self.regions
+            .iter()
+            .find(|region| {
+                region.row == row && region.col <= col && col < region.col + region.width
+            })
+            .map(|region| region.target)
+    }
+
+    // 1 から始まる数字キーを 0 から始まる番号にする
+    fn digit(input: Input) -> Option<usize> {
+        match input {
+            Input::Char(c) => c.to_digit(10).filter(|n| *n > 0).map(|n| n as usize - 1),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::color::Color::*;
+    use crate::game::game_command::GameCommand;
+    use crate::game::observation::Reserved;
+    use crate::game::turn::Phase;
+    use crate::tui::{Input, Target, Tui};
+
+    fn hot_seat() -> Tui {
+        Tui::new(vec![None, None])
+    }
+
+    #[test]
+    fn test_moves() {
+        let tui = hot_seat();
+        let moves = tui.moves();
+        assert!(moves.contains(&GameCommand::SelectTwoSameTokens(Red)));
+        assert!(moves.contains(&GameCommand::ReserveStackCard(
+            crate::game::level::Level::One
+        )));
+        assert!(!moves
+            .iter()
+            .any(|m| matches!(m, GameCommand::BuyReservedCard(_))));
+    }
+
+    #[test]
+    fn test_tokens() {
+        let mut tui = hot_seat();
+        tui.handle(Input::Char('3'));
+        tui.handle(Input::Char('3'));
+        assert_eq!(
+            tui.moves()[tui.cursor],
+            GameCommand::SelectTwoSameTokens(Red)
+        );
+        // 同じ色を 2個選んだ後は他の色を足せない
+        tui.handle(Input::Char('4'));
+        assert_eq!(tui.tokens, vec![Red, Red]);
+        assert!(!tui.status.is_empty());
+
+        tui.handle(Input::Cancel);
+        for key in ['1', '2', '5'].iter() {
+            tui.handle(Input::Char(*key));
+        }
+        assert_eq!(
+            tui.moves()[tui.cursor],
+            GameCommand::SelectThreeTokens(Black, White, Green)
+        );
+        tui.handle(Input::Enter);
+        assert_eq!(tui.game.pending(), Some((1, &Phase::Main)));
+        assert_eq!(tui.viewer, 0);
+        assert!(tui.tokens.is_empty());
+        let observation = tui.game.observation(0);
+        assert_eq!(observation.players()[0].token_stack.len(Green), 1);
+    }
+
+    #[test]
+    fn test_click() {
+        let mut tui = hot_seat();
+        tui.draw(200);
+        let region = *tui
+            .regions
+            .iter()
+            .find(|region| region.target == Target::Card(2, 1))
+            .unwrap();
+        tui.handle(Input::Click {
+            col: region.col,
+            row: region.row,
+        });
+        assert_eq!(tui.selected, Some(Target::Card(2, 1)));
+        // 最初は買えないので確保する手を選ぶ
+        assert_eq!(
+            tui.moves()[tui.cursor],
+            GameCommand::ReserveDevelopmentCard { x: 2, y: 1 }
+        );
+        tui.handle(Input::Char('b'));
+        assert!(!tui.status.is_empty());
+        tui.handle(Input::Char('r'));
+        tui.handle(Input::Enter);
+        assert_eq!(tui.game.observation(0).players()[0].hand.len(), 1);
+    }
+
+    #[test]
+    fn test_agent() {
+        let mut tui = Tui::new(vec![
+            None,
+            Some(Box::new(crate::game::agent::RandomAgent::new())),
+        ]);
+        tui.handle(Input::Char('1'));
+        tui.handle(Input::Char('2'));
+        tui.handle(Input::Char('3'));
+        tui.handle(Input::Enter);
+        assert!(!tui.is_human_turn());
+        // エージェントの番は入力を受け付けない
+        tui.handle(Input::Char('1'));
+        assert!(tui.tokens.is_empty());
+        while !tui.is_human_turn() && !tui.is_over() {
+            tui.play_agent();
+        }
+        assert_eq!(tui.viewer, 0);
+        assert!(!tui.handoff);
+        assert!(!tui.log.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_handoff() {
+        let mut tui = hot_seat();
+        let reserve = GameCommand::ReserveStackCard(crate::game::level::Level::One);
+        tui.cursor = tui.moves().iter().position(|m| *m == reserve).unwrap();
+        tui.handle(Input::Enter);
+        assert!(tui.handoff);
+        // 交代するまでは局面を出さず、Enter 以外は受け付けない
+        let lines = tui.draw(200);
+        assert!(tui.regions.is_empty());
+        assert!(!lines.iter().any(|line| line.contains('┌')));
+        tui.handle(Input::Char('1'));
+        assert!(tui.tokens.is_empty());
+
+        tui.handle(Input::Enter);
+        assert!(!tui.handoff);
+        assert_eq!(tui.viewer, 1);
+        let hidden = Reserved::Hidden(crate::game::level::Level::One).to_string();
+        assert!(tui.draw(200).iter().any(|line| line.contains(&hidden)));
+    }
+}
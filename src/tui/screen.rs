@@ -0,0 +1,347 @@
+use super::{Region, Target, Tui};
+use crate::game::card_catalog::CardCatalog;
+use crate::game::color::Color;
+use crate::game::game_command::GameCommand;
+use crate::game::gem::GEMS;
+use crate::game::level::Level;
+use crate::game::locale::{message, text};
+use crate::game::observation::{Observation, Reserved};
+use crate::game::renderer::{pad, width};
+use crate::game::turn::Phase;
+
+// 行の頭の山札の欄の幅
+const PREFIX: usize = 11;
+// 場のカードの左の欄 (選んでいるカードに ▶ をつける)
+const GUTTER: usize = 2;
+const LEFT_WIDTH: usize = 94;
+// 右の列を横に並べるのに必要な幅
+const RIGHT_WIDTH: usize = 60;
+const MOVES_SHOWN: usize = 10;
+const LOG_SHOWN: usize = 8;
+const ROWS: [(u8, Level); 3] = [(0, Level::Three), (1, Level::Two), (2, Level::One)];
+
+// 行と、行の中でクリックできる場所
+struct Column {
+    lines: Vec<String>,
+    regions: Vec<Region>,
+}
+
+impl Column {
+    fn new() -> Column {
+        Column {
+            lines: vec![],
+            regions: vec![],
+        }
+    }
+
+    fn push(&mut self, line: String) {
+        self.lines.push(line);
+    }
+
+    // 区切りをつなげて 1行にし、target のある区切りをクリックできる場所にする
+    fn push_segments(&mut self, segments: Vec<(String, Option<Target>)>) {
+        let row = self.lines.len() as u16;
+        let mut line = String::new();
+        for (segment, target) in segments {
+            if let Some(target) = target {
+                self.regions.push(Region {
+                    row,
+                    col: width(&line) as u16,
+                    width: width(&segment) as u16,
+                    target,
+                });
+            }
+            line.push_str(&segment);
+        }
+        self.lines.push(line);
+    }
+}
+
+impl Tui {
+    // 画面全体の行を作り、クリックできる場所を覚える
+    // 端末の幅が足りなければ、プレイヤー、手の一覧、ログを場の下に並べる
+    pub fn draw(&mut self, terminal_width: u16) -> Vec<String> {
+        if self.handoff {
+            self.regions.clear();
+            return self.draw_handoff();
+        }
+        self.update_viewer();
+        let observation = self.game.observation(self.viewer);
+        let moves = self.moves();
+        let left = self.draw_board(&observation, &moves);
+        let right = self.draw_side(&observation, &moves);
+
+        let mut lines = vec![];
+        self.regions = left.regions;
+        if terminal_width as usize >= LEFT_WIDTH + RIGHT_WIDTH {
+            for row in 0..left.lines.len().max(right.lines.len()) {
+                let l = left.lines.get(row).map_or("", String::as_str);
+                let r = right.lines.get(row).map_or("", String::as_str);
+                lines.push(format!("{}{}", pad(l, LEFT_WIDTH), r));
+            }
+            self.regions
+                .extend(right.regions.into_iter().map(|region| Region {
+                    col: region.col + LEFT_WIDTH as u16,
+                    ..region
+                }));
+        } else {
+            let offset = left.lines.len() as u16 + 1;
+            lines.extend(left.lines);
+            lines.push(String::new());
+            lines.extend(right.lines);
+            self.regions
+                .extend(right.regions.into_iter().map(|region| Region {
+                    row: region.row + offset,
+                    ..region
+                }));
+        }
+        lines
+    }
+
+    // 手番、貴族タイル、場のカード、トークン、手札、操作の説明
+    fn draw_board(&self, observation: &Observation, moves: &[GameCommand]) -> Column {
+        let renderer = &self.renderer;
+        let phase = self.game.pending().map(|(_, phase)| phase.clone());
+        let mut column = Column::new();
+        column.push(self.header());
+
+        let candidates = match &phase {
+            Some(Phase::Noble(ids)) => ids.clone(),
+            _ => vec![],
+        };
+        let mut segments = vec![(format!("{}: ", text("render.nobles")), None)];
+        for noble_tile in observation.peek_noble_tile().iter() {
+            let id = noble_tile.get_id();
+            let mut segment = renderer.noble_tile(noble_tile);
+            if let Some(index) = candidates.iter().position(|c| *c == id) {
+                segment = renderer.reverse(&format!("[{}] {}", index + 1, segment));
+            }
+            segments.push((segment, Some(Target::Noble(id))));
+            segments.push(("  ".to_string(), None));
+        }
+        column.push_segments(segments);
+        column.push(String::new());
+
+        let (users, _) = observation.to_game();
+        let me = &users[self.viewer];
+        for (x, level) in ROWS.iter() {
+            let deck = message(
+                "tui.deck",
+                &[
+                    &(*level as u8 + 1),
+                    &observation.get_number_of_stack_cards(*level),
+                ],
+            );
+            let boxes: Vec<Vec<String>> = (0..4)
+                .map(|y| {
+                    let card = observation.peek_card(*x, y);
+                    let mut lines = renderer.card_box(card);
+                    if card.is_some_and(|card| card.is_available(me)) {
+                        let last = lines.len() - 1;
+                        lines[0] = renderer.reverse(&lines[0]);
+                        lines[last] = renderer.reverse(&lines[last]);
+                    }
+                    lines
+                })
+                .collect();
+            for line in 0..boxes[0].len() {
+                let prefix = if line == 1 { deck.as_str() } else { "" };
+                let mut segments = vec![(pad(prefix, PREFIX), Some(Target::Deck(*level)))];
+                for (y, card_box) in boxes.iter().enumerate() {
+                    let target = Target::Card(*x, y as u8);
+                    let gutter = if self.selected == Some(target) && line == 1 {
+                        "▶ "
+                    } else {
+                        "  "
+                    };
+                    segments.push((pad(gutter, GUTTER), None));
+                    segments.push((card_box[line].clone(), Some(target)));
+                }
+                column.push_segments(segments);
+            }
+        }
+        column.push(String::new());
+
+        let player = &observation.players()[self.viewer];
+        match &phase {
+            Some(Phase::Discard(_)) => {
+                let mut segments = vec![(format!("{}: ", text("tui.discard")), None)];
+                let colors = GEMS.iter().chain([Color::Gold].iter());
+                for (index, color) in colors.enumerate() {
+                    let n = player.token_stack.len(*color);
+                    let segment = format!("[{}]{}", index + 1, renderer.pip(*color, n));
+                    let segment = if n > 0 {
+                        renderer.reverse(&segment)
+                    } else {
+                        renderer.dim(&segment)
+                    };
+                    segments.push((segment, Some(Target::Token(*color))));
+                    segments.push((" ".to_string(), None));
+                }
+                column.push_segments(segments);
+            }
+            _ => {
+                let mut segments = vec![(format!("{}: ", text("render.supply")), None)];
+                for (index, color) in GEMS.iter().enumerate() {
+                    let n = observation.get_number_of_tokens(*color);
+                    let chosen = self.tokens.iter().filter(|c| *c == color).count();
+                    let mut segment = format!("[{}]{}", index + 1, renderer.pip(*color, n));
+                    if chosen > 0 {
+                        segment = renderer.reverse(&format!("{}+{}", segment, chosen));
+                    } else if !self.can_add(*color, moves) {
+                        segment = renderer.dim(&segment);
+                    }
+                    segments.push((segment, Some(Target::Token(*color))));
+                    segments.push((" ".to_string(), None));
+                }
+                let gold = observation.get_number_of_tokens(Color::Gold);
+                segments.push((renderer.pip(Color::Gold, gold), None));
+                column.push_segments(segments);
+            }
+        }
+
+        let catalog = CardCatalog::global();
+        let mut segments = vec![(format!("{}: ", text("render.hand")), None)];
+        for (order, reserved) in player.hand.iter().enumerate() {
+            if let Reserved::Card(id) = reserved {
+                let card = catalog.card(*id);
+                let target = Target::Reserved(order as u8);
+                let mut segment = renderer.card(card);
+                if card.is_available(me) {
+                    segment = renderer.reverse(&segment);
+                }
+                if self.selected == Some(target) {
+                    segment = format!("▶ {}", segment);
+                }
+                segments.push((segment, Some(target)));
+                segments.push(("  ".to_string(), None));
+            }
+        }
+        column.push_segments(segments);
+        column.push(String::new());
+
+        column.push(self.status.clone());
+        let help = match (&phase, self.is_human_turn()) {
+            (None, _) => "tui.help_over",
+            (_, false) => "tui.help_waiting",
+            (Some(Phase::Discard(_)), _) => "tui.help_discard",
+            (Some(Phase::Noble(_)), _) => "tui.help_noble",
+            _ => "tui.help_main",
+        };
+        column.push(renderer.dim(text(help)));
+        column
+    }
+
+    // プレイヤー、手の一覧、最近の出来事
+    fn draw_side(&self, observation: &Observation, moves: &[GameCommand]) -> Column {
+        let pending = self.game.pending().map(|(seat, _)| seat);
+        let mut column = Column::new();
+        for (seat, player) in observation.players().iter().enumerate() {
+            let mut lines = self.renderer.player(seat, player, pending == Some(seat));
+            if let Some(agent) = &self.agents[seat] {
+                lines[0] = format!("{} ({})", lines[0], agent.name());
+            }
+            for line in lines {
+                column.push(line);
+            }
+            column.push(String::new());
+        }
+
+        column.push(self.renderer.bold(text("tui.moves")));
+        let start = self
+            .cursor
+            .saturating_sub(MOVES_SHOWN / 2)
+            .min(moves.len().saturating_sub(MOVES_SHOWN));
+        for (index, command) in moves.iter().enumerate().skip(start).take(MOVES_SHOWN) {
+            let line = format!("{:>2}. {}", index + 1, command);
+            let line = if index == self.cursor {
+                self.renderer.reverse(&format!("▶ {}", line))
+            } else {
+                format!("  {}", line)
+            };
+            column.push_segments(vec![(line, Some(Target::Move(index)))]);
+        }
+        column.push(String::new());
+
+        column.push(self.renderer.bold(text("tui.log")));
+        let log = self.log.borrow();
+        for line in log.iter().skip(log.len().saturating_sub(LOG_SHOWN)) {
+            column.push(line.clone());
+        }
+        column
+    }
+
+    // 交代を待つ画面 (誰に見えてもよい手番とログだけ)
+    fn draw_handoff(&self) -> Vec<String> {
+        let mut lines = vec![self.header(), String::new()];
+        let line = match self.game.pending() {
+            Some((seat, _)) if self.agents[seat].is_none() => {
+                self.renderer.bold(&message("tui.handoff", &[&seat]))
+            }
+            _ => text("tui.handoff_waiting").to_string(),
+        };
+        lines.push(line);
+        lines.push(String::new());
+
+        lines.push(self.renderer.bold(text("tui.log")));
+        let log = self.log.borrow();
+        lines.extend(
+            log.iter()
+                .skip(log.len().saturating_sub(LOG_SHOWN))
+                .cloned(),
+        );
+        lines.push(String::new());
+        lines.push(self.renderer.dim(text("tui.help_handoff")));
+        lines
+    }
+
+    fn header(&self) -> String {
+        let line = match self.game.pending() {
+            Some((seat, phase)) => {
+                let what = match &self.agents[seat] {
+                    Some(agent) => message("tui.thinking", &[&agent.name()]),
+                    None => phase.to_string(),
+                };
+                message("tui.header", &[&self.game.get_turn(), &seat, &what])
+            }
+            None => text("turn.over").to_string(),
+        };
+        self.renderer.bold(&line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::renderer::{width, Renderer};
+    use crate::tui::{Target, Tui};
+
+    #[test]
+    fn test_draw() {
+        let mut tui = Tui::new(vec![None, None]).renderer(Renderer::new().color(true));
+        let lines = tui.draw(200);
+        assert!(lines.iter().all(|line| width(line) < 200));
+        for x in 0..3 {
+            for y in 0..4 {
+                assert_eq!(
+                    tui.regions
+                        .iter()
+                        .filter(|region| region.target == Target::Card(x, y))
+                        .count(),
+                    4
+                );
+            }
+        }
+        assert!(tui
+            .regions
+            .iter()
+            .any(|region| region.target == Target::Move(0) && region.col > 90));
+
+        // 幅が足りなければ下に並べる
+        let narrow = tui.draw(100);
+        assert!(narrow.len() > lines.len());
+        assert!(tui
+            .regions
+            .iter()
+            .any(|region| region.target == Target::Move(0) && region.col == 0));
+    }
+}
@@ -0,0 +1,118 @@
+use super::{Input, Tui};
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use crossterm::style::Print;
+use crossterm::terminal::{
+    self, Clear, ClearType, DisableLineWrap, EnableLineWrap, EnterAlternateScreen,
+    LeaveAlternateScreen,
+};
+use crossterm::{execute, queue};
+use std::io::{self, Write};
+
+impl Tui {
+    // 端末を全画面にして、終局後にキーが押されるか q で抜けるまで対局する
+    pub fn run(mut self) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(
+            stdout,
+            EnterAlternateScreen,
+            EnableMouseCapture,
+            DisableLineWrap,
+            Hide
+        )?;
+        let result = self.event_loop(&mut stdout);
+        execute!(
+            stdout,
+            Show,
+            EnableLineWrap,
+            DisableMouseCapture,
+            LeaveAlternateScreen
+        )?;
+        terminal::disable_raw_mode()?;
+        result
+    }
+
+    fn event_loop<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        while !self.quit {
+            self.show(out)?;
+            if self.is_over() {
+                if Tui::read_input()?.is_some() {
+                    break;
+                }
+            } else if self.is_human_turn() {
+                if let Some(input) = Tui::read_input()? {
+                    self.handle(input);
+                }
+            } else {
+                // エージェントの手を見られるように待つ (その間も q で抜けられる)
+                if event::poll(self.delay)? {
+                    if let Some(Input::Quit) = Tui::read_input()? {
+                        self.quit = true;
+                    }
+                    continue;
+                }
+                self.play_agent();
+            }
+        }
+        Ok(())
+    }
+
+    fn show<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        let (width, _) = terminal::size()?;
+        let lines = self.draw(width);
+        if self.handoff {
+            // 前の人の局面をスクロールで見られないように消しておく
+            queue!(out, Clear(ClearType::All), Clear(ClearType::Purge))?;
+        }
+        for (row, line) in lines.iter().enumerate() {
+            queue!(
+                out,
+                MoveTo(0, row as u16),
+                Print(line),
+                Clear(ClearType::UntilNewLine)
+            )?;
+        }
+        queue!(
+            out,
+            MoveTo(0, lines.len() as u16),
+            Clear(ClearType::FromCursorDown)
+        )?;
+        out.flush()
+    }
+
+    // 画面の描き直しだけでよいイベントは None
+    fn read_input() -> io::Result<Option<Input>> {
+        let input = match event::read()? {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) => match code {
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                    Some(Input::Quit)
+                }
+                KeyCode::Char('q') => Some(Input::Quit),
+                KeyCode::Up | KeyCode::Char('k') => Some(Input::Up),
+                KeyCode::Down | KeyCode::Char('j') => Some(Input::Down),
+                KeyCode::Enter | KeyCode::Char(' ') => Some(Input::Enter),
+                KeyCode::Esc | KeyCode::Backspace => Some(Input::Cancel),
+                KeyCode::Char(c) => Some(Input::Char(c)),
+                _ => None,
+            },
+            Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column,
+                row,
+                ..
+            }) => Some(Input::Click { col: column, row }),
+            _ => None,
+        };
+        Ok(input)
+    }
+}
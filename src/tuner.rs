@@ -0,0 +1,165 @@
+use crate::game::action_reward_table::Weights;
+use crate::game::agent::{Agent, HeuristicAgent};
+use crate::tournament::Tournament;
+
+use rand::rngs::StdRng;
+use rand::{FromEntropy, Rng, SeedableRng};
+
+const DEFAULT_NUMBER_OF_GENERATIONS: u32 = 20;
+const DEFAULT_NUMBER_OF_GAMES: u32 = 20;
+const DEFAULT_STEP: f32 = 0.2;
+// 1/5 成功則: 成功率がちょうど 1/5 のとき step が変わらないよう、
+// 失敗したときは 1.5^(-1/4) 倍にする (1.5 * 0.904^4 ≒ 1)
+const SUCCESS_FACTOR: f32 = 1.5;
+const FAILURE_FACTOR: f32 = 0.904;
+
+// (1+1)-ES による局所探索
+// 現在の重みを変異させた候補と対局させ、勝ち越したら候補を採用する
+pub struct Tuner {
+    weights: Weights,
+    generations: u32,
+    games: u32,
+    step: f32,
+    rng: StdRng,
+}
+
+impl Tuner {
+    pub fn new(weights: Weights) -> Tuner {
+        Tuner {
+            weights,
+            generations: DEFAULT_NUMBER_OF_GENERATIONS,
+            games: DEFAULT_NUMBER_OF_GAMES,
+            step: DEFAULT_STEP,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    pub fn generations(mut self, generations: u32) -> Tuner {
+        self.generations = generations;
+        self
+    }
+
+    pub fn games(mut self, games: u32) -> Tuner {
+        self.games = games;
+        self
+    }
+
+    // 変異の乱数の種 (同じ種なら同じ候補を作る)
+    pub fn seed(mut self, seed: u64) -> Tuner {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    // 候補が採用されるたびに (世代, 重み, 勝率) を on_improve に渡す
+    pub fn run<F>(mut self, mut on_improve: F) -> Weights
+    where
+        F: FnMut(u32, &Weights, f64),
+    {
+        for generation in 1..=self.generations {
+            let candidate = self.mutate();
+            let score = self.evaluate(&candidate);
+            if self.select(candidate, score) {
+                on_improve(generation, &self.weights, score);
+            }
+        }
+        self.weights
+    }
+
+    // 勝ち越した候補を採用して true を返す
+    // 勝ち越した候補が 1/5 より多ければ変異を大きく、少なければ小さくする
+    fn select(&mut self, candidate: Weights, score: f64) -> bool {
+        if score > 0.5 {
+            self.weights = candidate;
+            self.step *= SUCCESS_FACTOR;
+            true
+        } else {
+            self.step *= FAILURE_FACTOR;
+            false
+        }
+    }
+
+    fn mutate(&mut self) -> Weights {
+        let mut candidate = self.weights.clone();
+        for value in candidate.values_mut().iter_mut() {
+            let noise = self.rng.gen_range(-1.0, 1.0) * self.step * value.abs().max(0.1);
+            **value = (**value + noise).max(0.0);
+        }
+        candidate
+    }
+
+    // 候補から見た勝ち点の割合 (引き分けは 0.5)
+    fn evaluate(&self, candidate: &Weights) -> f64 {
+        let candidate = candidate.clone();
+        let incumbent = self.weights.clone();
+        let report = Tournament::new(self.games)
+            .add("candidate", move || {
+                Box::new(HeuristicAgent::with_weights(candidate.clone())) as Box<dyn Agent>
+            })
+            .add("incumbent", move || {
+                Box::new(HeuristicAgent::with_weights(incumbent.clone())) as Box<dyn Agent>
+            })
+            .run();
+
+        let pairing = &report.pairings[0];
+        let games = pairing.wins + pairing.losses + pairing.draws;
+        if games == 0 {
+            return 0.0;
+        }
+        (f64::from(pairing.wins) + 0.5 * f64::from(pairing.draws)) / f64::from(games)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Tuner, DEFAULT_STEP, FAILURE_FACTOR, SUCCESS_FACTOR};
+    use crate::game::action_reward_table::Weights;
+
+    #[test]
+    fn test_mutate() {
+        let mut tuner = Tuner::new(Weights::default());
+        let mut candidate = tuner.mutate();
+        assert_ne!(candidate, Weights::default());
+        for value in candidate.values_mut().iter() {
+            assert!(**value >= 0.0);
+        }
+    }
+
+    // 5回に 1回成功すれば step はほぼ元に戻る
+    #[test]
+    fn test_factors() {
+        let step = SUCCESS_FACTOR * FAILURE_FACTOR.powi(4);
+        assert!((step - 1.0).abs() < 0.01);
+    }
+
+    // 採用したら SUCCESS_FACTOR 倍、しなければ FAILURE_FACTOR 倍
+    #[test]
+    fn test_select() {
+        let mut tuner = Tuner::new(Weights::default()).seed(1);
+        let candidate = tuner.mutate();
+
+        assert!(!tuner.select(candidate.clone(), 0.5));
+        assert_eq!(tuner.weights, Weights::default());
+        assert_eq!(tuner.step, DEFAULT_STEP * FAILURE_FACTOR);
+
+        assert!(tuner.select(candidate.clone(), 0.75));
+        assert_eq!(tuner.weights, candidate);
+        assert_eq!(tuner.step, DEFAULT_STEP * FAILURE_FACTOR * SUCCESS_FACTOR);
+
+        // 同じ種なら同じ候補になる
+        let mut other = Tuner::new(Weights::default()).seed(1);
+        assert_eq!(other.mutate(), candidate);
+    }
+
+    #[test]
+    fn test_run() {
+        let mut improved = 0;
+        Tuner::new(Weights::default())
+            .generations(1)
+            .games(2)
+            .run(|_, _, score| {
+                assert!(score > 0.5);
+                improved += 1;
+            });
+        assert!(improved <= 1);
+    }
+}